@@ -1,5 +1,6 @@
 export { ChainSignatureServer } from './src/server/ChainSignatureServer';
 export { CryptoUtils } from './src/modules/CryptoUtils';
+export { AddressUtils } from './src/modules/AddressUtils';
 export { EthereumTransactionProcessor } from './src/modules/ethereum/EthereumTransactionProcessor';
 export { EthereumMonitor } from './src/modules/ethereum/EthereumMonitor';
 export { BitcoinTransactionProcessor } from './src/modules/bitcoin/BitcoinTransactionProcessor';
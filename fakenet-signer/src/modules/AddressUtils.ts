@@ -0,0 +1,64 @@
+import * as bitcoin from 'bitcoinjs-lib';
+import * as ecc from 'tiny-secp256k1';
+import { bech32 } from 'bech32';
+import { ethers } from 'ethers';
+
+bitcoin.initEccLib(ecc);
+
+/**
+ * Destination chain addresses of a derived secp256k1 key, matching
+ * `chain_signatures::address`. Keys are 0x-hex secret keys, e.g. from
+ * `CryptoUtils.deriveSigningKeyFromEpsilon`.
+ */
+export class AddressUtils {
+  /** Uncompressed public key as `x || y` hex, without the `04` prefix. */
+  static publicKey(privateKeyHex: string): string {
+    return new ethers.SigningKey(privateKeyHex).publicKey.slice(4);
+  }
+
+  /** EIP-55 checksummed EVM address. */
+  static evmAddress(privateKeyHex: string): string {
+    return ethers.computeAddress(privateKeyHex);
+  }
+
+  /** Bitcoin P2WPKH address for the network `hrp` (`bc`, `tb`, `bcrt`). */
+  static p2wpkhAddress(privateKeyHex: string, hrp: string): string {
+    const { address } = bitcoin.payments.p2wpkh({
+      pubkey: this.compressedPublicKey(privateKeyHex),
+      network: this.bitcoinNetwork(hrp),
+    });
+    if (!address) {
+      throw new Error('Failed to derive P2WPKH address');
+    }
+    return address;
+  }
+
+  /** Bitcoin BIP-86 P2TR address for the network `hrp`. */
+  static p2trAddress(privateKeyHex: string, hrp: string): string {
+    const { address } = bitcoin.payments.p2tr({
+      internalPubkey: this.compressedPublicKey(privateKeyHex).subarray(1),
+      network: this.bitcoinNetwork(hrp),
+    });
+    if (!address) {
+      throw new Error('Failed to derive P2TR address');
+    }
+    return address;
+  }
+
+  /** Cosmos SDK account address for the account `prefix`. */
+  static cosmosAddress(privateKeyHex: string, prefix: string): string {
+    const hash = bitcoin.crypto.hash160(
+      this.compressedPublicKey(privateKeyHex)
+    );
+    return bech32.encode(prefix, bech32.toWords(hash));
+  }
+
+  private static compressedPublicKey(privateKeyHex: string): Buffer {
+    const { compressedPublicKey } = new ethers.SigningKey(privateKeyHex);
+    return Buffer.from(compressedPublicKey.slice(2), 'hex');
+  }
+
+  private static bitcoinNetwork(hrp: string): bitcoin.Network {
+    return { ...bitcoin.networks.bitcoin, bech32: hrp };
+  }
+}
@@ -1,11 +1,19 @@
 import { CONFIG } from '../config/Config';
 import { ethers } from 'ethers';
-import { SignatureResponse } from '../types';
+import {
+  EXECUTION_STATUSES,
+  type ExecutionStatus,
+  SignatureResponse,
+} from '../types';
 import * as ecc from 'tiny-secp256k1';
 
 export class CryptoUtils {
-  static deriveEpsilon(requester: string, path: string): bigint {
-    const derivationPath = `${CONFIG.EPSILON_DERIVATION_PREFIX}:${CONFIG.SOLANA_CAIP2_ID}:${requester}:${path}`;
+  static deriveEpsilon(
+    requester: string,
+    path: string,
+    chainId: string = CONFIG.SOLANA_CAIP2_ID
+  ): bigint {
+    const derivationPath = `${CONFIG.EPSILON_DERIVATION_PREFIX}:${chainId}:${requester}:${path}`;
     const hash = ethers.keccak256(ethers.toUtf8Bytes(derivationPath));
     return BigInt(hash);
   }
@@ -23,9 +31,10 @@ export class CryptoUtils {
   static async deriveSigningKey(
     path: string,
     predecessor: string,
-    basePrivateKey: string
+    basePrivateKey: string,
+    chainId?: string
   ): Promise<string> {
-    const epsilon = this.deriveEpsilon(predecessor, path);
+    const epsilon = this.deriveEpsilon(predecessor, path, chainId);
     const privateKeyBigInt = BigInt(basePrivateKey);
     const derivedPrivateKey =
       (privateKeyBigInt + epsilon) % BigInt(CONFIG.SECP256K1_N);
@@ -111,6 +120,58 @@ export class CryptoUtils {
     return this.signMessage(messageHash, derivedPrivateKey);
   }
 
+  /**
+   * Message hash of a Solana `respond_bidirectional` response:
+   * `keccak256(borsh(request_id, status, serialized_output, revert_data))`,
+   * matching `chain_signatures::response::bidirectional_response_hash`.
+   */
+  static bidirectionalResponseHash(
+    requestId: Uint8Array,
+    status: ExecutionStatus,
+    serializedOutput: Uint8Array,
+    revertData: Uint8Array | null
+  ): string {
+    const vec = (bytes: Uint8Array) => {
+      const length = Buffer.alloc(4);
+      length.writeUInt32LE(bytes.length);
+      return Buffer.concat([length, bytes]);
+    };
+    const message = Buffer.concat([
+      requestId,
+      Buffer.from([EXECUTION_STATUSES.indexOf(status)]),
+      vec(serializedOutput),
+      revertData
+        ? Buffer.concat([Buffer.from([1]), vec(revertData)])
+        : Buffer.from([0]),
+    ]);
+    return ethers.keccak256(message);
+  }
+
+  static async signExecutionResponse(
+    requestId: Uint8Array,
+    status: ExecutionStatus,
+    serializedOutput: Uint8Array,
+    revertData: Uint8Array | null,
+    basePrivateKey: string,
+    sender: string
+  ): Promise<SignatureResponse> {
+    const derivedPrivateKey = await this.deriveSigningKey(
+      CONFIG.SOLANA_RESPOND_BIDIRECTIONAL_PATH,
+      sender,
+      basePrivateKey
+    );
+
+    return this.signMessage(
+      this.bidirectionalResponseHash(
+        requestId,
+        status,
+        serializedOutput,
+        revertData
+      ),
+      derivedPrivateKey
+    );
+  }
+
   static async signDigestDirectly(
     digest: Uint8Array,
     privateKeyHex: string
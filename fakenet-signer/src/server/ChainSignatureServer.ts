@@ -14,6 +14,7 @@ import type {
   ServerConfig,
   CpiEventData,
   SignatureResponse,
+  ExecutionStatus,
 } from '../types';
 import { isSignBidirectionalEvent, isSignatureRequestedEvent } from '../types';
 import { serverConfigSchema } from '../types';
@@ -217,6 +218,7 @@ export class ChainSignatureServer {
       console.log(`Program: ${program.programId.toString()}`);
 
       await this.ensureInitialized();
+      await this.ensureRootPublicKey();
     }
 
     if (this.substrateMonitor) {
@@ -596,7 +598,8 @@ export class ChainSignatureServer {
             const done = await this.executeWithRecovery(
               txHash,
               txInfo,
-              () => this.handleFailedTransaction(txHash, txInfo),
+              () =>
+                this.handleFailedTransaction(txHash, txInfo, result.reason),
               'handleFailedTransaction',
               false // circular — can't send error response for a failed error response
             );
@@ -608,7 +611,7 @@ export class ChainSignatureServer {
             console.error(
               `Fatal error for transaction ${txHash}: ${result.reason}`
             );
-            await this.sendErrorResponse(txHash, txInfo);
+            await this.sendErrorResponse(txHash, txInfo, result.reason);
             pendingTransactions.delete(txHash);
             break;
         }
@@ -702,19 +705,35 @@ export class ChainSignatureServer {
       }
 
       case 'solana': {
-        const { serializedOutput, signature } =
-          await this.serializeAndSignBorshResponse(
-            requestIdBytes,
-            txInfo,
-            result
-          );
+        this.log(`🔗 serializeBorshOutput...`);
+        const serializedOutput = this.serializeBorshOutput(
+          result.output,
+          txInfo.respondSerializationSchema
+        );
+        this.log(
+          `✓ serializeBorshOutput done (${serializedOutput.length} bytes)`
+        );
+
+        this.log(`🔗 CryptoUtils: signExecutionResponse...`);
+        const signature = await CryptoUtils.signExecutionResponse(
+          requestIdBytes,
+          'success',
+          serializedOutput,
+          null,
+          this.config.mpcRootKey,
+          txInfo.sender
+        );
+        this.log(`✓ CryptoUtils: signExecutionResponse done`);
+
         const { wallet, program } = this.requireSolana();
         this.log(`🔗 Solana RPC: respondBidirectional() for ${txHash}...`);
         await this.withTimeout(
           program.methods
             .respondBidirectional(
               Array.from(requestIdBytes),
+              { success: {} },
               Buffer.from(serializedOutput),
+              null,
               signature
             )
             .accounts({
@@ -802,7 +821,7 @@ export class ChainSignatureServer {
     return data;
   }
 
-  /** Borsh-serialize the output and MPC-sign it for Solana/Substrate responses. */
+  /** Borsh-serialize the output and MPC-sign it for Substrate responses. */
   private async serializeAndSignBorshResponse(
     requestIdBytes: Buffer,
     txInfo: PendingTransaction,
@@ -953,7 +972,8 @@ export class ChainSignatureServer {
 
   private async handleFailedTransaction(
     txHash: string,
-    txInfo: PendingTransaction
+    txInfo: PendingTransaction,
+    reason: string
   ) {
     console.warn(`❌ Transaction failed: ${txHash}`);
 
@@ -990,22 +1010,22 @@ export class ChainSignatureServer {
       return;
     }
 
-    const errorSchema = { struct: { error: 'bool' } };
-    const borshData = borsh.serialize(errorSchema, { error: true });
-    const errorData = Buffer.concat([MAGIC_ERROR_PREFIX, borshData]);
-
-    const serializedOutput = new Uint8Array(errorData);
+    if (txInfo.source === 'polkadot' && this.substrateMonitor) {
+      const errorSchema = { struct: { error: 'bool' } };
+      const borshData = borsh.serialize(errorSchema, { error: true });
+      const serializedOutput = new Uint8Array(
+        Buffer.concat([MAGIC_ERROR_PREFIX, borshData])
+      );
 
-    this.log(`🔗 CryptoUtils: signBidirectionalResponse (error response)...`);
-    const signature = await CryptoUtils.signBidirectionalResponse(
-      requestIdBytes,
-      serializedOutput,
-      this.config.mpcRootKey,
-      txInfo.sender
-    );
-    this.log(`✓ CryptoUtils: signBidirectionalResponse done`);
+      this.log(`🔗 CryptoUtils: signBidirectionalResponse (error response)...`);
+      const signature = await CryptoUtils.signBidirectionalResponse(
+        requestIdBytes,
+        serializedOutput,
+        this.config.mpcRootKey,
+        txInfo.sender
+      );
+      this.log(`✓ CryptoUtils: signBidirectionalResponse done`);
 
-    if (txInfo.source === 'polkadot' && this.substrateMonitor) {
       await this.substrateMonitor.sendRespondBidirectional(
         requestIdBytes,
         serializedOutput,
@@ -1015,13 +1035,35 @@ export class ChainSignatureServer {
       return;
     }
 
+    // Solana: the failure is a signed execution status with empty output.
+    const status: ExecutionStatus =
+      reason === 'reverted'
+        ? 'reverted'
+        : reason === 'replaced' || reason === 'inputs_spent'
+          ? 'replaced'
+          : 'dropped';
+    const serializedOutput = new Uint8Array();
+
+    this.log(`🔗 CryptoUtils: signExecutionResponse (${status})...`);
+    const signature = await CryptoUtils.signExecutionResponse(
+      requestIdBytes,
+      status,
+      serializedOutput,
+      null,
+      this.config.mpcRootKey,
+      txInfo.sender
+    );
+    this.log(`✓ CryptoUtils: signExecutionResponse done`);
+
     const { wallet, program } = this.requireSolana();
     this.log(`🔗 Solana RPC: respondBidirectional() error for ${txHash}...`);
     await this.withTimeout(
       program.methods
         .respondBidirectional(
           Array.from(requestIdBytes),
+          { [status]: {} },
           Buffer.from(serializedOutput),
+          null,
           signature
         )
         .accounts({
@@ -1307,10 +1349,11 @@ export class ChainSignatureServer {
 
   private async sendErrorResponse(
     txHash: string,
-    txInfo: PendingTransaction
+    txInfo: PendingTransaction,
+    reason = 'dropped'
   ): Promise<void> {
     try {
-      await this.handleFailedTransaction(txHash, txInfo);
+      await this.handleFailedTransaction(txHash, txInfo, reason);
     } catch (error) {
       console.error(
         `⛔ Could not send error response for ${txHash}: ${
@@ -1390,7 +1433,8 @@ export class ChainSignatureServer {
     const derivedPrivateKey = await CryptoUtils.deriveSigningKey(
       event.path,
       event.sender.toString(),
-      this.config.mpcRootKey
+      this.config.mpcRootKey,
+      event.chainId
     );
     this.log(`✓ CryptoUtils: deriveSigningKey done`);
 
@@ -1453,12 +1497,91 @@ export class ChainSignatureServer {
         .accounts({
           responder: wallet.publicKey,
         })
+        .remainingAccounts(
+          this.respondAccounts(
+            program.programId,
+            [requestIdBytes],
+            event.feePayer ?? event.sender
+          )
+        )
         .rpc(),
       'respond()'
     );
     console.log(`✅ Signature sent! tx=${tx}`);
   }
 
+  /**
+   * Publishes the MPC root public key so `respond` can verify signatures for
+   * tracked `sign` requests on-chain. Skipped when the key is already set.
+   */
+  private async ensureRootPublicKey() {
+    const { connection, wallet, program } = this.requireSolana();
+    const [rootPublicKeyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('root-public-key')],
+      program.programId
+    );
+    const publicKey = Array.from(
+      ethers.getBytes(new ethers.SigningKey(this.config.mpcRootKey).publicKey)
+    ).slice(1);
+
+    const accountInfo = await this.withTimeout(
+      connection.getAccountInfo(rootPublicKeyPda),
+      'getAccountInfo'
+    );
+    if (
+      accountInfo &&
+      Buffer.from(accountInfo.data.subarray(8, 72)).equals(
+        Buffer.from(publicKey)
+      )
+    ) {
+      return;
+    }
+
+    try {
+      this.log(`🔗 Solana RPC: program.setRootPublicKey()...`);
+      await this.withTimeout(
+        program.methods
+          .setRootPublicKey(publicKey)
+          .accounts({
+            admin: wallet.publicKey,
+          })
+          .rpc(),
+        'program.setRootPublicKey()'
+      );
+      this.log(`✓ Solana RPC: program.setRootPublicKey() done`);
+    } catch (error: unknown) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.log(
+        `⚠️ Could not set root public key (${errorMsg}), on-chain verification of sign responses will fail`
+      );
+    }
+  }
+
+  /**
+   * Remaining accounts of `respond`: for each request id, in order, its
+   * `SignatureRequest` PDA (seeds `["request", requestId]`), the
+   * `ResponseRecord` PDA (seeds `["response", requestId]`) that `respond`
+   * creates to reject a second response, and `rentPayer`, which receives the
+   * rent of the closed `SignatureRequest`.
+   */
+  private respondAccounts(
+    programId: anchor.web3.PublicKey,
+    requestIds: number[][],
+    rentPayer: anchor.web3.PublicKey
+  ): anchor.web3.AccountMeta[] {
+    return requestIds.flatMap((requestId) => [
+      ...['request', 'response'].map((seed) => ({
+        pubkey: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from(seed), Buffer.from(requestId)],
+          programId
+        )[0],
+        isSigner: false,
+        isWritable: true,
+      })),
+      { pubkey: rentPayer, isSigner: false, isWritable: true },
+    ]);
+  }
+
   private getBidirectionalContext(): BidirectionalHandlerContext {
     const { wallet, program } = this.requireSolana();
     return {
@@ -1474,6 +1597,14 @@ export class ChainSignatureServer {
             .accounts({
               responder: wallet.publicKey,
             })
+            .remainingAccounts(
+              // Bidirectional requests are not tracked, so no rent is refunded.
+              this.respondAccounts(
+                program.programId,
+                requestIdArrays,
+                wallet.publicKey
+              )
+            )
             .rpc(),
           label
         );
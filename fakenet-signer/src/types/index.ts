@@ -108,6 +108,14 @@ export interface SignBidirectionalEvent {
   params: string;
   outputDeserializationSchema: Buffer | Uint8Array;
   respondSerializationSchema: Buffer | Uint8Array;
+  /**
+   * Program of the top-level instruction when it is not the signet program;
+   * null for direct calls or without the instructions sysvar. Informational
+   * only.
+   */
+  topLevelProgram?: PublicKey | null;
+  /** CAIP-2 chain id of the source chain the signing key is derived with. */
+  chainId?: string;
 }
 
 export interface SignatureRequestedEvent {
@@ -121,6 +129,12 @@ export interface SignatureRequestedEvent {
   dest: string;
   params: string;
   feePayer: PublicKey | null;
+  /**
+   * Program of the top-level instruction when it is not the signet program;
+   * null for direct calls or without the instructions sysvar. Informational
+   * only.
+   */
+  topLevelProgram: PublicKey | null;
 }
 
 export interface PrevoutRef {
@@ -246,6 +260,19 @@ export type TransactionStatus =
   | { status: 'error'; reason: string }
   | { status: 'fatal_error'; reason: string };
 
+/**
+ * Variants of the program's `ExecutionStatus` enum, in declaration order (the
+ * index is the Borsh discriminant).
+ */
+export const EXECUTION_STATUSES = [
+  'success',
+  'reverted',
+  'dropped',
+  'replaced',
+] as const;
+
+export type ExecutionStatus = (typeof EXECUTION_STATUSES)[number];
+
 export interface SignatureResponse {
   bigR: { x: number[]; y: number[] };
   s: number[];
@@ -0,0 +1,169 @@
+/**
+ * Benchmark harness for program instructions
+ *
+ * This module provides reusable functions for:
+ * - Measuring the compute units an instruction consumes via simulation
+ * - Computing the wire size of the transaction carrying it
+ * - Writing a report and comparing it against a committed baseline
+ */
+import * as anchor from '@coral-xyz/anchor';
+import bs58 from 'bs58';
+import fs from 'fs';
+import path from 'path';
+import { fileURLToPath } from 'url';
+
+const __dirname = path.dirname(fileURLToPath(import.meta.url));
+
+export const REPORT_PATH = path.resolve(__dirname, 'report.json');
+export const BASELINE_PATH = path.resolve(__dirname, 'baseline.json');
+
+/** Maximum size of a serialized transaction. */
+export const PACKET_DATA_SIZE = 1232;
+
+export interface BenchmarkResult {
+  instruction: string;
+  /** Input size being measured, e.g. `path=64B` or `requests=20`. */
+  input: string;
+  /** `null` when the transaction exceeds `PACKET_DATA_SIZE` and cannot land. */
+  computeUnits: number | null;
+  transactionSize: number;
+}
+
+export interface Regression {
+  key: string;
+  metric: 'computeUnits' | 'transactionSize';
+  baseline: number;
+  current: number;
+}
+
+const compactU16Len = (value: number) =>
+  value < 0x80 ? 1 : value < 0x4000 ? 2 : 3;
+
+/**
+ * Size of the legacy transaction carrying `instructions`, computed from the
+ * compiled message since web3.js refuses to serialize oversized transactions.
+ */
+export const transactionSize = (
+  payer: anchor.web3.PublicKey,
+  instructions: anchor.web3.TransactionInstruction[]
+): number => {
+  const message = new anchor.web3.Transaction({
+    feePayer: payer,
+    recentBlockhash: anchor.web3.PublicKey.default.toBase58(),
+  })
+    .add(...instructions)
+    .compileMessage();
+  const signatures = message.header.numRequiredSignatures;
+
+  return (
+    compactU16Len(signatures) +
+    64 * signatures +
+    3 +
+    compactU16Len(message.accountKeys.length) +
+    32 * message.accountKeys.length +
+    32 +
+    compactU16Len(message.instructions.length) +
+    message.instructions.reduce((size, instruction) => {
+      const data = bs58.decode(instruction.data).length;
+      return (
+        size +
+        1 +
+        compactU16Len(instruction.accounts.length) +
+        instruction.accounts.length +
+        compactU16Len(data) +
+        data
+      );
+    }, 0)
+  );
+};
+
+/**
+ * Simulates `instruction` alone in a transaction paid by `payer` and returns
+ * its compute units and transaction size. Simulation failures throw with the
+ * program logs, since they mean the benchmark itself is broken.
+ */
+export const measure = async (
+  connection: anchor.web3.Connection,
+  payer: anchor.web3.PublicKey,
+  instruction: anchor.web3.TransactionInstruction
+): Promise<Pick<BenchmarkResult, 'computeUnits' | 'transactionSize'>> => {
+  const size = transactionSize(payer, [instruction]);
+  if (size > PACKET_DATA_SIZE) {
+    return { computeUnits: null, transactionSize: size };
+  }
+
+  const message = new anchor.web3.TransactionMessage({
+    payerKey: payer,
+    recentBlockhash: anchor.web3.PublicKey.default.toBase58(),
+    instructions: [instruction],
+  }).compileToLegacyMessage();
+  const { value } = await connection.simulateTransaction(
+    new anchor.web3.VersionedTransaction(message),
+    { sigVerify: false, replaceRecentBlockhash: true }
+  );
+  if (value.err || value.unitsConsumed === undefined) {
+    throw new Error(
+      `Simulation failed: ${JSON.stringify(value.err)}\n${(value.logs ?? []).join('\n')}`
+    );
+  }
+
+  return { computeUnits: value.unitsConsumed, transactionSize: size };
+};
+
+export const resultKey = ({ instruction, input }: BenchmarkResult) =>
+  `${instruction}/${input}`;
+
+export const writeReport = (results: BenchmarkResult[], file = REPORT_PATH) =>
+  fs.writeFileSync(file, `${JSON.stringify(results, null, 2)}\n`);
+
+export const readBaseline = (): BenchmarkResult[] | null =>
+  fs.existsSync(BASELINE_PATH)
+    ? (JSON.parse(fs.readFileSync(BASELINE_PATH, 'utf8')) as BenchmarkResult[])
+    : null;
+
+/**
+ * Metrics of `results` that grew by more than `threshold` (a fraction) over
+ * the matching entry of `baseline`. A case that no longer fits a transaction
+ * while it did in the baseline always regresses.
+ */
+export const findRegressions = (
+  results: BenchmarkResult[],
+  baseline: BenchmarkResult[],
+  threshold: number
+): Regression[] => {
+  const previous = new Map(
+    baseline.map((result) => [resultKey(result), result] as const)
+  );
+
+  return results.flatMap((result) => {
+    const base = previous.get(resultKey(result));
+    if (!base) return [];
+
+    return (['computeUnits', 'transactionSize'] as const).flatMap((metric) => {
+      const before = base[metric];
+      const after = result[metric];
+      if (before === null) return [];
+      if (after === null || after > before * (1 + threshold)) {
+        return [
+          {
+            key: resultKey(result),
+            metric,
+            baseline: before,
+            current: after ?? Infinity,
+          },
+        ];
+      }
+      return [];
+    });
+  });
+};
+
+export const formatTable = (results: BenchmarkResult[]) =>
+  [
+    '| Instruction | Input | Compute units | Transaction size |',
+    '| ----------- | ----- | ------------- | ---------------- |',
+    ...results.map(
+      ({ instruction, input, computeUnits, transactionSize: size }) =>
+        `| ${instruction} | ${input} | ${computeUnits ?? 'exceeds packet'} | ${size} |`
+    ),
+  ].join('\n');
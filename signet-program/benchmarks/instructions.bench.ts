@@ -0,0 +1,397 @@
+import { assert } from 'chai';
+import * as anchor from '@coral-xyz/anchor';
+import type { Program } from '@coral-xyz/anchor';
+import { SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
+import { randomBytes } from 'crypto';
+import { ethers } from 'ethers';
+import { CryptoUtils } from 'fakenet-signer';
+import { contracts } from 'signet.js';
+import type { ChainSignatures } from '../target/types/chain_signatures';
+import { testEnvConfig } from '../test-utils/testEnvConfig.js';
+import type { SignArgs } from '../test-utils/signingUtils.js';
+import {
+  requestBufferPda,
+  respondAccounts,
+  responseRecordPda,
+  signatureRequestPda,
+} from '../test-utils/utils.js';
+import {
+  BASELINE_PATH,
+  REPORT_PATH,
+  findRegressions,
+  formatTable,
+  measure,
+  readBaseline,
+  writeReport,
+  type BenchmarkResult,
+} from './harness.js';
+
+// Runs through `anchor run bench` against a running validator without starting
+// the fakenet signer, so tracked requests stay pending until the benchmark
+// responds to them.
+describe('Instruction benchmarks', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.chainSignatures as Program<ChainSignatures>;
+  const requester = provider.wallet.publicKey;
+  const results: BenchmarkResult[] = [];
+
+  const [programStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('program-state')],
+    program.programId
+  );
+
+  // The fakenet signer initializes the program and publishes the root key
+  // when the test suite runs; on a fresh validator the benchmark does it.
+  before(async () => {
+    const programState =
+      await program.account.programState.fetchNullable(programStatePda);
+    if (!programState) {
+      await program.methods
+        .initialize(
+          new anchor.BN('100000'),
+          'solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp'
+        )
+        .accounts({ admin: requester })
+        .rpc();
+    }
+
+    const [rootPublicKeyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('root-public-key')],
+      program.programId
+    );
+    if (!(await provider.connection.getAccountInfo(rootPublicKeyPda))) {
+      const publicKey = Array.from(
+        ethers.getBytes(
+          new ethers.SigningKey(testEnvConfig.MPC_ROOT_KEY).publicKey
+        )
+      ).slice(1);
+      await program.methods
+        .setRootPublicKey(publicKey)
+        .accounts({ admin: requester })
+        .rpc();
+    }
+  });
+
+  const OUTPUT_SCHEMA = Buffer.from('[{"name":"ok","type":"bool"}]');
+  const RESPOND_SCHEMA = Buffer.from('{"struct":{"ok":"bool"}}');
+
+  const record = async (
+    instruction: string,
+    input: string,
+    ix: anchor.web3.TransactionInstruction
+  ) => {
+    results.push({
+      instruction,
+      input,
+      ...(await measure(provider.connection, requester, ix)),
+    });
+  };
+
+  const signArgs = (path: string): SignArgs => ({
+    payload: Array.from(randomBytes(32)),
+    keyVersion: 0,
+    path,
+    algo: 'secp256k1',
+    dest: 'ethereum',
+    params: '{}',
+  });
+
+  const requestIdOf = async (args: SignArgs) => {
+    const { chainId } =
+      await program.account.programState.fetch(programStatePda);
+    const requestId = contracts.solana.getRequestIdRespond({
+      address: requester.toString(),
+      payload: args.payload,
+      path: args.path,
+      keyVersion: args.keyVersion,
+      chainId,
+      algo: args.algo,
+      dest: args.dest,
+      params: args.params,
+    });
+    return Array.from(Buffer.from(requestId.slice(2), 'hex'));
+  };
+
+  const signIx = async (args: SignArgs) =>
+    program.methods
+      .sign(
+        args.payload,
+        args.keyVersion,
+        args.path,
+        args.algo,
+        args.dest,
+        args.params
+      )
+      .accountsPartial({
+        signatureRequest: signatureRequestPda(
+          program.programId,
+          await requestIdOf(args)
+        ),
+        responseRecord: responseRecordPda(
+          program.programId,
+          await requestIdOf(args)
+        ),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+  const bufferId = () => new anchor.BN(randomBytes(8));
+
+  const uploadRequestBuffer = async (
+    id: anchor.BN,
+    serializedTransaction: Buffer
+  ) => {
+    await program.methods
+      .initRequestBuffer(
+        id,
+        serializedTransaction.length,
+        OUTPUT_SCHEMA.length,
+        RESPOND_SCHEMA.length
+      )
+      .accounts({ owner: requester, payer: requester })
+      .rpc();
+
+    const payload = Buffer.concat([
+      serializedTransaction,
+      OUTPUT_SCHEMA,
+      RESPOND_SCHEMA,
+    ]);
+    for (let offset = 0; offset < payload.length; offset += 900) {
+      await program.methods
+        .writeRequestBuffer(id, offset, payload.subarray(offset, offset + 900))
+        .accountsPartial({
+          owner: requester,
+          requestBuffer: requestBufferPda(program.programId, requester, id),
+        })
+        .rpc();
+    }
+  };
+
+  it('sign', async () => {
+    for (const pathLen of [0, 64, 256]) {
+      await record(
+        'sign',
+        `path=${pathLen}B`,
+        await signIx(signArgs('p'.repeat(pathLen)))
+      );
+    }
+  });
+
+  it('sign_bidirectional', async () => {
+    for (const txLen of [64, 256, 512, 768]) {
+      const ix = await program.methods
+        .signBidirectional(
+          randomBytes(txLen),
+          'eip155:1',
+          0,
+          '',
+          'ecdsa',
+          'ethereum',
+          '',
+          program.programId,
+          OUTPUT_SCHEMA,
+          RESPOND_SCHEMA
+        )
+        .accountsPartial({ instructions: SYSVAR_INSTRUCTIONS_PUBKEY })
+        .instruction();
+      await record('sign_bidirectional', `tx=${txLen}B`, ix);
+    }
+  });
+
+  it('sign_bidirectional schema validation', async () => {
+    for (const fields of [1, 16, 48]) {
+      const respondSchema = Buffer.from(
+        JSON.stringify({
+          struct: Object.fromEntries(
+            Array.from({ length: fields }, (_, i) => [`f${i}`, 'u8'])
+          ),
+        })
+      );
+      const ix = await program.methods
+        .signBidirectional(
+          randomBytes(64),
+          'eip155:1',
+          0,
+          '',
+          'ecdsa',
+          'ethereum',
+          '',
+          program.programId,
+          OUTPUT_SCHEMA,
+          respondSchema
+        )
+        .accountsPartial({ instructions: SYSVAR_INSTRUCTIONS_PUBKEY })
+        .instruction();
+      await record(
+        'sign_bidirectional',
+        `schema=${respondSchema.length}B`,
+        ix
+      );
+    }
+  });
+
+  it('init_request_buffer', async () => {
+    for (const txLen of [1024, 8000]) {
+      const ix = await program.methods
+        .initRequestBuffer(
+          bufferId(),
+          txLen,
+          OUTPUT_SCHEMA.length,
+          RESPOND_SCHEMA.length
+        )
+        .accounts({ owner: requester, payer: requester })
+        .instruction();
+      await record('init_request_buffer', `tx=${txLen}B`, ix);
+    }
+  });
+
+  it('write_request_buffer', async () => {
+    const id = bufferId();
+    await uploadRequestBuffer(id, randomBytes(1024));
+
+    for (const chunkLen of [256, 512, 900]) {
+      const ix = await program.methods
+        .writeRequestBuffer(id, 0, randomBytes(chunkLen))
+        .accountsPartial({
+          owner: requester,
+          requestBuffer: requestBufferPda(program.programId, requester, id),
+        })
+        .instruction();
+      await record('write_request_buffer', `chunk=${chunkLen}B`, ix);
+    }
+  });
+
+  it('sign_bidirectional_from_buffer', async () => {
+    for (const txLen of [1024, 4096, 8000]) {
+      const id = bufferId();
+      await uploadRequestBuffer(id, randomBytes(txLen));
+
+      const ix = await program.methods
+        .signBidirectionalFromBuffer(
+          id,
+          'eip155:1',
+          0,
+          '',
+          'ecdsa',
+          'ethereum',
+          '',
+          program.programId
+        )
+        .accountsPartial({
+          requestBuffer: requestBufferPda(program.programId, requester, id),
+          rentPayer: requester,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .instruction();
+      await record('sign_bidirectional_from_buffer', `tx=${txLen}B`, ix);
+    }
+  });
+
+  it('respond', async () => {
+    const { chainId } =
+      await program.account.programState.fetch(programStatePda);
+    const requests = Array.from({ length: 20 }, (_, i) =>
+      signArgs(`bench-respond-${i}`)
+    );
+
+    for (let i = 0; i < requests.length; i += 4) {
+      const batch = await Promise.all(requests.slice(i, i + 4).map(signIx));
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(...batch)
+      );
+    }
+
+    const requestIds = await Promise.all(requests.map(requestIdOf));
+    const signatures = await Promise.all(
+      requests.map(async (args) =>
+        CryptoUtils.signMessage(
+          args.payload,
+          await CryptoUtils.deriveSigningKey(
+            args.path,
+            requester.toString(),
+            testEnvConfig.MPC_ROOT_KEY,
+            chainId
+          )
+        )
+      )
+    );
+
+    for (const count of [1, 2, 4, 8, 20]) {
+      const ids = requestIds.slice(0, count);
+      const ix = await program.methods
+        .respond(ids, signatures.slice(0, count))
+        .accounts({ responder: requester })
+        .remainingAccounts(respondAccounts(program.programId, ids, requester))
+        .instruction();
+      await record('respond', `requests=${count}`, ix);
+    }
+  });
+
+  it('respond_bidirectional', async () => {
+    for (const outputLen of [0, 256, 512]) {
+      const requestId = Array.from(randomBytes(32));
+      const output = randomBytes(outputLen);
+      const signature = await CryptoUtils.signExecutionResponse(
+        Uint8Array.from(requestId),
+        'success',
+        output,
+        null,
+        testEnvConfig.MPC_ROOT_KEY,
+        requester.toString()
+      );
+
+      const ix = await program.methods
+        .respondBidirectional(
+          requestId,
+          { success: {} },
+          output,
+          null,
+          signature
+        )
+        .accounts({ responder: requester })
+        .instruction();
+      await record('respond_bidirectional', `output=${outputLen}B`, ix);
+    }
+  });
+
+  it('get_signature_deposit', async () => {
+    await record(
+      'get_signature_deposit',
+      '-',
+      await program.methods.getSignatureDeposit().instruction()
+    );
+  });
+
+  it('Stays within the regression threshold of the baseline', () => {
+    writeReport(results);
+    console.warn(`${formatTable(results)}\n\nReport: ${REPORT_PATH}`);
+
+    if (process.env.BENCH_UPDATE_BASELINE) {
+      writeReport(results, BASELINE_PATH);
+      return;
+    }
+
+    // A missing baseline must not pass silently: record one with
+    // BENCH_UPDATE_BASELINE=1 and commit it.
+    const baseline = readBaseline();
+    if (!baseline) {
+      assert.fail(
+        `No benchmark baseline at ${BASELINE_PATH}; ` +
+          'rerun with BENCH_UPDATE_BASELINE=1 to record one'
+      );
+    }
+
+    const threshold = Number(process.env.BENCH_THRESHOLD ?? '0.05');
+    const regressions = findRegressions(results, baseline, threshold);
+    assert.isEmpty(
+      regressions,
+      regressions
+        .map(
+          ({ key, metric, baseline: before, current }) =>
+            `${key} ${metric}: ${before} -> ${current}`
+        )
+        .join('\n')
+    );
+  });
+});
@@ -1,10 +1,45 @@
+#![allow(unexpected_cfgs)]
+// Anchor's `#[program]` emits its IDL instruction handlers in a crate-root
+// `__private` module that calls the deprecated `AccountInfo::realloc`. That
+// module is a sibling of the program module, so this is the narrowest scope
+// the allow can take.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
-use chain_signatures::cpi::accounts::Sign as SignetSign;
+use chain_signatures::address::derived_public_key;
+use chain_signatures::cpi::accounts::{
+    Sign as SignetSign, SignBidirectional as SignetSignBidirectional,
+};
+use chain_signatures::evm::{self, Eip1559Transaction};
 use chain_signatures::program::ChainSignatures;
-use chain_signatures::ProgramState as SignetProgramState;
+use chain_signatures::schema::{AbiParam, AbiType, BorshType};
+use chain_signatures::{
+    key_version_root_public_key, load_key_version_registry, ExecutionStatus,
+    ProgramState as SignetProgramState, RootPublicKey as SignetRootPublicKey,
+    Signature as SignetSignature,
+};
 
 declare_id!("76SSSaQQjQ35d8shjHUsUNFwfpnJamVAiCN5hWzuF84f");
 
+/// Seed of the vault PDA, which requests signatures for keys owned by this
+/// program.
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Seed prefix of [`PendingErc20Deposit`] accounts.
+pub const PENDING_DEPOSIT_SEED: &[u8] = b"pending-erc20-deposit";
+
+/// Seed prefix of [`Erc20Balance`] accounts.
+pub const ERC20_BALANCE_SEED: &[u8] = b"erc20-balance";
+
+/// Derivation path of the vault's EVM treasury, which receives deposits.
+pub const TREASURY_PATH: &str = "treasury";
+
+/// MPC key version used for deposits.
+pub const DEPOSIT_KEY_VERSION: u32 = 0;
+
+/// Selector of ERC20 `transfer(address,uint256)`.
+const ERC20_TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
 #[program]
 pub mod proxy_test_cpi {
     use super::*;
@@ -20,12 +55,28 @@ pub mod proxy_test_cpi {
     ) -> Result<()> {
         let cpi_accounts = SignetSign {
             program_state: ctx.accounts.signet_program_state.to_account_info(),
+            key_version_registry: ctx.accounts.key_version_registry.to_account_info(),
             requester: ctx.accounts.requester.to_account_info(),
-            fee_payer: match &ctx.accounts.fee_payer {
-                Some(payer) => Some(payer.to_account_info()),
-                None => None,
-            },
+            fee_payer: ctx
+                .accounts
+                .fee_payer
+                .as_ref()
+                .map(|payer| payer.to_account_info()),
+            signature_request: Some(ctx.accounts.signature_request.to_account_info()),
+            allowlist_entry: ctx
+                .accounts
+                .allowlist_entry
+                .as_ref()
+                .map(|entry| entry.to_account_info()),
+            rate_limit_config: ctx.accounts.rate_limit_config.to_account_info(),
+            rate_limit: ctx
+                .accounts
+                .rate_limit
+                .as_ref()
+                .map(|rate_limit| rate_limit.to_account_info()),
             system_program: ctx.accounts.system_program.to_account_info(),
+            instructions: Some(ctx.accounts.instructions.to_account_info()),
+            response_record: Some(ctx.accounts.response_record.to_account_info()),
             event_authority: ctx.accounts.event_authority.to_account_info(),
             program: ctx.accounts.signet_program.to_account_info(),
         };
@@ -38,6 +89,293 @@ pub mod proxy_test_cpi {
         msg!("Successfully called signet program via CPI");
         Ok(())
     }
+
+    /// Request a signature with the vault PDA as requester.
+    ///
+    /// The vault signs through `invoke_signed`, so the derived key belongs to
+    /// this program and no user can request signatures for it directly. The
+    /// caller pays the deposit as fee payer.
+    pub fn call_sign_as_vault(
+        ctx: Context<CallSignAsVault>,
+        payload: [u8; 32],
+        key_version: u32,
+        path: String,
+        algo: String,
+        dest: String,
+        params: String,
+    ) -> Result<()> {
+        let cpi_accounts = SignetSign {
+            program_state: ctx.accounts.signet_program_state.to_account_info(),
+            key_version_registry: ctx.accounts.key_version_registry.to_account_info(),
+            requester: ctx.accounts.vault.to_account_info(),
+            fee_payer: Some(ctx.accounts.fee_payer.to_account_info()),
+            signature_request: Some(ctx.accounts.signature_request.to_account_info()),
+            allowlist_entry: ctx
+                .accounts
+                .allowlist_entry
+                .as_ref()
+                .map(|entry| entry.to_account_info()),
+            rate_limit_config: ctx.accounts.rate_limit_config.to_account_info(),
+            rate_limit: ctx
+                .accounts
+                .rate_limit
+                .as_ref()
+                .map(|rate_limit| rate_limit.to_account_info()),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            instructions: Some(ctx.accounts.instructions.to_account_info()),
+            response_record: Some(ctx.accounts.response_record.to_account_info()),
+            event_authority: ctx.accounts.event_authority.to_account_info(),
+            program: ctx.accounts.signet_program.to_account_info(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, &[ctx.bumps.vault]]];
+        let cpi_program = ctx.accounts.signet_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        chain_signatures::cpi::sign(cpi_ctx, payload, key_version, path, algo, dest, params)?;
+
+        msg!("Successfully called signet program via CPI as the vault");
+        Ok(())
+    }
+
+    /// Move `amount` of an ERC20 token from the requester's deposit address to
+    /// the vault's treasury on an EVM chain.
+    ///
+    /// The deposit address is the vault's derived address at the requester's
+    /// address as path, so only this program can move funds out of it. The
+    /// transfer is requested through `sign_bidirectional` and recorded as a
+    /// [`PendingErc20Deposit`] until [`claim_erc20`] consumes the response.
+    ///
+    /// `request_id` must be the request id of the resulting transaction, see
+    /// `chain_signatures::evm::generate_request_id`.
+    pub fn deposit_erc20(
+        ctx: Context<DepositErc20>,
+        request_id: [u8; 32],
+        erc20_address: [u8; 20],
+        amount: u128,
+        tx_params: EvmTxParams,
+    ) -> Result<()> {
+        let vault = ctx.accounts.vault.key();
+        let chain_id = &ctx.accounts.signet_program_state.chain_id;
+        let root_public_key = key_version_root_public_key(
+            load_key_version_registry(&ctx.accounts.key_version_registry)?.as_ref(),
+            Some(&ctx.accounts.root_public_key),
+            DEPOSIT_KEY_VERSION,
+        )?;
+        let treasury = evm::public_key_to_address(&derived_public_key(
+            &root_public_key,
+            chain_id,
+            &vault,
+            TREASURY_PATH,
+        )?);
+
+        let transaction = Eip1559Transaction {
+            chain_id: tx_params.chain_id,
+            nonce: tx_params.nonce,
+            max_priority_fee_per_gas: tx_params.max_priority_fee_per_gas,
+            max_fee_per_gas: tx_params.max_fee_per_gas,
+            gas_limit: tx_params.gas_limit,
+            to: Some(erc20_address),
+            value: 0,
+            data: erc20_transfer_data(&treasury, amount),
+            access_list: vec![],
+        };
+        let serialized_transaction = transaction.encode_for_signing();
+        let caip2_id = evm::caip2_id(tx_params.chain_id);
+        let path = ctx.accounts.requester.key().to_string();
+
+        require!(
+            evm::generate_request_id(
+                &vault,
+                &serialized_transaction,
+                &caip2_id,
+                DEPOSIT_KEY_VERSION,
+                &path,
+                "",
+                "",
+                "",
+            ) == request_id,
+            ProxyError::InvalidRequestId
+        );
+
+        let pending = &mut ctx.accounts.pending_deposit;
+        pending.requester = ctx.accounts.requester.key();
+        pending.erc20_address = erc20_address;
+        pending.amount = amount;
+        pending.key_version = DEPOSIT_KEY_VERSION;
+
+        let cpi_accounts = SignetSignBidirectional {
+            program_state: ctx.accounts.signet_program_state.to_account_info(),
+            key_version_registry: ctx.accounts.key_version_registry.to_account_info(),
+            requester: ctx.accounts.vault.to_account_info(),
+            fee_payer: Some(ctx.accounts.requester.to_account_info()),
+            allowlist_entry: ctx
+                .accounts
+                .allowlist_entry
+                .as_ref()
+                .map(|entry| entry.to_account_info()),
+            rate_limit_config: ctx.accounts.rate_limit_config.to_account_info(),
+            rate_limit: ctx
+                .accounts
+                .rate_limit
+                .as_ref()
+                .map(|rate_limit| rate_limit.to_account_info()),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            instructions: Some(ctx.accounts.instructions.to_account_info()),
+            event_authority: ctx.accounts.event_authority.to_account_info(),
+            program: ctx.accounts.signet_program.to_account_info(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, &[ctx.bumps.vault]]];
+        let cpi_program = ctx.accounts.signet_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        chain_signatures::cpi::sign_bidirectional(
+            cpi_ctx,
+            serialized_transaction,
+            caip2_id,
+            DEPOSIT_KEY_VERSION,
+            path,
+            String::new(),
+            String::new(),
+            String::new(),
+            crate::ID,
+            AbiParam::schema_bytes(&[AbiParam::new("", AbiType::Bool)]),
+            BorshType::Bool.schema_bytes(),
+        )?;
+
+        msg!("Requested ERC20 deposit of {}", amount);
+        Ok(())
+    }
+
+    /// Consume the `respond_bidirectional` response of a [`deposit_erc20`]
+    /// request, crediting the requester's [`Erc20Balance`] if the transfer
+    /// succeeded.
+    ///
+    /// The response must be signed by the vault's response key (see
+    /// `chain_signatures::evm::response_address`) under the root key of the
+    /// deposit's key version, read from the signet key version registry so
+    /// that rotated keys are followed. Failed transfers close the
+    /// pending deposit without crediting anything; output that does not decode
+    /// as the `bool` returned by `transfer` fails with
+    /// [`ProxyError::InvalidOutput`] and leaves the deposit pending.
+    ///
+    /// Solana responses report failures through `status`, with empty output.
+    /// The `0xdeadbeef` error prefix the fakenet signer prepends to failure
+    /// output is only used for Substrate source chains, which have no
+    /// [`ExecutionStatus`], so it never reaches this instruction.
+    pub fn claim_erc20(
+        ctx: Context<ClaimErc20>,
+        request_id: [u8; 32],
+        status: ExecutionStatus,
+        serialized_output: Vec<u8>,
+        revert_data: Option<Vec<u8>>,
+        signature: SignetSignature,
+    ) -> Result<()> {
+        let root_public_key = key_version_root_public_key(
+            load_key_version_registry(&ctx.accounts.key_version_registry)?.as_ref(),
+            Some(&ctx.accounts.root_public_key),
+            ctx.accounts.pending_deposit.key_version,
+        )?;
+        let response_address = evm::response_address(
+            &root_public_key,
+            &ctx.accounts.signet_program_state.chain_id,
+            &ctx.accounts.vault.key(),
+        )?;
+        evm::verify_response(
+            &request_id,
+            status,
+            &serialized_output,
+            revert_data.as_deref(),
+            &signature,
+            &response_address,
+        )?;
+
+        // ERC20 `transfer` returns a bool, serialized with the Borsh schema
+        // given in `deposit_erc20`. `respond_bidirectional` rejects output for
+        // every other status.
+        let transferred = status == ExecutionStatus::Success
+            && bool::try_from_slice(&serialized_output)
+                .map_err(|_| error!(ProxyError::InvalidOutput))?;
+        if !transferred {
+            msg!("ERC20 deposit failed with status {:?}", status);
+            return Ok(());
+        }
+
+        let balance = &mut ctx.accounts.balance;
+        balance.amount = balance
+            .amount
+            .checked_add(ctx.accounts.pending_deposit.amount)
+            .ok_or(ProxyError::BalanceOverflow)?;
+
+        msg!(
+            "Claimed ERC20 deposit of {}",
+            ctx.accounts.pending_deposit.amount
+        );
+        Ok(())
+    }
+}
+
+/// Calldata of ERC20 `transfer(to, amount)`.
+fn erc20_transfer_data(to: &[u8; 20], amount: u128) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + 32 + 32);
+    data.extend_from_slice(&ERC20_TRANSFER_SELECTOR);
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(to);
+    data.extend_from_slice(&[0u8; 16]);
+    data.extend_from_slice(&amount.to_be_bytes());
+    data
+}
+
+/// Fee parameters of the EVM transaction built by `deposit_erc20`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EvmTxParams {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+/// ERC20 deposit awaiting its `respond_bidirectional` response.
+///
+/// Seeds: `[b"pending-erc20-deposit", request_id]`
+#[account]
+pub struct PendingErc20Deposit {
+    /// User credited once the deposit succeeds.
+    pub requester: Pubkey,
+    pub erc20_address: [u8; 20],
+    pub amount: u128,
+    /// MPC key version of the request, whose root key signs the response.
+    pub key_version: u32,
+}
+
+impl PendingErc20Deposit {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 32 + 20 + 16 + 4;
+}
+
+/// Claimed ERC20 deposits of a user.
+///
+/// Seeds: `[b"erc20-balance", requester, erc20_address]`
+#[account]
+pub struct Erc20Balance {
+    pub amount: u128,
+}
+
+impl Erc20Balance {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 16;
+}
+
+#[error_code]
+pub enum ProxyError {
+    #[msg("Request id does not match the deposit transaction")]
+    InvalidRequestId,
+    #[msg("Balance overflow")]
+    BalanceOverflow,
+    #[msg("Response output is not a Borsh-encoded bool")]
+    InvalidOutput,
 }
 
 #[derive(Accounts)]
@@ -54,6 +392,15 @@ pub struct CallSign<'info> {
     )]
     pub signet_program_state: Account<'info, SignetProgramState>,
 
+    /// The signet key version registry, which may be uninitialized
+    /// CHECK: Validated by the signet program
+    #[account(
+        seeds = [b"key-version-registry"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub key_version_registry: UncheckedAccount<'info>,
+
     /// The requester making the signature request
     #[account(mut)]
     pub requester: Signer<'info>,
@@ -62,10 +409,273 @@ pub struct CallSign<'info> {
     #[account(mut)]
     pub fee_payer: Option<Signer<'info>>,
 
+    /// The signet request account tracking this signature request
+    /// CHECK: Validated by the signet program
+    #[account(mut)]
+    pub signature_request: UncheckedAccount<'info>,
+
+    /// The signet response record of this signature request, which rejects
+    /// requests that were already answered
+    /// CHECK: Validated by the signet program
+    pub response_record: UncheckedAccount<'info>,
+
+    /// The signet allowlist entry of this program, required in permissioned
+    /// mode
+    /// CHECK: Validated by the signet program
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+
+    /// The signet rate limit config, which may be uninitialized
+    /// CHECK: Validated by the signet program
+    #[account(
+        mut,
+        seeds = [b"rate-limit-config"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub rate_limit_config: UncheckedAccount<'info>,
+
+    /// The signet rate limit account of this program, required while rate
+    /// limiting is enabled
+    /// CHECK: Validated by the signet program
+    #[account(mut)]
+    pub rate_limit: Option<UncheckedAccount<'info>>,
+
+    /// System program for transfers
+    pub system_program: Program<'info, System>,
+
+    /// Instructions sysvar, used by the signet program to identify this program
+    /// CHECK: Address checked against the instructions sysvar id
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// Event authority for CPI events
+    /// CHECK: This is used by the Anchor event CPI system
+    pub event_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallSignAsVault<'info> {
+    /// The signet program we're calling via CPI
+    pub signet_program: Program<'info, ChainSignatures>,
+
+    /// The signet program's state account
+    #[account(
+        mut,
+        seeds = [b"program-state"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub signet_program_state: Account<'info, SignetProgramState>,
+
+    /// The signet key version registry, which may be uninitialized
+    /// CHECK: Validated by the signet program
+    #[account(
+        seeds = [b"key-version-registry"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub key_version_registry: UncheckedAccount<'info>,
+
+    /// The vault PDA, signing as the requester
+    /// CHECK: Holds no data; only signs through `invoke_signed`
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// Pays the deposit and the request account rent
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+
+    /// The signet request account tracking this signature request
+    /// CHECK: Validated by the signet program
+    #[account(mut)]
+    pub signature_request: UncheckedAccount<'info>,
+
+    /// The signet response record of this signature request, which rejects
+    /// requests that were already answered
+    /// CHECK: Validated by the signet program
+    pub response_record: UncheckedAccount<'info>,
+
+    /// The signet allowlist entry of this program, required in permissioned
+    /// mode
+    /// CHECK: Validated by the signet program
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+
+    /// The signet rate limit config, which may be uninitialized
+    /// CHECK: Validated by the signet program
+    #[account(
+        mut,
+        seeds = [b"rate-limit-config"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub rate_limit_config: UncheckedAccount<'info>,
+
+    /// The signet rate limit account of this program, required while rate
+    /// limiting is enabled
+    /// CHECK: Validated by the signet program
+    #[account(mut)]
+    pub rate_limit: Option<UncheckedAccount<'info>>,
+
     /// System program for transfers
     pub system_program: Program<'info, System>,
 
+    /// Instructions sysvar, used by the signet program to identify this program
+    /// CHECK: Address checked against the instructions sysvar id
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
     /// Event authority for CPI events
     /// CHECK: This is used by the Anchor event CPI system
     pub event_authority: AccountInfo<'info>,
 }
+
+#[derive(Accounts)]
+#[instruction(request_id: [u8; 32])]
+pub struct DepositErc20<'info> {
+    /// The signet program we're calling via CPI
+    pub signet_program: Program<'info, ChainSignatures>,
+
+    /// The signet program's state account
+    #[account(
+        mut,
+        seeds = [b"program-state"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub signet_program_state: Account<'info, SignetProgramState>,
+
+    /// The signet root public key, from which the treasury address is derived
+    /// for key versions that are not registered
+    #[account(
+        seeds = [b"root-public-key"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub root_public_key: Account<'info, SignetRootPublicKey>,
+
+    /// The signet key version registry, which may be uninitialized
+    /// CHECK: Validated by the signet program
+    #[account(
+        seeds = [b"key-version-registry"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub key_version_registry: UncheckedAccount<'info>,
+
+    /// The vault PDA, signing as the requester
+    /// CHECK: Holds no data; only signs through `invoke_signed`
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The depositing user, paying the deposit and account rent
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = PendingErc20Deposit::SPACE,
+        seeds = [PENDING_DEPOSIT_SEED, request_id.as_ref()],
+        bump
+    )]
+    pub pending_deposit: Account<'info, PendingErc20Deposit>,
+
+    /// The signet allowlist entry of this program, required in permissioned
+    /// mode
+    /// CHECK: Validated by the signet program
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+
+    /// The signet rate limit config, which may be uninitialized
+    /// CHECK: Validated by the signet program
+    #[account(
+        mut,
+        seeds = [b"rate-limit-config"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub rate_limit_config: UncheckedAccount<'info>,
+
+    /// The signet rate limit account of this program, required while rate
+    /// limiting is enabled
+    /// CHECK: Validated by the signet program
+    #[account(mut)]
+    pub rate_limit: Option<UncheckedAccount<'info>>,
+
+    /// System program for transfers
+    pub system_program: Program<'info, System>,
+
+    /// Instructions sysvar, used by the signet program to identify this program
+    /// CHECK: Address checked against the instructions sysvar id
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// Event authority for CPI events
+    /// CHECK: This is used by the Anchor event CPI system
+    pub event_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_id: [u8; 32])]
+pub struct ClaimErc20<'info> {
+    /// The signet program that signed the response
+    pub signet_program: Program<'info, ChainSignatures>,
+
+    /// The signet program's state account
+    #[account(
+        seeds = [b"program-state"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub signet_program_state: Account<'info, SignetProgramState>,
+
+    /// The signet root public key, from which the response key is derived
+    /// for key versions that are not registered
+    #[account(
+        seeds = [b"root-public-key"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub root_public_key: Account<'info, SignetRootPublicKey>,
+
+    /// The signet key version registry, which may be uninitialized
+    /// CHECK: Address checked against the signet PDA, read in the handler
+    #[account(
+        seeds = [b"key-version-registry"],
+        bump,
+        seeds::program = signet_program.key()
+    )]
+    pub key_version_registry: UncheckedAccount<'info>,
+
+    /// The vault PDA, the requester of deposits
+    /// CHECK: Only its address is used
+    #[account(seeds = [VAULT_SEED], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = requester,
+        has_one = requester,
+        seeds = [PENDING_DEPOSIT_SEED, request_id.as_ref()],
+        bump
+    )]
+    pub pending_deposit: Account<'info, PendingErc20Deposit>,
+
+    #[account(
+        init_if_needed,
+        payer = requester,
+        space = Erc20Balance::SPACE,
+        seeds = [
+            ERC20_BALANCE_SEED,
+            requester.key().as_ref(),
+            pending_deposit.erc20_address.as_ref()
+        ],
+        bump
+    )]
+    pub balance: Account<'info, Erc20Balance>,
+
+    /// The depositing user, refunded the pending deposit's rent
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
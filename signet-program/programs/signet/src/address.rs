@@ -0,0 +1,322 @@
+//! # Derived Addresses
+//!
+//! Destination chain addresses of a requester's derived secp256k1 key.
+//!
+//! ```text
+//! epsilon     = derive_epsilon(chain_id, sender, path)
+//! derived_key = derive_public_key(root_public_key, epsilon)
+//! ```
+//!
+//! | Chain | Address | Function |
+//! |-------|---------|----------|
+//! | EVM | `keccak256(x \|\| y)[12..]`, EIP-55 checksummed | [`evm_address`], [`evm_checksum_address`] |
+//! | Bitcoin P2WPKH | bech32 witness v0 of `hash160(compressed key)` | [`p2wpkh_address`] |
+//! | Bitcoin P2TR | bech32m witness v1 of the BIP-86 output key | [`p2tr_address`] |
+//! | Cosmos SDK | bech32 of `hash160(compressed key)` | [`cosmos_address`] |
+//!
+//! Keys of a program's PDA, which the program requests signatures for through
+//! `invoke_signed`, are derived with [`program_derived_public_key`].
+//!
+//! The bech32 encodings ([`hash160`], [`p2wpkh_address`], [`p2tr_address`]
+//! and [`cosmos_address`]) are only needed off-chain and are behind the
+//! `address-encoding` feature. Bitcoin functions take the network's
+//! human-readable part (`bc`, `tb` or `bcrt`); Cosmos functions take the
+//! chain's account prefix (`cosmos`, `osmo`, ...).
+//!
+//! The test vectors in `test-vectors/derived_addresses.json` are shared with
+//! the fakenet signer, which derives the same addresses from the root secret
+//! key.
+use crate::bitcoin::tagged_hash;
+use crate::derivation::{derive_epsilon, derive_public_key};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+#[cfg(feature = "address-encoding")]
+pub use self::encoding::{cosmos_address, hash160, p2tr_address, p2wpkh_address};
+
+/// secp256k1 field prime `p` (big-endian).
+const SECP256K1_P: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xfc, 0x2f,
+];
+
+/// Derived public key of `sender`'s key at `path` (`x || y`, big-endian).
+///
+/// `chain_id` is the CAIP-2 id of the source chain, the `chain_id` of this
+/// program.
+pub fn derived_public_key(
+    root_public_key: &[u8; 64],
+    chain_id: &str,
+    sender: &Pubkey,
+    path: &str,
+) -> Result<[u8; 64]> {
+    derive_public_key(root_public_key, &derive_epsilon(chain_id, sender, path))
+}
+
+/// Derived public key owned by a program: the key of the canonical PDA of
+/// `seeds` under `program_id` at `path`.
+///
+/// The program requests signatures for it by signing as the PDA via
+/// `invoke_signed`, so no user can request signatures for this key directly.
+pub fn program_derived_public_key(
+    root_public_key: &[u8; 64],
+    chain_id: &str,
+    program_id: &Pubkey,
+    seeds: &[&[u8]],
+    path: &str,
+) -> Result<[u8; 64]> {
+    let (requester, _) = Pubkey::find_program_address(seeds, program_id);
+    derived_public_key(root_public_key, chain_id, &requester, path)
+}
+
+/// SEC1 compressed form of an uncompressed public key (`x || y`).
+pub fn compressed_public_key(public_key: &[u8; 64]) -> [u8; 33] {
+    let mut compressed = [0u8; 33];
+    compressed[0] = 0x02 | (public_key[63] & 1);
+    compressed[1..].copy_from_slice(&public_key[..32]);
+    compressed
+}
+
+/// EVM address of an uncompressed public key (`x || y`).
+pub fn evm_address(public_key: &[u8; 64]) -> [u8; 20] {
+    let hash = keccak::hash(public_key).to_bytes();
+    hash[12..].try_into().unwrap()
+}
+
+/// EIP-55 checksummed hex of an EVM address.
+pub fn evm_checksum_address(address: &[u8; 20]) -> String {
+    let lower: String = address.iter().map(|byte| format!("{byte:02x}")).collect();
+    let hash = keccak::hash(lower.as_bytes()).to_bytes();
+
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{checksummed}")
+}
+
+/// BIP-86 Taproot output key of an internal key without a script tree.
+///
+/// The internal key is lifted to even `y` and tweaked with
+/// `tagged_hash("TapTweak", x)`.
+pub fn taproot_output_key(public_key: &[u8; 64]) -> Result<[u8; 32]> {
+    let mut internal_key = *public_key;
+    if internal_key[63] & 1 == 1 {
+        internal_key[32..].copy_from_slice(&negate_y(&public_key[32..]));
+    }
+    let tweak = tagged_hash(b"TapTweak", &internal_key[..32]);
+    let output_key = derive_public_key(&internal_key, &tweak)?;
+
+    Ok(output_key[..32].try_into().unwrap())
+}
+
+/// `p - y` for a big-endian field element `0 < y < p`.
+fn negate_y(y: &[u8]) -> [u8; 32] {
+    let mut negated = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = SECP256K1_P[i] as i16 - y[i] as i16 - borrow;
+        borrow = if diff < 0 {
+            diff += 256;
+            1
+        } else {
+            0
+        };
+        negated[i] = diff as u8;
+    }
+    negated
+}
+
+#[cfg(feature = "address-encoding")]
+mod encoding {
+    use super::*;
+    use crate::ChainSignaturesError;
+    use anchor_lang::solana_program::hash::hashv;
+    use bech32::{segwit, Bech32, Hrp};
+    use ripemd::{Digest, Ripemd160};
+
+    /// `ripemd160(sha256(data))`.
+    pub fn hash160(data: &[u8]) -> [u8; 20] {
+        Ripemd160::digest(hashv(&[data]).to_bytes()).into()
+    }
+
+    /// Bitcoin P2WPKH address of a public key.
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidAddressPrefix`] - `hrp` is not a valid
+    ///   human-readable part
+    pub fn p2wpkh_address(public_key: &[u8; 64], hrp: &str) -> Result<String> {
+        segwit::encode_v0(
+            parse_hrp(hrp)?,
+            &hash160(&compressed_public_key(public_key)),
+        )
+        .map_err(|_| error!(ChainSignaturesError::InvalidAddressPrefix))
+    }
+
+    /// Bitcoin P2TR address of a public key used as BIP-86 internal key.
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidAddressPrefix`] - `hrp` is not a valid
+    ///   human-readable part
+    pub fn p2tr_address(public_key: &[u8; 64], hrp: &str) -> Result<String> {
+        segwit::encode_v1(parse_hrp(hrp)?, &taproot_output_key(public_key)?)
+            .map_err(|_| error!(ChainSignaturesError::InvalidAddressPrefix))
+    }
+
+    /// Cosmos SDK account address of a public key.
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidAddressPrefix`] - `prefix` is not a valid
+    ///   human-readable part
+    pub fn cosmos_address(public_key: &[u8; 64], prefix: &str) -> Result<String> {
+        bech32::encode::<Bech32>(
+            parse_hrp(prefix)?,
+            &hash160(&compressed_public_key(public_key)),
+        )
+        .map_err(|_| error!(ChainSignaturesError::InvalidAddressPrefix))
+    }
+
+    fn parse_hrp(hrp: &str) -> Result<Hrp> {
+        Hrp::parse(hrp).map_err(|_| error!(ChainSignaturesError::InvalidAddressPrefix))
+    }
+}
+
+#[cfg(all(test, feature = "address-encoding"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::hex;
+    use serde::Deserialize;
+    use std::str::FromStr;
+
+    const VECTORS: &str = include_str!("../test-vectors/derived_addresses.json");
+
+    #[derive(Deserialize)]
+    struct Vector {
+        root_public_key: String,
+        chain_id: String,
+        sender: String,
+        path: String,
+        epsilon: String,
+        derived_public_key: String,
+        evm_address: String,
+        bitcoin: BitcoinVector,
+        cosmos: CosmosVector,
+    }
+
+    #[derive(Deserialize)]
+    struct BitcoinVector {
+        hrp: String,
+        p2wpkh: String,
+        p2tr: String,
+    }
+
+    #[derive(Deserialize)]
+    struct CosmosVector {
+        hrp: String,
+        address: String,
+    }
+
+    fn vectors() -> Vec<Vector> {
+        serde_json::from_str(VECTORS).unwrap()
+    }
+
+    #[test]
+    fn derives_shared_vectors() {
+        for vector in vectors() {
+            let root: [u8; 64] = hex(&vector.root_public_key).try_into().unwrap();
+            let sender = Pubkey::from_str(&vector.sender).unwrap();
+
+            assert_eq!(
+                derive_epsilon(&vector.chain_id, &sender, &vector.path).to_vec(),
+                hex(&vector.epsilon)
+            );
+
+            let key = derived_public_key(&root, &vector.chain_id, &sender, &vector.path).unwrap();
+            assert_eq!(key.to_vec(), hex(&vector.derived_public_key));
+            assert_eq!(evm_checksum_address(&evm_address(&key)), vector.evm_address);
+            assert_eq!(
+                p2wpkh_address(&key, &vector.bitcoin.hrp).unwrap(),
+                vector.bitcoin.p2wpkh
+            );
+            assert_eq!(
+                p2tr_address(&key, &vector.bitcoin.hrp).unwrap(),
+                vector.bitcoin.p2tr
+            );
+            assert_eq!(
+                cosmos_address(&key, &vector.cosmos.hrp).unwrap(),
+                vector.cosmos.address
+            );
+        }
+    }
+
+    #[test]
+    fn matches_reference_addresses() {
+        // secp256k1 generator, the BIP-173 example key
+        let generator: [u8; 64] = hex("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8").try_into().unwrap();
+        assert_eq!(
+            p2wpkh_address(&generator, "bc").unwrap(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+
+        // BIP-86 first receiving address, with the internal key given with odd y
+        let internal_key: [u8; 64] = hex(
+            "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115\
+            8190abf51fae206f0a1c825717ed512366620dad8c82b09807e7f27986e5c3fb",
+        )
+        .try_into()
+        .unwrap();
+        assert_eq!(
+            p2tr_address(&internal_key, "bc").unwrap(),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+
+        // EIP-55 example
+        let address: [u8; 20] = hex("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            evm_checksum_address(&address),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+    }
+
+    #[test]
+    fn derives_program_owned_keys() {
+        let vector = &vectors()[0];
+        let root: [u8; 64] = hex(&vector.root_public_key).try_into().unwrap();
+        let program_id = Pubkey::new_from_array([7; 32]);
+        let (vault, _) = Pubkey::find_program_address(&[b"vault"], &program_id);
+
+        let key =
+            program_derived_public_key(&root, &vector.chain_id, &program_id, &[b"vault"], "eth")
+                .unwrap();
+        assert_eq!(
+            key,
+            derived_public_key(&root, &vector.chain_id, &vault, "eth").unwrap()
+        );
+        assert_ne!(
+            key,
+            program_derived_public_key(&root, &vector.chain_id, &program_id, &[b"other"], "eth")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_prefix() {
+        let key: [u8; 64] = hex(&vectors()[0].derived_public_key).try_into().unwrap();
+
+        assert!(p2wpkh_address(&key, "").is_err());
+        assert!(cosmos_address(&key, "cos mos").is_err());
+    }
+}
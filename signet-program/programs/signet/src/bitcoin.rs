@@ -0,0 +1,879 @@
+//! # Solana → Bitcoin Destination Chain Integration
+//!
+//! Helpers for requesting Bitcoin transactions through `sign_bidirectional`
+//! with a `bip122:` CAIP-2 id, and for consuming the MPC response.
+//!
+//! ```text
+//! ┌─────────────────────────────────────────────────────────────────────────┐
+//! │                        SOLANA → BITCOIN FLOW                            │
+//! ├─────────────────────────────────────────────────────────────────────────┤
+//! │                                                                         │
+//! │  Solana Program              MPC Network              Bitcoin           │
+//! │       │                           │                       │             │
+//! │       │ sign_bidirectional(PSBT)  │                       │             │
+//! │       ├──────────────────────────►│                       │             │
+//! │       │                           │ Sign every input      │             │
+//! │       │◄── SignatureResponded ────┤ (one per input)       │             │
+//! │       │                           │                       │             │
+//! │       │             User finalizes + broadcasts tx ──────►│             │
+//! │       │                           │                       │             │
+//! │       │                           │◄─── Confirmations ────┤             │
+//! │       │                           │                       │             │
+//! │       │◄─ RespondBidirectional ───┤                       │             │
+//! │       │   (execution result)      │                       │             │
+//! │                                                                         │
+//! └─────────────────────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! # Building the Request
+//!
+//! `serialized_transaction` is a PSBT whose global unsigned transaction is the
+//! transaction to sign and whose inputs carry their `witness_utxo`.
+//! [`Transaction::to_psbt`] produces exactly that:
+//!
+//! ```rust
+//! use chain_signatures::bitcoin::{self, OutPoint, Transaction, TxIn, TxOut};
+//! # let (utxo_txid, recipient_hash, user_pubkey_hash) = ([1; 32], [2; 20], [3; 20]);
+//!
+//! let tx = Transaction {
+//!     version: 2,
+//!     inputs: vec![TxIn::new(OutPoint { txid: utxo_txid, vout: 0 })],
+//!     outputs: vec![TxOut {
+//!         value: 90_000,
+//!         script_pubkey: bitcoin::p2wpkh_script_pubkey(&recipient_hash),
+//!     }],
+//!     lock_time: 0,
+//! };
+//! let prevouts = [TxOut {
+//!     value: 100_000,
+//!     script_pubkey: bitcoin::p2wpkh_script_pubkey(&user_pubkey_hash),
+//! }];
+//!
+//! let psbt = tx.to_psbt(&prevouts)?;
+//! assert!(psbt.starts_with(b"psbt\xff"));
+//! # Ok::<(), anchor_lang::error::Error>(())
+//! ```
+//!
+//! The calling program then passes `psbt` to `sign_bidirectional` over CPI
+//! with a `bip122:` CAIP-2 id (`bip122:000000000019d6689c085ae165831e93` for
+//! mainnet), its own program id as `program_id`, and
+//! [`OUTPUT_DESERIALIZATION_SCHEMA`] / [`RESPOND_SERIALIZATION_SCHEMA`] as the
+//! schemas.
+//!
+//! # Request IDs
+//!
+//! Bitcoin requests are keyed by the explorer-facing txid (big-endian) rather
+//! than by the PSBT bytes, since the same transaction has many PSBT encodings:
+//!
+//! | Response | `serialized_transaction` in the request id |
+//! |----------|--------------------------------------------|
+//! | Signature of input `i` (`respond`) | `explorer_txid \|\| i as u32 LE` |
+//! | Execution result (`respond_bidirectional`) | `explorer_txid` |
+//!
+//! See [`input_request_id`] and [`aggregate_request_id`].
+//!
+//! # Signatures
+//!
+//! Each input is signed over its sighash: BIP-143 for P2WPKH inputs
+//! ([`Transaction::p2wpkh_sighash`]) and BIP-341 for P2TR key-path spends
+//! ([`Transaction::taproot_key_spend_sighash`]).
+//!
+//! # Response
+//!
+//! Bitcoin has no call output. A successful transaction is reported as
+//! [`BitcoinOutcome`] `{ success: true }` serialized with
+//! [`RESPOND_SERIALIZATION_SCHEMA`]; failures carry a non-success
+//! [`ExecutionStatus`] (`Replaced` when the inputs were spent by another
+//! transaction). [`decode_outcome`] handles both.
+use crate::request_id::sign_bidirectional_request_id;
+use crate::{ChainSignaturesError, ExecutionStatus};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+/// CAIP-2 namespace of Bitcoin chains.
+pub const CAIP2_NAMESPACE: &str = "bip122";
+
+/// `output_deserialization_schema` for Bitcoin requests.
+pub const OUTPUT_DESERIALIZATION_SCHEMA: &[u8] = br#"{"struct":{"success":"bool"}}"#;
+
+/// `respond_serialization_schema` for Bitcoin requests, matching [`BitcoinOutcome`].
+pub const RESPOND_SERIALIZATION_SCHEMA: &[u8] = br#"{"struct":{"success":"bool"}}"#;
+
+/// BIP-341 default sighash type (behaves as `SIGHASH_ALL`, 64-byte signature).
+pub const SIGHASH_DEFAULT: u8 = 0x00;
+/// Signs all inputs and outputs.
+pub const SIGHASH_ALL: u8 = 0x01;
+/// Signs all inputs and no outputs.
+pub const SIGHASH_NONE: u8 = 0x02;
+/// Signs all inputs and the output with the same index as the signed input.
+pub const SIGHASH_SINGLE: u8 = 0x03;
+/// Flag restricting the signed inputs to the input being signed.
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
+/// Default input sequence (final, no relative lock time).
+pub const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+
+/// Decoded `respond_bidirectional` output of a Bitcoin request.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitcoinOutcome {
+    /// Whether the transaction confirmed on the Bitcoin network
+    pub success: bool,
+}
+
+/// Reference to a previous transaction output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutPoint {
+    /// Txid in internal byte order (the reverse of the explorer-facing hex)
+    pub txid: [u8; 32],
+    /// Output index
+    pub vout: u32,
+}
+
+/// Transaction input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxIn {
+    pub previous_output: OutPoint,
+    /// Empty for SegWit inputs
+    pub script_sig: Vec<u8>,
+    pub sequence: u32,
+}
+
+impl TxIn {
+    /// Unsigned input spending `previous_output` with [`SEQUENCE_FINAL`].
+    pub fn new(previous_output: OutPoint) -> Self {
+        Self {
+            previous_output,
+            script_sig: Vec::new(),
+            sequence: SEQUENCE_FINAL,
+        }
+    }
+}
+
+/// Transaction output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxOut {
+    /// Amount in satoshis
+    pub value: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+/// Bitcoin transaction without witness data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub version: i32,
+    pub inputs: Vec<TxIn>,
+    pub outputs: Vec<TxOut>,
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// Serializes the transaction in the legacy (non-witness) format.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        write_compact_size(&mut bytes, self.inputs.len() as u64);
+        for input in &self.inputs {
+            write_outpoint(&mut bytes, &input.previous_output);
+            write_var_bytes(&mut bytes, &input.script_sig);
+            bytes.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut bytes, self.outputs.len() as u64);
+        for output in &self.outputs {
+            write_tx_out(&mut bytes, output);
+        }
+        bytes.extend_from_slice(&self.lock_time.to_le_bytes());
+        bytes
+    }
+
+    /// Parses a transaction in the legacy (non-witness) format.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader(bytes);
+        let version = i32::from_le_bytes(reader.array()?);
+
+        let input_count = reader.compact_size()?;
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            inputs.push(TxIn {
+                previous_output: OutPoint {
+                    txid: reader.array()?,
+                    vout: u32::from_le_bytes(reader.array()?),
+                },
+                script_sig: reader.var_bytes()?.to_vec(),
+                sequence: u32::from_le_bytes(reader.array()?),
+            });
+        }
+
+        let output_count = reader.compact_size()?;
+        let mut outputs = Vec::new();
+        for _ in 0..output_count {
+            outputs.push(reader.tx_out()?);
+        }
+
+        let lock_time = u32::from_le_bytes(reader.array()?);
+        require!(
+            reader.0.is_empty(),
+            ChainSignaturesError::InvalidTransaction
+        );
+
+        Ok(Self {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+        })
+    }
+
+    /// Txid in internal byte order.
+    pub fn txid(&self) -> [u8; 32] {
+        sha256d(&[&self.serialize()])
+    }
+
+    /// Txid in the big-endian order shown by block explorers and used in
+    /// request ids.
+    pub fn explorer_txid(&self) -> [u8; 32] {
+        let mut txid = self.txid();
+        txid.reverse();
+        txid
+    }
+
+    /// BIP-143 sighash of a P2WPKH input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_index` - Index of the input being signed
+    /// * `pubkey_hash` - HASH160 of the compressed public key owning the input
+    /// * `value` - Amount of the spent output in satoshis
+    /// * `sighash_type` - `SIGHASH_ALL`, `SIGHASH_NONE` or `SIGHASH_SINGLE`,
+    ///   optionally combined with `SIGHASH_ANYONECANPAY`
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidTransaction`] - Input index out of range
+    ///   or unsupported sighash type
+    pub fn p2wpkh_sighash(
+        &self,
+        input_index: usize,
+        pubkey_hash: &[u8; 20],
+        value: u64,
+        sighash_type: u8,
+    ) -> Result<[u8; 32]> {
+        let input = self
+            .inputs
+            .get(input_index)
+            .ok_or(ChainSignaturesError::InvalidTransaction)?;
+        let base_type = sighash_type & !SIGHASH_ANYONECANPAY;
+        require!(
+            (SIGHASH_ALL..=SIGHASH_SINGLE).contains(&base_type),
+            ChainSignaturesError::InvalidTransaction
+        );
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+        let hash_prevouts = if anyone_can_pay {
+            [0; 32]
+        } else {
+            sha256d(&[&self.serialize_outpoints()])
+        };
+        let hash_sequence = if anyone_can_pay || base_type != SIGHASH_ALL {
+            [0; 32]
+        } else {
+            sha256d(&[&self.serialize_sequences()])
+        };
+        let hash_outputs = match base_type {
+            SIGHASH_ALL => sha256d(&[&self.serialize_outputs()]),
+            SIGHASH_SINGLE if input_index < self.outputs.len() => {
+                let mut output = Vec::new();
+                write_tx_out(&mut output, &self.outputs[input_index]);
+                sha256d(&[&output])
+            }
+            _ => [0; 32],
+        };
+
+        let mut preimage = Vec::with_capacity(182);
+        preimage.extend_from_slice(&self.version.to_le_bytes());
+        preimage.extend_from_slice(&hash_prevouts);
+        preimage.extend_from_slice(&hash_sequence);
+        write_outpoint(&mut preimage, &input.previous_output);
+        // scriptCode of P2WPKH: OP_DUP OP_HASH160 <pubkey_hash> OP_EQUALVERIFY OP_CHECKSIG
+        preimage.extend_from_slice(&[0x19, 0x76, 0xa9, 0x14]);
+        preimage.extend_from_slice(pubkey_hash);
+        preimage.extend_from_slice(&[0x88, 0xac]);
+        preimage.extend_from_slice(&value.to_le_bytes());
+        preimage.extend_from_slice(&input.sequence.to_le_bytes());
+        preimage.extend_from_slice(&hash_outputs);
+        preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&u32::from(sighash_type).to_le_bytes());
+
+        Ok(sha256d(&[&preimage]))
+    }
+
+    /// BIP-341 sighash of a P2TR key-path spend (no annex).
+    ///
+    /// # Arguments
+    ///
+    /// * `input_index` - Index of the input being signed
+    /// * `prevouts` - Outputs spent by every input, in input order
+    /// * `sighash_type` - [`SIGHASH_DEFAULT`], or `SIGHASH_ALL`, `SIGHASH_NONE`
+    ///   or `SIGHASH_SINGLE` optionally combined with `SIGHASH_ANYONECANPAY`
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidTransaction`] - Input index out of range,
+    ///   `prevouts` not matching the inputs, unsupported sighash type, or
+    ///   `SIGHASH_SINGLE` without a matching output
+    pub fn taproot_key_spend_sighash(
+        &self,
+        input_index: usize,
+        prevouts: &[TxOut],
+        sighash_type: u8,
+    ) -> Result<[u8; 32]> {
+        require!(
+            input_index < self.inputs.len() && prevouts.len() == self.inputs.len(),
+            ChainSignaturesError::InvalidTransaction
+        );
+        let base_type = sighash_type & !SIGHASH_ANYONECANPAY;
+        require!(
+            sighash_type == SIGHASH_DEFAULT || (SIGHASH_ALL..=SIGHASH_SINGLE).contains(&base_type),
+            ChainSignaturesError::InvalidTransaction
+        );
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+        let mut message = Vec::with_capacity(206);
+        message.push(0x00); // sighash epoch
+        message.push(sighash_type);
+        message.extend_from_slice(&self.version.to_le_bytes());
+        message.extend_from_slice(&self.lock_time.to_le_bytes());
+
+        if !anyone_can_pay {
+            let amounts: Vec<u8> = prevouts
+                .iter()
+                .flat_map(|p| p.value.to_le_bytes())
+                .collect();
+            let mut script_pubkeys = Vec::new();
+            for prevout in prevouts {
+                write_var_bytes(&mut script_pubkeys, &prevout.script_pubkey);
+            }
+            message.extend_from_slice(&sha256(&[&self.serialize_outpoints()]));
+            message.extend_from_slice(&sha256(&[&amounts]));
+            message.extend_from_slice(&sha256(&[&script_pubkeys]));
+            message.extend_from_slice(&sha256(&[&self.serialize_sequences()]));
+        }
+        if base_type != SIGHASH_NONE && base_type != SIGHASH_SINGLE {
+            message.extend_from_slice(&sha256(&[&self.serialize_outputs()]));
+        }
+
+        message.push(0x00); // spend type: key path, no annex
+        if anyone_can_pay {
+            let input = &self.inputs[input_index];
+            write_outpoint(&mut message, &input.previous_output);
+            write_tx_out(&mut message, &prevouts[input_index]);
+            message.extend_from_slice(&input.sequence.to_le_bytes());
+        } else {
+            message.extend_from_slice(&(input_index as u32).to_le_bytes());
+        }
+
+        if base_type == SIGHASH_SINGLE {
+            let output = self
+                .outputs
+                .get(input_index)
+                .ok_or(ChainSignaturesError::InvalidTransaction)?;
+            let mut serialized = Vec::new();
+            write_tx_out(&mut serialized, output);
+            message.extend_from_slice(&sha256(&[&serialized]));
+        }
+
+        Ok(tagged_hash(b"TapSighash", &message))
+    }
+
+    /// Encodes the transaction as the PSBT expected by `sign_bidirectional`:
+    /// the unsigned transaction plus the `witness_utxo` of every input.
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidTransaction`] - `prevouts` not matching
+    ///   the inputs, or an input with a non-empty `script_sig`
+    pub fn to_psbt(&self, prevouts: &[TxOut]) -> Result<Vec<u8>> {
+        require!(
+            prevouts.len() == self.inputs.len()
+                && self.inputs.iter().all(|input| input.script_sig.is_empty()),
+            ChainSignaturesError::InvalidTransaction
+        );
+
+        let mut psbt = PSBT_MAGIC.to_vec();
+        write_var_bytes(&mut psbt, &[PSBT_GLOBAL_UNSIGNED_TX]);
+        write_var_bytes(&mut psbt, &self.serialize());
+        psbt.push(0x00);
+
+        for prevout in prevouts {
+            let mut witness_utxo = Vec::new();
+            write_tx_out(&mut witness_utxo, prevout);
+            write_var_bytes(&mut psbt, &[PSBT_IN_WITNESS_UTXO]);
+            write_var_bytes(&mut psbt, &witness_utxo);
+            psbt.push(0x00);
+        }
+        // Outputs carry no fields, only their separators
+        psbt.resize(psbt.len() + self.outputs.len(), 0x00);
+
+        Ok(psbt)
+    }
+
+    fn serialize_outpoints(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.inputs.len() * 36);
+        for input in &self.inputs {
+            write_outpoint(&mut bytes, &input.previous_output);
+        }
+        bytes
+    }
+
+    fn serialize_sequences(&self) -> Vec<u8> {
+        self.inputs
+            .iter()
+            .flat_map(|input| input.sequence.to_le_bytes())
+            .collect()
+    }
+
+    fn serialize_outputs(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for output in &self.outputs {
+            write_tx_out(&mut bytes, output);
+        }
+        bytes
+    }
+}
+
+/// `scriptPubKey` of a P2WPKH output: `OP_0 <pubkey_hash>`.
+pub fn p2wpkh_script_pubkey(pubkey_hash: &[u8; 20]) -> Vec<u8> {
+    [&[0x00, 0x14][..], pubkey_hash].concat()
+}
+
+/// `scriptPubKey` of a P2TR output: `OP_1 <output_key>`.
+pub fn p2tr_script_pubkey(output_key: &[u8; 32]) -> Vec<u8> {
+    [&[0x51, 0x20][..], output_key].concat()
+}
+
+/// Request id of the signature for input `input_index` of a Bitcoin request.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidChainId`] - `caip2_id` is not a `bip122:` id
+pub fn input_request_id(
+    sender: &Pubkey,
+    explorer_txid: &[u8; 32],
+    input_index: u32,
+    caip2_id: &str,
+    key_version: u32,
+    path: &str,
+    algo: &str,
+    dest: &str,
+    params: &str,
+) -> Result<[u8; 32]> {
+    require_bip122(caip2_id)?;
+    let mut payload = [0u8; 36];
+    payload[..32].copy_from_slice(explorer_txid);
+    payload[32..].copy_from_slice(&input_index.to_le_bytes());
+    Ok(sign_bidirectional_request_id(
+        sender,
+        &payload,
+        caip2_id,
+        key_version,
+        path,
+        algo,
+        dest,
+        params,
+    ))
+}
+
+/// Request id of the `respond_bidirectional` execution result of a Bitcoin
+/// request.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidChainId`] - `caip2_id` is not a `bip122:` id
+pub fn aggregate_request_id(
+    sender: &Pubkey,
+    explorer_txid: &[u8; 32],
+    caip2_id: &str,
+    key_version: u32,
+    path: &str,
+    algo: &str,
+    dest: &str,
+    params: &str,
+) -> Result<[u8; 32]> {
+    require_bip122(caip2_id)?;
+    Ok(sign_bidirectional_request_id(
+        sender,
+        explorer_txid,
+        caip2_id,
+        key_version,
+        path,
+        algo,
+        dest,
+        params,
+    ))
+}
+
+/// Decodes the output of a verified `respond_bidirectional` for a Bitcoin
+/// request.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidResponseOutput`] - Successful response whose
+///   output is not a [`BitcoinOutcome`]
+pub fn decode_outcome(status: ExecutionStatus, serialized_output: &[u8]) -> Result<BitcoinOutcome> {
+    match status {
+        ExecutionStatus::Success => BitcoinOutcome::try_from_slice(serialized_output)
+            .map_err(|_| ChainSignaturesError::InvalidResponseOutput.into()),
+        _ => Ok(BitcoinOutcome { success: false }),
+    }
+}
+
+fn require_bip122(caip2_id: &str) -> Result<()> {
+    match caip2_id.split_once(':') {
+        Some((CAIP2_NAMESPACE, reference)) if !reference.is_empty() => Ok(()),
+        _ => err!(ChainSignaturesError::InvalidChainId),
+    }
+}
+
+fn sha256(data: &[&[u8]]) -> [u8; 32] {
+    hashv(data).to_bytes()
+}
+
+fn sha256d(data: &[&[u8]]) -> [u8; 32] {
+    sha256(&[&sha256(data)])
+}
+
+/// BIP-340 tagged hash: `sha256(sha256(tag) || sha256(tag) || data)`.
+pub(crate) fn tagged_hash(tag: &[u8], data: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256(&[tag]);
+    sha256(&[&tag_hash, &tag_hash, data])
+}
+
+fn write_compact_size(bytes: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0xfc => bytes.push(value as u8),
+        0xfd..=0xffff => {
+            bytes.push(0xfd);
+            bytes.extend_from_slice(&(value as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            bytes.push(0xfe);
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        _ => {
+            bytes.push(0xff);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+fn write_var_bytes(bytes: &mut Vec<u8>, data: &[u8]) {
+    write_compact_size(bytes, data.len() as u64);
+    bytes.extend_from_slice(data);
+}
+
+fn write_outpoint(bytes: &mut Vec<u8>, outpoint: &OutPoint) {
+    bytes.extend_from_slice(&outpoint.txid);
+    bytes.extend_from_slice(&outpoint.vout.to_le_bytes());
+}
+
+fn write_tx_out(bytes: &mut Vec<u8>, output: &TxOut) {
+    bytes.extend_from_slice(&output.value.to_le_bytes());
+    write_var_bytes(bytes, &output.script_pubkey);
+}
+
+/// Cursor over consensus-encoded bytes.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        require!(
+            len <= self.0.len(),
+            ChainSignaturesError::InvalidTransaction
+        );
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn compact_size(&mut self) -> Result<u64> {
+        Ok(match self.array::<1>()?[0] {
+            0xfd => u16::from_le_bytes(self.array()?).into(),
+            0xfe => u32::from_le_bytes(self.array()?).into(),
+            0xff => u64::from_le_bytes(self.array()?),
+            value => value.into(),
+        })
+    }
+
+    fn var_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.compact_size()?;
+        let len = usize::try_from(len).map_err(|_| ChainSignaturesError::InvalidTransaction)?;
+        self.take(len)
+    }
+
+    fn tx_out(&mut self) -> Result<TxOut> {
+        Ok(TxOut {
+            value: u64::from_le_bytes(self.array()?),
+            script_pubkey: self.var_bytes()?.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{hex, hex32};
+
+    fn prevouts(s: &str) -> Vec<TxOut> {
+        let bytes = hex(s);
+        let mut reader = Reader(&bytes);
+        (0..reader.compact_size().unwrap())
+            .map(|_| reader.tx_out().unwrap())
+            .collect()
+    }
+
+    /// Native P2WPKH example from BIP-143
+    /// (<https://github.com/bitcoin/bips/blob/master/bip-0143.mediawiki#native-p2wpkh>).
+    #[test]
+    fn p2wpkh_sighash_matches_bip143() {
+        let tx = Transaction::deserialize(&hex(
+            "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000\
+             00eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a01000000\
+             00ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac90\
+             93510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000",
+        ))
+        .unwrap();
+        let pubkey_hash = hex("1d0f172a0ecb48aee1be1f2687d2963ae33f71a1")
+            .try_into()
+            .unwrap();
+
+        let sighash = tx
+            .p2wpkh_sighash(1, &pubkey_hash, 600_000_000, SIGHASH_ALL)
+            .unwrap();
+
+        assert_eq!(
+            sighash,
+            hex32("c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670")
+        );
+    }
+
+    /// Key-path spending cases from the Bitcoin Core test framework
+    /// (`src/test/data/bip341_wallet_vectors.json`, `keyPathSpending`).
+    #[test]
+    fn taproot_key_spend_sighash_matches_reference_vectors() {
+        let cases = [
+            (
+                "020000000164eb050a5e3da0c2a65e4786f26d753b7bc69691fabccafb11f7acef36641f1846010000003101b2b404392a22000000000017a9147f2bde86fe78bf68a0544a4f290e12f0b7e0a08c87580200000000000017a91425d11723074ecfb96a0a83c3956bfaf362ae0c908758020000000000001600147e20f938993641de67bb0cdd71682aa34c4d29ad5802000000000000160014c64984dc8761acfa99418bd6bedc79b9287d652d72000000",
+                "01365724000000000023542156b39dab4f8f3508e0432cfb41fab110170acaa2d4c42539cb90a4dc7c093bc500",
+                0,
+                SIGHASH_DEFAULT,
+                "33ca0ebfb4a945eeee9569fc0f5040221275f88690b7f8592ada88ce3bdf6703",
+            ),
+            (
+                "0200000002fff49be59befe7566050737910f6ccdc5e749c7f8860ddc140386463d88c5ad0f3000000002cf68eb4a3d67f9d4c079249f7e4f27b8854815cb1ed13842d4fbf395f9e217fd605ee24090100000065235d9203f458520000000000160014b6d48333bb13b4c644e57c43a9a26df3a44b785e58020000000000001976a914eea9461a9e1e3f765d3af3e726162e0229fe3eb688ac58020000000000001976a9143a8869c9f2b5ea1d4ff3aeeb6a8fb2fffb1ad5fe88ac0ad7125c",
+                "02591f220000000000225120f25ad35583ea31998d968871d7de1abd2a52f6fe4178b54ea158274806ff4ece48fb310000000000225120f25ad35583ea31998d968871d7de1abd2a52f6fe4178b54ea158274806ff4ece",
+                1,
+                SIGHASH_ALL,
+                "626ab955d58c9a8a600a0c580549d06dc7da4e802eb2a531f62a588e430967a8",
+            ),
+            (
+                "0200000001350005f65aa830ced2079df348e2d8c2bdb4f10e2dde6a161d8a07b40d1ad87dae000000001611d0d603d9dc0e000000000017a914459b6d7d6bbb4d8837b4bf7e9a4556f952da2f5c8758020000000000001976a9141dd70e1299ffc2d5b51f6f87de9dfe9398c33cbb88ac58020000000000001976a9141dd70e1299ffc2d5b51f6f87de9dfe9398c33cbb88aca71c1f4f",
+                "01c4811000000000002251201bf9297d0a2968ae6693aadd0fa514717afefd218087a239afb7418e2d22e65c",
+                0,
+                SIGHASH_ALL | SIGHASH_ANYONECANPAY,
+                "dfa9437f9c9a1d1f9af271f79f2f5482f287cdb0d2e03fa92c8a9b216cc6061c",
+            ),
+            (
+                "020000000185bed1a6da2bffbd60ec681a1bfb71c5111d6395b99b3f8b2bf90167111bcb18f5010000007c83ace802ded24a00000000001600142c4698f9f7a773866879755aa78c516fb332af8e5802000000000000160014d38639dfbac4259323b98a472405db0c461b31fa61073747",
+                "0144c84d0000000000225120e3f2107989c88e67296ab2faca930efa2e3a5bd3ff0904835a11c9e807458621",
+                0,
+                SIGHASH_NONE,
+                "3129de36a5d05fff97ffca31eb75fcccbbbc27b3147a7a36a9e4b45d8b625067",
+            ),
+            (
+                "02000000017836b409a5fed32211407e44b971591f2032053f14701fb5b3a30c0ff382f2cc9c0100000061ac55f60288fb5600000000001976a9144ea02f6f182b082fb6ce47e36bbde390b6a41b5088ac58020000000000001976a9144ea02f6f182b082fb6ce47e36bbde390b6a41b5088ace4000000",
+                "01efa558000000000022512007071ea3dc7e331b0687d0193d1e6d6ed10e645ef36f10ef8831d5e522ac9e80",
+                0,
+                SIGHASH_SINGLE,
+                "30239345177cadd0e3ea413d49803580abb6cb27971b481b7788a78d35117a88",
+            ),
+            (
+                "0100000001aa6deae89d5e0aaca58714fc76ef6f3c8284224888089232d4e663843ed3ab3eae010000008b6657a60450cb4c0000000000160014a3d42b5413ef0c0701c4702f3cd7d4df222c147058020000000000001976a91430b4ed8723a4ee8992aa2c8814cfe5c3ad0ab9d988ac5802000000000000160014365b1166a6ed0a5e8e9dff17a6d00bbb43454bc758020000000000001976a914bc98c51a84fe7fad5dc380eb8b39586eff47241688ac4f313247",
+                "0107af4e00000000002251202c36d243dfc06cb56a248e62df27ecba7417307511a81ae61aa41c597a929c69",
+                0,
+                SIGHASH_SINGLE | SIGHASH_ANYONECANPAY,
+                "bf9c83f26c6dd16449e4921f813f551c4218e86f2ec906ca8611175b41b566df",
+            ),
+        ];
+
+        for (tx, spent, input_index, sighash_type, expected) in cases {
+            let tx = Transaction::deserialize(&hex(tx)).unwrap();
+            let sighash = tx
+                .taproot_key_spend_sighash(input_index, &prevouts(spent), sighash_type)
+                .unwrap();
+            assert_eq!(sighash, hex32(expected), "sighash type {sighash_type:#04x}");
+        }
+    }
+
+    #[test]
+    fn sighash_rejects_bad_input_index_and_type() {
+        let tx = Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(OutPoint {
+                txid: [1; 32],
+                vout: 0,
+            })],
+            outputs: vec![],
+            lock_time: 0,
+        };
+        let prevout = TxOut {
+            value: 1_000,
+            script_pubkey: p2tr_script_pubkey(&[2; 32]),
+        };
+
+        assert!(tx.p2wpkh_sighash(1, &[0; 20], 1_000, SIGHASH_ALL).is_err());
+        assert!(tx.p2wpkh_sighash(0, &[0; 20], 1_000, 0x04).is_err());
+        assert!(tx
+            .taproot_key_spend_sighash(0, &[], SIGHASH_DEFAULT)
+            .is_err());
+        assert!(tx
+            .taproot_key_spend_sighash(
+                0,
+                std::slice::from_ref(&prevout),
+                SIGHASH_DEFAULT | SIGHASH_ANYONECANPAY
+            )
+            .is_err());
+        // SIGHASH_SINGLE without a matching output
+        assert!(tx
+            .taproot_key_spend_sighash(0, &[prevout], SIGHASH_SINGLE)
+            .is_err());
+    }
+
+    /// First Bitcoin transaction between two parties, block 170 of mainnet,
+    /// txid `f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16`
+    /// (<https://mempool.space/tx/f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16>).
+    #[test]
+    fn transaction_roundtrips_and_txid_is_reversed_for_explorers() {
+        let bytes = hex(
+            "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000\
+             004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd4102\
+             20181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200\
+             ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa2\
+             8414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee00\
+             00000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0\
+             eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000",
+        );
+        let tx = Transaction::deserialize(&bytes).unwrap();
+
+        assert_eq!(tx.serialize(), bytes);
+        assert_eq!(
+            tx.explorer_txid(),
+            hex32("f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16")
+        );
+        let mut txid = tx.explorer_txid();
+        txid.reverse();
+        assert_eq!(txid, tx.txid());
+        // Spends the coinbase of block 9, shown reversed by explorers.
+        let mut spent = tx.inputs[0].previous_output.txid;
+        spent.reverse();
+        assert_eq!(
+            spent,
+            hex32("0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9")
+        );
+        assert_eq!(
+            tx.outputs
+                .iter()
+                .map(|output| output.value)
+                .collect::<Vec<_>>(),
+            [1_000_000_000, 4_000_000_000]
+        );
+
+        assert!(Transaction::deserialize(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Transaction::deserialize(&[bytes.as_slice(), &[0]].concat()).is_err());
+    }
+
+    #[test]
+    fn psbt_holds_unsigned_tx_and_witness_utxos() {
+        let tx = Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(OutPoint {
+                txid: [0xab; 32],
+                vout: 1,
+            })],
+            outputs: vec![TxOut {
+                value: 90_000,
+                script_pubkey: p2wpkh_script_pubkey(&[0x11; 20]),
+            }],
+            lock_time: 0,
+        };
+        let prevout = TxOut {
+            value: 100_000,
+            script_pubkey: p2wpkh_script_pubkey(&[0x22; 20]),
+        };
+
+        let psbt = tx.to_psbt(std::slice::from_ref(&prevout)).unwrap();
+
+        let unsigned_tx = tx.serialize();
+        let mut witness_utxo = Vec::new();
+        write_tx_out(&mut witness_utxo, &prevout);
+        let expected = [
+            b"psbt\xff".as_slice(),
+            &[0x01, 0x00, unsigned_tx.len() as u8],
+            &unsigned_tx,
+            &[0x00],
+            &[0x01, 0x01, witness_utxo.len() as u8],
+            &witness_utxo,
+            &[0x00],
+            &[0x00],
+        ]
+        .concat();
+        assert_eq!(psbt, expected);
+
+        assert!(tx.to_psbt(&[]).is_err());
+    }
+
+    #[test]
+    fn request_ids_use_explorer_txid_and_input_index() {
+        let sender = Pubkey::new_from_array([7; 32]);
+        let txid = [0x5a; 32];
+        let caip2_id = "bip122:000000000933ea01ad0ee984209779ba";
+
+        let input = input_request_id(&sender, &txid, 2, caip2_id, 0, "path", "", "", "").unwrap();
+        let mut payload = txid.to_vec();
+        payload.extend_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            input,
+            sign_bidirectional_request_id(&sender, &payload, caip2_id, 0, "path", "", "", "")
+        );
+
+        let aggregate =
+            aggregate_request_id(&sender, &txid, caip2_id, 0, "path", "", "", "").unwrap();
+        assert_eq!(
+            aggregate,
+            sign_bidirectional_request_id(&sender, &txid, caip2_id, 0, "path", "", "", "")
+        );
+        assert_ne!(input, aggregate);
+
+        assert!(aggregate_request_id(&sender, &txid, "eip155:1", 0, "path", "", "", "").is_err());
+        assert!(input_request_id(&sender, &txid, 0, "bip122:", 0, "path", "", "", "").is_err());
+    }
+
+    #[test]
+    fn outcome_decodes_status_and_output() {
+        assert_eq!(
+            decode_outcome(ExecutionStatus::Success, &[1]).unwrap(),
+            BitcoinOutcome { success: true }
+        );
+        assert_eq!(
+            decode_outcome(ExecutionStatus::Replaced, &[]).unwrap(),
+            BitcoinOutcome { success: false }
+        );
+        assert!(decode_outcome(ExecutionStatus::Success, &[]).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! # Key Derivation
+//!
+//! Every requester gets its own signing key, derived from the MPC root public
+//! key, the requester address and the derivation `path`:
+//!
+//! ```text
+//! epsilon     = keccak256("sig.network v2.0.0 epsilon derivation:{chain_id}:{sender}:{path}")
+//! derived_key = root_public_key + epsilon * G
+//! ```
+//!
+//! `chain_id` is the CAIP-2 id of the source chain (this program's
+//! `chain_id`) and `sender` is the base58 address of the requester.
+//!
+//! Solana has no syscall for secp256k1 point arithmetic, so the derived key is
+//! computed with `secp256k1_recover`: recovering a signature whose `R` point is
+//! the root key, with `r = s = root.x` and `hash = -epsilon * root.x mod n`,
+//! yields exactly `root + epsilon * G`.
+use crate::{ChainSignaturesError, Signature};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::secp256k1_recover::secp256k1_recover;
+
+/// Prefix of the epsilon derivation string.
+pub const EPSILON_DERIVATION_PREFIX: &str = "sig.network v2.0.0 epsilon derivation";
+
+/// Order of the secp256k1 group as big-endian 64-bit limbs.
+const SECP256K1_N: U256 = [
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_fffe,
+    0xbaae_dce6_af48_a03b,
+    0xbfd2_5e8c_d036_4141,
+];
+
+/// 256-bit unsigned integer as big-endian 64-bit limbs.
+type U256 = [u64; 4];
+
+/// Computes the derivation tweak of `sender`'s key at `path`.
+pub fn derive_epsilon(chain_id: &str, sender: &Pubkey, path: &str) -> [u8; 32] {
+    let derivation_path = format!("{EPSILON_DERIVATION_PREFIX}:{chain_id}:{sender}:{path}");
+    keccak::hash(derivation_path.as_bytes()).to_bytes()
+}
+
+/// Computes `root_public_key + epsilon * G`.
+///
+/// Public keys are uncompressed secp256k1 points without the `0x04` prefix
+/// (`x || y`, big-endian).
+pub fn derive_public_key(root_public_key: &[u8; 64], epsilon: &[u8; 32]) -> Result<[u8; 64]> {
+    let x = u256_from_be_bytes(&root_public_key[..32]);
+    require!(x < SECP256K1_N, ChainSignaturesError::InvalidRootPublicKey);
+
+    let epsilon = reduce_mod_n(u256_from_be_bytes(epsilon));
+    let hash = u256_to_be_bytes(sub_mod_n([0; 4], mul_mod_n(epsilon, x)));
+    let recovery_id = root_public_key[63] & 1;
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(&root_public_key[..32]);
+    signature[32..].copy_from_slice(&root_public_key[..32]);
+
+    let derived = secp256k1_recover(&hash, recovery_id, &signature)
+        .map_err(|_| ChainSignaturesError::InvalidRootPublicKey)?;
+
+    Ok(derived.to_bytes())
+}
+
+/// Recovers the public key that produced `signature` over `message_hash`.
+pub fn recover_public_key(message_hash: &[u8; 32], signature: &Signature) -> Result<[u8; 64]> {
+    let mut signature_bytes = [0u8; 64];
+    signature_bytes[..32].copy_from_slice(&signature.big_r.x);
+    signature_bytes[32..].copy_from_slice(&signature.s);
+
+    let recovered = secp256k1_recover(message_hash, signature.recovery_id, &signature_bytes)
+        .map_err(|_| ChainSignaturesError::InvalidSignature)?;
+
+    Ok(recovered.to_bytes())
+}
+
+fn u256_from_be_bytes(bytes: &[u8]) -> U256 {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    limbs
+}
+
+fn u256_to_be_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(value) {
+        chunk.copy_from_slice(&limb.to_be_bytes());
+    }
+    bytes
+}
+
+/// Reduces a 256-bit value modulo `n`. One subtraction suffices since `2^256 < 2n`.
+fn reduce_mod_n(value: U256) -> U256 {
+    if value < SECP256K1_N {
+        value
+    } else {
+        sub(value, SECP256K1_N).0
+    }
+}
+
+/// `a + b mod n` for `a, b < n`.
+fn add_mod_n(a: U256, b: U256) -> U256 {
+    let (sum, carry) = add(a, b);
+    if carry || sum >= SECP256K1_N {
+        sub(sum, SECP256K1_N).0
+    } else {
+        sum
+    }
+}
+
+/// `a - b mod n` for `a, b < n`.
+fn sub_mod_n(a: U256, b: U256) -> U256 {
+    let (difference, borrow) = sub(a, b);
+    if borrow {
+        add(difference, SECP256K1_N).0
+    } else {
+        difference
+    }
+}
+
+/// `a * b mod n` for `a, b < n`, by double-and-add over the bits of `a`.
+fn mul_mod_n(a: U256, b: U256) -> U256 {
+    let mut result = [0u64; 4];
+    for limb in a {
+        for bit in (0..64).rev() {
+            result = add_mod_n(result, result);
+            if (limb >> bit) & 1 == 1 {
+                result = add_mod_n(result, b);
+            }
+        }
+    }
+    result
+}
+
+/// Wrapping 256-bit addition, returning the carry.
+fn add(a: U256, b: U256) -> (U256, bool) {
+    let mut result = [0u64; 4];
+    let mut carry = false;
+    for i in (0..4).rev() {
+        let (sum, overflow_a) = a[i].overflowing_add(b[i]);
+        let (sum, overflow_b) = sum.overflowing_add(carry as u64);
+        result[i] = sum;
+        carry = overflow_a || overflow_b;
+    }
+    (result, carry)
+}
+
+/// Wrapping 256-bit subtraction, returning the borrow.
+fn sub(a: U256, b: U256) -> (U256, bool) {
+    let mut result = [0u64; 4];
+    let mut borrow = false;
+    for i in (0..4).rev() {
+        let (difference, underflow_a) = a[i].overflowing_sub(b[i]);
+        let (difference, underflow_b) = difference.overflowing_sub(borrow as u64);
+        result[i] = difference;
+        borrow = underflow_a || underflow_b;
+    }
+    (result, borrow)
+}
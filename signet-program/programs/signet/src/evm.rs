@@ -39,7 +39,34 @@
 //! ## Transaction Encoding
 //!
 //! EVM transactions must be RLP-encoded before passing to `sign_bidirectional`.
-//! Use libraries like `alloy` or `signet-rs` for encoding.
+//! With the `evm` feature enabled, [`Eip1559Transaction`] does this without
+//! extra dependencies. Libraries like `alloy` or `signet-rs` work as well.
+//!
+//! ### Using the `evm` Feature
+//!
+//! ```toml
+//! chain-signatures-solana-program = { version = "0.4", features = ["cpi", "evm"] }
+//! ```
+//!
+//! ```rust
+//! use chain_signatures::evm::Eip1559Transaction;
+//! # let contract_address = [0x12; 20];
+//! # let call_data = vec![0xa9, 0x05, 0x9c, 0xbb];
+//!
+//! let evm_tx = Eip1559Transaction {
+//!     chain_id: 1,
+//!     nonce: 0,
+//!     max_priority_fee_per_gas: 1_000_000_000,
+//!     max_fee_per_gas: 20_000_000_000,
+//!     gas_limit: 100_000,
+//!     to: Some(contract_address),
+//!     value: 0,
+//!     data: call_data,
+//!     access_list: vec![],
+//! };
+//!
+//! let rlp_encoded = evm_tx.encode_for_signing();
+//! ```
 //!
 //! ### Using signet-rs (Recommended for Solana Programs)
 //!
@@ -92,61 +119,92 @@
 //! // Result: [{"name":"","type":"bool"}]
 //! ```
 //!
+//! ### Building by Hand
+//!
+//! ```rust
+//! use chain_signatures::schema::{AbiParam, AbiType};
+//!
+//! let output_schema = AbiParam::schema_bytes(&[AbiParam::new("", AbiType::Bool)]);
+//! assert_eq!(output_schema, br#"[{"name":"","type":"bool"}]"#);
+//! ```
+//!
 //! ## Respond Serialization Schema (Borsh Format)
 //!
 //! Tells the MPC how to serialize the response for Solana consumption.
 //! The response is Borsh-encoded for efficient on-chain deserialization.
+//! Build it with the [`schema`](crate::schema) types rather than by hand;
+//! `sign_bidirectional` rejects malformed schemas with `InvalidSchema`.
 //!
 //! ### Simple Boolean Response
 //!
 //! For ERC20 `transfer` which returns `bool`:
 //!
-//! ```rust,ignore
-//! // Schema: just the type name as JSON string
-//! let respond_schema = serde_json::to_vec(&serde_json::json!("bool")).unwrap();
-//! // Result: "bool"
+//! ```rust
+//! use anchor_lang::prelude::*;
+//! use chain_signatures::schema::BorshType;
+//!
+//! let respond_schema = BorshType::Bool.schema_bytes();
+//! assert_eq!(respond_schema, br#""bool""#);
 //!
 //! // On-chain deserialization:
-//! let success: bool = BorshDeserialize::try_from_slice(&serialized_output)?;
+//! # let serialized_output = vec![1];
+//! let success = bool::try_from_slice(&serialized_output)?;
+//! # assert!(success);
+//! # Ok::<(), std::io::Error>(())
 //! ```
 //!
 //! ### Struct Response
 //!
-//! For complex return types, define a matching Borsh struct:
+//! For complex return types, define a matching Borsh struct with
+//! [`borsh_schema_struct!`](crate::borsh_schema_struct):
 //!
-//! ```rust,ignore
-//! use borsh::{BorshDeserialize, BorshSerialize, BorshSchema};
+//! ```rust
+//! use anchor_lang::prelude::*;
+//! use chain_signatures::borsh_schema_struct;
+//! use chain_signatures::schema::ToBorshSchema;
 //!
-//! #[derive(BorshDeserialize, BorshSerialize, BorshSchema)]
-//! pub struct SwapResult {
-//!     pub amount_out: u128,
-//!     pub fee: u128,
+//! borsh_schema_struct! {
+//!     #[derive(AnchorDeserialize, AnchorSerialize)]
+//!     pub struct SwapResult {
+//!         pub amount_out: u128,
+//!         pub fee: u128,
+//!     }
 //! }
 //!
-//! // Schema from BorshSchema
-//! let respond_schema = serde_json::to_vec(
-//!     &serde_json::json!([
-//!         {"name": "amount_out", "type": "u128"},
-//!         {"name": "fee", "type": "u128"}
-//!     ])
-//! ).unwrap();
+//! let respond_schema = SwapResult::schema_bytes();
+//! assert_eq!(
+//!     respond_schema,
+//!     br#"{"struct":{"amount_out":"u128","fee":"u128"}}"#
+//! );
 //!
 //! // On-chain deserialization:
-//! let result: SwapResult = BorshDeserialize::try_from_slice(&serialized_output)?;
+//! # let serialized_output = [[7; 16], [1; 16]].concat();
+//! let result = SwapResult::try_from_slice(&serialized_output)?;
+//! # assert_eq!(result.amount_out, u128::from_le_bytes([7; 16]));
+//! # Ok::<(), std::io::Error>(())
 //! ```
 //!
 //! ### Bytes Wrapper Response
 //!
 //! For raw bytes passthrough:
 //!
-//! ```rust,ignore
-//! let respond_schema = serde_json::to_vec(
-//!     &serde_json::json!([{"name": "output", "type": "bytes"}])
-//! ).unwrap();
+//! ```rust
+//! use chain_signatures::schema::BorshType;
+//!
+//! let respond_schema = BorshType::Struct(vec![(
+//!     "output".to_string(),
+//!     BorshType::Vec(Box::new(BorshType::U8)),
+//! )])
+//! .schema_bytes();
 //! ```
 //!
 //! # Complete Integration Example
 //!
+//! The `proxy-test-cpi` program in this repository implements this flow as a
+//! compiled and tested reference: `deposit_erc20` requests the transfer as a
+//! program-owned vault PDA and records it as pending, and `claim_erc20`
+//! verifies the response with [`verify_response`] before crediting it.
+//!
 //! ## Rust (Solana Program with CPI)
 //!
 //! ```rust,ignore
@@ -193,7 +251,7 @@
 //!     let functions = IERC20::abi::functions();
 //!     let transfer_func = functions.get("transfer").unwrap().first().unwrap();
 //!     let output_schema = serde_json::to_vec(&transfer_func.outputs)?;
-//!     let respond_schema = serde_json::to_vec(&serde_json::json!("bool"))?;
+//!     let respond_schema = BorshType::Bool.schema_bytes();
 //!
 //!     // 4. Generate CAIP-2 chain ID
 //!     let caip2_id = format!("eip155:{}", tx_params.chain_id);
@@ -221,61 +279,46 @@
 //!
 //! After the MPC observes the EVM transaction and calls `respond_bidirectional`:
 //!
-//! ```rust,ignore
-//! use borsh::BorshDeserialize;
-//! use anchor_lang::solana_program::keccak;
-//! use anchor_lang::solana_program::secp256k1_recover::secp256k1_recover;
+//! ```rust
+//! use anchor_lang::prelude::*;
+//! use chain_signatures::evm::verify_response;
+//! use chain_signatures::{ExecutionStatus, Signature};
 //!
-//! /// Magic prefix indicating failed EVM transaction
-//! const ERROR_PREFIX: [u8; 4] = [0xDE, 0xAD, 0xBE, 0xEF];
+//! #[error_code]
+//! pub enum ErrorCode {
+//!     EvmTransactionFailed,
+//!     TransferReturnedFalse,
+//! }
 //!
 //! pub fn claim_deposit(
-//!     ctx: Context<ClaimDeposit>,
+//!     response_address: &[u8; 20],
 //!     request_id: [u8; 32],
+//!     status: ExecutionStatus,
 //!     serialized_output: Vec<u8>,
-//!     signature: chain_signatures::Signature,
+//!     revert_data: Option<Vec<u8>>,
+//!     signature: Signature,
 //! ) -> Result<()> {
-//!     // 1. Verify the signature
-//!     let message_hash = hash_response(&request_id, &serialized_output);
-//!     verify_signature(&message_hash, &signature, &ctx.accounts.config.mpc_address)?;
-//!
-//!     // 2. Check for error prefix
-//!     if serialized_output.len() >= 4 && serialized_output[..4] == ERROR_PREFIX {
-//!         // Transaction failed on EVM - handle error case
-//!         return Err(ErrorCode::EvmTransactionFailed.into());
+//!     // 1. Verify the signature (covers status, output and revert data)
+//!     verify_response(
+//!         &request_id,
+//!         status,
+//!         &serialized_output,
+//!         revert_data.as_deref(),
+//!         &signature,
+//!         response_address,
+//!     )?;
+//!
+//!     // 2. Check the execution status
+//!     if status != ExecutionStatus::Success {
+//!         // Transaction reverted, was dropped or was replaced on EVM
+//!         return err!(ErrorCode::EvmTransactionFailed);
 //!     }
 //!
 //!     // 3. Deserialize the result (bool for ERC20 transfer)
-//!     let success: bool = BorshDeserialize::try_from_slice(&serialized_output)?;
+//!     let success = bool::try_from_slice(&serialized_output)?;
 //!     require!(success, ErrorCode::TransferReturnedFalse);
 //!
-//!     // 4. Update on-chain state
-//!     ctx.accounts.user_balance.amount += pending.amount;
-//!
-//!     Ok(())
-//! }
-//!
-//! fn hash_response(request_id: &[u8; 32], output: &[u8]) -> [u8; 32] {
-//!     let mut data = Vec::with_capacity(32 + output.len());
-//!     data.extend_from_slice(request_id);
-//!     data.extend_from_slice(output);
-//!     keccak::hash(&data).to_bytes()
-//! }
-//!
-//! fn verify_signature(
-//!     message_hash: &[u8; 32],
-//!     signature: &chain_signatures::Signature,
-//!     expected_address: &[u8; 20],
-//! ) -> Result<()> {
-//!     let mut sig_bytes = [0u8; 64];
-//!     sig_bytes[..32].copy_from_slice(&signature.big_r.x);
-//!     sig_bytes[32..].copy_from_slice(&signature.s);
-//!
-//!     let recovered = secp256k1_recover(message_hash, signature.recovery_id, &sig_bytes)?;
-//!     let pubkey_hash = keccak::hash(&recovered.to_bytes());
-//!     let address = &pubkey_hash.to_bytes()[12..];
-//!
-//!     require!(address == expected_address, ErrorCode::InvalidSignature);
+//!     // 4. Credit the pending deposit in your program's state
 //!     Ok(())
 //! }
 //! ```
@@ -291,50 +334,43 @@
 //!
 //! # Request ID Generation
 //!
-//! Each request must have a unique ID. Generate it using packed ABI encoding:
-//!
-//! ```rust,ignore
-//! use alloy_sol_types::SolValue;
-//! use anchor_lang::solana_program::keccak;
-//!
-//! fn generate_request_id(
-//!     sender: &Pubkey,
-//!     transaction_data: &[u8],
-//!     caip2_id: &str,
-//!     key_version: u32,
-//!     path: &str,
-//!     algo: &str,
-//!     dest: &str,
-//!     params: &str,
-//! ) -> [u8; 32] {
-//!     let encoded = (
-//!         sender.to_string(),
-//!         transaction_data,
-//!         caip2_id,
-//!         key_version,
-//!         path,
-//!         algo,
-//!         dest,
-//!         params,
-//!     ).abi_encode_packed();
-//!
-//!     keccak::hash(&encoded).to_bytes()
-//! }
+//! Each request must have a unique ID, the keccak256 hash of the packed
+//! encoding of the request fields. [`generate_request_id`] computes it:
+//!
+//! ```rust
+//! use anchor_lang::prelude::Pubkey;
+//! use chain_signatures::evm::{caip2_id, generate_request_id};
+//! # let sender = Pubkey::new_unique();
+//! # let rlp_encoded = vec![0x02, 0xc0];
+//! # let (key_version, path, algo, dest, params) = (0, "", "", "", "");
+//!
+//! let request_id = generate_request_id(
+//!     &sender,
+//!     &rlp_encoded,
+//!     &caip2_id(1),
+//!     key_version,
+//!     &path,
+//!     &algo,
+//!     &dest,
+//!     &params,
+//! );
 //! ```
 //!
 //! # Error Handling
 //!
-//! ## Magic Error Prefix
+//! ## Execution Status
 //!
-//! Failed EVM transactions return output prefixed with `0xDEADBEEF`:
+//! Every response carries a signed [`ExecutionStatus`](crate::ExecutionStatus):
 //!
-//! ```rust,ignore
-//! const ERROR_PREFIX: [u8; 4] = [0xDE, 0xAD, 0xBE, 0xEF];
+//! | Status | Meaning on EVM |
+//! |--------|----------------|
+//! | `Success` | Receipt status 1, `serialized_output` holds the result |
+//! | `Reverted` | Receipt status 0, `revert_data` holds the revert reason if available |
+//! | `Dropped` | The transaction left the mempool without being mined |
+//! | `Replaced` | Another transaction with the same nonce was mined |
 //!
-//! fn is_error_response(output: &[u8]) -> bool {
-//!     output.len() >= 4 && output[..4] == ERROR_PREFIX
-//! }
-//! ```
+//! Output of a non-successful response is empty. Never infer failure from the
+//! output bytes.
 //!
 //! # Response Signature Verification
 //!
@@ -347,7 +383,359 @@
 //!
 //! This means you must derive the expected response public key using:
 //! ```text
-//! epsilon = derive_epsilon(key_version, sender, "solana response key")
+//! epsilon = derive_epsilon(chain_id, sender, "solana response key")
 //! response_pubkey = derive_key(mpc_root_pubkey, epsilon)
 //! response_address = keccak256(response_pubkey)[12..32]
 //! ```
+//!
+//! where `mpc_root_pubkey` is the root key of the request's key version,
+//! read with [`key_version_root_public_key`](crate::key_version_root_public_key)
+//! from the [`KeyVersionRegistry`](crate::KeyVersionRegistry).
+//! [`response_address`] computes this address and [`verify_response`] checks a
+//! response against it. A program that stores the address must derive it again
+//! when the admin rotates the key of that version.
+
+pub use crate::request_id::sign_bidirectional_request_id as generate_request_id;
+
+use crate::derivation::{derive_epsilon, derive_public_key, recover_public_key};
+use crate::response::{bidirectional_response_hash, RESPONSE_KEY_PATH};
+use crate::{ChainSignaturesError, ExecutionStatus, Signature};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+/// CAIP-2 namespace of EVM chains.
+pub const CAIP2_NAMESPACE: &str = "eip155";
+
+/// Transaction type byte of EIP-1559 transactions.
+pub const EIP1559_TX_TYPE: u8 = 0x02;
+
+/// Formats the CAIP-2 id of the EVM chain with the given chain id.
+pub fn caip2_id(chain_id: u64) -> String {
+    format!("{CAIP2_NAMESPACE}:{chain_id}")
+}
+
+/// Entry of an EIP-2930 access list.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessListItem {
+    pub address: [u8; 20],
+    pub storage_keys: Vec<[u8; 32]>,
+}
+
+/// Unsigned EIP-1559 (type 2) transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Eip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub gas_limit: u64,
+    /// Recipient, `None` for contract creation
+    pub to: Option<[u8; 20]>,
+    /// Value in wei
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+}
+
+impl Eip1559Transaction {
+    /// Encodes the transaction as `0x02 || rlp([chain_id, nonce, ...])`, the
+    /// `serialized_transaction` expected by `sign_bidirectional`.
+    pub fn encode_for_signing(&self) -> Vec<u8> {
+        let mut fields = Vec::with_capacity(64 + self.data.len());
+        rlp::uint(&mut fields, self.chain_id.into());
+        rlp::uint(&mut fields, self.nonce.into());
+        rlp::uint(&mut fields, self.max_priority_fee_per_gas);
+        rlp::uint(&mut fields, self.max_fee_per_gas);
+        rlp::uint(&mut fields, self.gas_limit.into());
+        rlp::bytes(&mut fields, self.to.as_ref().map_or(&[][..], |to| &to[..]));
+        rlp::uint(&mut fields, self.value);
+        rlp::bytes(&mut fields, &self.data);
+
+        let mut access_list = Vec::new();
+        for item in &self.access_list {
+            let mut storage_keys = Vec::with_capacity(33 * item.storage_keys.len());
+            for key in &item.storage_keys {
+                rlp::bytes(&mut storage_keys, key);
+            }
+            let mut entry = Vec::with_capacity(21 + 3 + storage_keys.len());
+            rlp::bytes(&mut entry, &item.address);
+            rlp::list(&mut entry, &storage_keys);
+            rlp::list(&mut access_list, &entry);
+        }
+        rlp::list(&mut fields, &access_list);
+
+        let mut encoded = vec![EIP1559_TX_TYPE];
+        rlp::list(&mut encoded, &fields);
+        encoded
+    }
+
+    /// Computes the hash the MPC network signs for this transaction.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak::hash(&self.encode_for_signing()).to_bytes()
+    }
+}
+
+/// Computes the EVM address of an uncompressed public key (`x || y`).
+pub fn public_key_to_address(public_key: &[u8; 64]) -> [u8; 20] {
+    crate::address::evm_address(public_key)
+}
+
+/// Recovers the address that produced `signature` over `message_hash`.
+pub fn recover_address(message_hash: &[u8; 32], signature: &Signature) -> Result<[u8; 20]> {
+    Ok(public_key_to_address(&recover_public_key(
+        message_hash,
+        signature,
+    )?))
+}
+
+/// Checks that `signature` over `message_hash` was produced by
+/// `expected_address`.
+pub fn verify_signature(
+    message_hash: &[u8; 32],
+    signature: &Signature,
+    expected_address: &[u8; 20],
+) -> Result<()> {
+    let address = recover_address(message_hash, signature)?;
+    require!(
+        address == *expected_address,
+        ChainSignaturesError::InvalidSignature
+    );
+    Ok(())
+}
+
+/// Computes the address that signs `sender`'s bidirectional responses.
+///
+/// `root_public_key` is the root key of the request's key version, see
+/// [`key_version_root_public_key`](crate::key_version_root_public_key).
+/// `chain_id` is the CAIP-2 id of the source chain, the `chain_id` of this
+/// program.
+pub fn response_address(
+    root_public_key: &[u8; 64],
+    chain_id: &str,
+    sender: &Pubkey,
+) -> Result<[u8; 20]> {
+    let epsilon = derive_epsilon(chain_id, sender, RESPONSE_KEY_PATH);
+    Ok(public_key_to_address(&derive_public_key(
+        root_public_key,
+        &epsilon,
+    )?))
+}
+
+/// Checks that a `respond_bidirectional` response was signed by
+/// `expected_address`, usually the [`response_address`] of the requester.
+pub fn verify_response(
+    request_id: &[u8; 32],
+    status: ExecutionStatus,
+    serialized_output: &[u8],
+    revert_data: Option<&[u8]>,
+    signature: &Signature,
+    expected_address: &[u8; 20],
+) -> Result<()> {
+    let message_hash =
+        bidirectional_response_hash(request_id, status, serialized_output, revert_data);
+    verify_signature(&message_hash, signature, expected_address)
+}
+
+/// Minimal RLP encoder for the transaction fields.
+mod rlp {
+    /// Appends `value` as a big-endian integer without leading zeros.
+    pub fn uint(out: &mut Vec<u8>, value: u128) {
+        let bytes = value.to_be_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        self::bytes(out, &bytes[start..]);
+    }
+
+    /// Appends a byte string.
+    pub fn bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+        if bytes.len() == 1 && bytes[0] < 0x80 {
+            out.push(bytes[0]);
+        } else {
+            header(out, 0x80, bytes.len());
+            out.extend_from_slice(bytes);
+        }
+    }
+
+    /// Appends a list whose items are already encoded in `payload`.
+    pub fn list(out: &mut Vec<u8>, payload: &[u8]) {
+        header(out, 0xc0, payload.len());
+        out.extend_from_slice(payload);
+    }
+
+    fn header(out: &mut Vec<u8>, offset: u8, len: usize) {
+        if len <= 55 {
+            out.push(offset + len as u8);
+        } else {
+            let len = (len as u64).to_be_bytes();
+            let start = len.iter().position(|b| *b != 0).unwrap_or(len.len());
+            out.push(offset + 55 + (len.len() - start) as u8);
+            out.extend_from_slice(&len[start..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::hex;
+    use crate::AffinePoint;
+
+    fn signature(rs: [u8; 64], recovery_id: u8) -> Signature {
+        Signature {
+            big_r: AffinePoint {
+                x: rs[..32].try_into().unwrap(),
+                y: [0; 32],
+            },
+            s: rs[32..].try_into().unwrap(),
+            recovery_id,
+        }
+    }
+
+    /// Mainnet transaction from the alloy-consensus test suite.
+    fn mainnet_transaction() -> Eip1559Transaction {
+        Eip1559Transaction {
+            chain_id: 1,
+            nonce: 0x42,
+            max_priority_fee_per_gas: 0x3b9aca00,
+            max_fee_per_gas: 0x4a817c800,
+            gas_limit: 44386,
+            to: Some(
+                hex("6069a6c32cf691f5982febae4faf8a6f3ab2f0f6")
+                    .try_into()
+                    .unwrap(),
+            ),
+            value: 0,
+            data: hex(
+                "a22cb4650000000000000000000000005eee75727d804a2b13038928d36f8b188945a57a\
+                 0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            access_list: vec![],
+        }
+    }
+
+    #[test]
+    fn signing_hash_matches_reference_vector() {
+        assert_eq!(
+            mainnet_transaction().signing_hash().to_vec(),
+            hex("0d5688ac3897124635b6cf1bc0e29d6dfebceebdc10a54d74f2ef8b56535b682")
+        );
+    }
+
+    #[test]
+    fn recovers_signer_of_reference_transaction() {
+        let signature = signature(
+            hex(
+                "840cfc572845f5786e702984c2a582528cad4b49b2a10b9db1be7fca90058565\
+                 25e7109ceb98168d95b09b18bbf6b685130e0562f233877d492b94eee0c5b6d1",
+            )
+            .try_into()
+            .unwrap(),
+            0,
+        );
+        let expected = hex("dd6b8b3dc6b7ad97db52f08a275ff4483e024cea")
+            .try_into()
+            .unwrap();
+
+        let hash = mainnet_transaction().signing_hash();
+        assert_eq!(recover_address(&hash, &signature).unwrap(), expected);
+        assert!(verify_signature(&hash, &signature, &expected).is_ok());
+        assert!(verify_signature(&hash, &signature, &[0; 20]).is_err());
+    }
+
+    #[test]
+    fn encodes_access_list_and_contract_creation() {
+        let tx = Eip1559Transaction {
+            chain_id: 1,
+            access_list: vec![AccessListItem {
+                address: [0x11; 20],
+                storage_keys: vec![[0x22; 32]],
+            }],
+            ..Default::default()
+        };
+
+        let mut expected = hex("02f842");
+        expected.extend(hex("01808080808080"));
+        expected.extend(hex("80f838f7"));
+        expected.push(0x94);
+        expected.extend([0x11; 20]);
+        expected.extend(hex("e1a0"));
+        expected.extend([0x22; 32]);
+        assert_eq!(tx.encode_for_signing(), expected);
+    }
+
+    #[test]
+    fn public_key_of_generator_maps_to_known_address() {
+        let generator: [u8; 64] = hex(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+             483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        )
+        .try_into()
+        .unwrap();
+
+        assert_eq!(
+            public_key_to_address(&generator).to_vec(),
+            hex("7e5f4552091a69125d5dfcb7b8c2659029395bdf")
+        );
+    }
+
+    #[test]
+    fn verifies_response_signed_by_response_key() {
+        let chain_id = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+        let sender = Pubkey::new_unique();
+        let request_id = [7u8; 32];
+        let output = [1u8];
+
+        let root_secret = libsecp256k1::SecretKey::parse(&[0x42; 32]).unwrap();
+        let root_public_key: [u8; 64] = libsecp256k1::PublicKey::from_secret_key(&root_secret)
+            .serialize()[1..]
+            .try_into()
+            .unwrap();
+
+        let mut response_secret = root_secret;
+        response_secret
+            .tweak_add_assign(
+                &libsecp256k1::SecretKey::parse(&derive_epsilon(
+                    chain_id,
+                    &sender,
+                    RESPONSE_KEY_PATH,
+                ))
+                .unwrap(),
+            )
+            .unwrap();
+
+        let message_hash =
+            bidirectional_response_hash(&request_id, ExecutionStatus::Success, &output, None);
+        let mut nonce = libsecp256k1::curve::Scalar::default();
+        let _ = nonce.set_b32(&[0x24; 32]);
+        let (r, s, recovery_id) = libsecp256k1::ECMULT_GEN_CONTEXT
+            .sign_raw(
+                &response_secret.into(),
+                &libsecp256k1::Message::parse(&message_hash).0,
+                &nonce,
+            )
+            .unwrap();
+        let mut rs = [0u8; 64];
+        rs[..32].copy_from_slice(&r.b32());
+        rs[32..].copy_from_slice(&s.b32());
+        let signature = signature(rs, recovery_id);
+
+        let address = response_address(&root_public_key, chain_id, &sender).unwrap();
+        assert!(verify_response(
+            &request_id,
+            ExecutionStatus::Success,
+            &output,
+            None,
+            &signature,
+            &address,
+        )
+        .is_ok());
+        assert!(verify_response(
+            &request_id,
+            ExecutionStatus::Reverted,
+            &output,
+            None,
+            &signature,
+            &address,
+        )
+        .is_err());
+    }
+}
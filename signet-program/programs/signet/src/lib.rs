@@ -1,8 +1,27 @@
 #![doc = include_str!("../README.md")]
 #![allow(unexpected_cfgs)]
+// Anchor's `#[program]` emits its IDL instruction handlers in a crate-root
+// `__private` module that calls the deprecated `AccountInfo::realloc`. That
+// module is a sibling of the program module, so this is the narrowest scope
+// the allow can take.
+#![allow(deprecated)]
+#![allow(clippy::too_many_arguments)]
 
+pub mod address;
+pub mod bitcoin;
+pub mod derivation;
+#[cfg(feature = "evm")]
 pub mod evm;
+pub mod output;
+pub mod request_id;
+pub mod response;
+pub mod schema;
+pub mod solana_dest;
+pub mod substrate;
+#[cfg(test)]
+mod test_utils;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
 
 declare_id!("SigMcRMjKfnC7RDG5q4yUMZM1s5KJ9oYTPP4NmJRDRw");
 
@@ -26,11 +45,18 @@ pub mod chain_signatures {
     ///
     /// * `program_state` - PDA to store program configuration
     /// * `admin` - Admin account (becomes program admin)
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::ChainIdTooLong`] - `chain_id` is longer than [`MAX_CHAIN_ID_LEN`]
+    /// * [`ChainSignaturesError::InvalidChainId`] - `chain_id` is not a CAIP-2 id
     pub fn initialize(
         ctx: Context<Initialize>,
         signature_deposit: u64,
         chain_id: String,
     ) -> Result<()> {
+        validate_chain_id(&chain_id)?;
+
         let program_state = &mut ctx.accounts.program_state;
         program_state.admin = ctx.accounts.admin.key();
         program_state.signature_deposit = signature_deposit;
@@ -66,6 +92,43 @@ pub mod chain_signatures {
         Ok(())
     }
 
+    /// Update the CAIP-2 chain identifier of this chain.
+    ///
+    /// The chain id is part of every key derivation and request id, so changing
+    /// it moves all derived addresses. It is meant to correct a wrong id before
+    /// the program is in use.
+    ///
+    /// # Admin Only
+    ///
+    /// This instruction is restricted to the program administrator and is **not intended
+    /// for application developers**. It is used for program maintenance.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_id` - New CAIP-2 chain identifier
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::ChainIdTooLong`] - `chain_id` is longer than [`MAX_CHAIN_ID_LEN`]
+    /// * [`ChainSignaturesError::InvalidChainId`] - `chain_id` is not a CAIP-2 id
+    ///
+    /// # Emits
+    ///
+    /// * [`ChainIdUpdatedEvent`]
+    pub fn update_chain_id(ctx: Context<AdminOnly>, chain_id: String) -> Result<()> {
+        validate_chain_id(&chain_id)?;
+
+        let program_state = &mut ctx.accounts.program_state;
+        let old_chain_id = std::mem::replace(&mut program_state.chain_id, chain_id.clone());
+
+        emit!(ChainIdUpdatedEvent {
+            old_chain_id,
+            new_chain_id: chain_id,
+        });
+
+        Ok(())
+    }
+
     /// Withdraw accumulated funds from the program.
     ///
     /// # Admin Only
@@ -112,20 +175,269 @@ pub mod chain_signatures {
         Ok(())
     }
 
+    /// Set the MPC root public key that response signatures are verified against.
+    ///
+    /// # Admin Only
+    ///
+    /// This instruction is restricted to the program administrator and is **not intended
+    /// for application developers**. It is used for program maintenance.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - Uncompressed secp256k1 root public key (`x || y`, without `0x04` prefix)
+    ///
+    /// # Emits
+    ///
+    /// * [`RootPublicKeyUpdatedEvent`]
+    pub fn set_root_public_key(ctx: Context<SetRootPublicKey>, public_key: [u8; 64]) -> Result<()> {
+        let root_public_key = &mut ctx.accounts.root_public_key;
+        let old_public_key = root_public_key.public_key;
+        root_public_key.public_key = public_key;
+
+        emit!(RootPublicKeyUpdatedEvent {
+            old_public_key,
+            new_public_key: public_key,
+        });
+
+        Ok(())
+    }
+
+    /// Register or update an MPC key version in the [`KeyVersionRegistry`].
+    ///
+    /// Once the registry exists, [`sign`] and [`sign_bidirectional`] only accept
+    /// registered versions that are not retired, and [`respond`] verifies
+    /// signatures against the root public key of the request's version.
+    ///
+    /// # Admin Only
+    ///
+    /// This instruction is restricted to the program administrator and is **not intended
+    /// for application developers**. It is used for key rotation.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_version` - MPC key version
+    /// * `public_key` - Uncompressed secp256k1 root public key of the version (`x || y`)
+    /// * `active` - Whether the version accepts new requests
+    /// * `deprecation_slot` - Slot from which the version no longer accepts new requests
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::KeyVersionRegistryFull`] - [`MAX_KEY_VERSIONS`] versions are already registered
+    ///
+    /// # Emits
+    ///
+    /// * [`KeyVersionUpdatedEvent`]
+    pub fn set_key_version(
+        ctx: Context<SetKeyVersion>,
+        key_version: u32,
+        public_key: [u8; 64],
+        active: bool,
+        deprecation_slot: Option<u64>,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.key_version_registry;
+        let entry = KeyVersionEntry {
+            key_version,
+            public_key,
+            active,
+            deprecation_slot,
+        };
+
+        match registry
+            .versions
+            .iter_mut()
+            .find(|existing| existing.key_version == key_version)
+        {
+            Some(existing) => *existing = entry,
+            None => {
+                require!(
+                    registry.versions.len() < MAX_KEY_VERSIONS,
+                    ChainSignaturesError::KeyVersionRegistryFull
+                );
+                registry.versions.push(entry);
+            }
+        }
+
+        emit!(KeyVersionUpdatedEvent {
+            key_version,
+            public_key,
+            active,
+            deprecation_slot,
+        });
+
+        Ok(())
+    }
+
+    /// Set the request rate limit applied to every requester.
+    ///
+    /// Each requester may make at most `max_requests` [`sign`] and
+    /// [`sign_bidirectional`] requests per window of `window_slots` slots, and
+    /// all requesters together at most `global_max_requests`. Requests made
+    /// through CPI are counted against their requester like direct ones. A
+    /// limit of zero disables that limit.
+    ///
+    /// # Admin Only
+    ///
+    /// This instruction is restricted to the program administrator and is **not intended
+    /// for application developers**. It is used for program maintenance.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_requests` - Requests allowed per requester per window, zero for no limit
+    /// * `window_slots` - Window length in slots
+    /// * `global_max_requests` - Requests allowed across all requesters per
+    ///   window, zero for no limit
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidRateLimit`] - Empty window with a non-zero limit
+    ///
+    /// # Emits
+    ///
+    /// * [`RateLimitUpdatedEvent`]
+    pub fn set_rate_limit(
+        ctx: Context<SetRateLimit>,
+        max_requests: u32,
+        window_slots: u64,
+        global_max_requests: u32,
+    ) -> Result<()> {
+        require!(
+            (max_requests == 0 && global_max_requests == 0) || window_slots > 0,
+            ChainSignaturesError::InvalidRateLimit
+        );
+
+        let config = &mut ctx.accounts.rate_limit_config;
+        config.max_requests = max_requests;
+        config.window_slots = window_slots;
+        config.global_max_requests = global_max_requests;
+
+        emit!(RateLimitUpdatedEvent {
+            max_requests,
+            window_slots,
+            global_max_requests,
+        });
+
+        Ok(())
+    }
+
+    /// Enable or disable permissioned mode.
+    ///
+    /// In permissioned mode [`sign`] and [`sign_bidirectional`] only accept
+    /// requests from allowlisted requesters (see [`add_to_allowlist`]),
+    /// whether made directly or through CPI.
+    ///
+    /// # Admin Only
+    ///
+    /// This instruction is restricted to the program administrator and is **not intended
+    /// for application developers**. It is used for program maintenance.
+    ///
+    /// # Arguments
+    ///
+    /// * `permissioned` - Whether requests are restricted to the allowlist
+    ///
+    /// # Emits
+    ///
+    /// * [`PermissionedModeUpdatedEvent`]
+    pub fn set_permissioned(ctx: Context<AdminOnly>, permissioned: bool) -> Result<()> {
+        ctx.accounts.program_state.permissioned = permissioned;
+
+        emit!(PermissionedModeUpdatedEvent { permissioned });
+
+        Ok(())
+    }
+
+    /// Allow a requester to make requests in permissioned mode.
+    ///
+    /// # Admin Only
+    ///
+    /// This instruction is restricted to the program administrator and is **not intended
+    /// for application developers**. It is used for program maintenance.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Requester address, a PDA for program-owned requesters
+    ///
+    /// # Emits
+    ///
+    /// * [`AllowlistUpdatedEvent`]
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, key: Pubkey) -> Result<()> {
+        ctx.accounts.allowlist_entry.key = key;
+
+        emit!(AllowlistUpdatedEvent { key, allowed: true });
+
+        Ok(())
+    }
+
+    /// Remove a requester from the allowlist, refunding the
+    /// entry's rent to the admin.
+    ///
+    /// # Admin Only
+    ///
+    /// This instruction is restricted to the program administrator and is **not intended
+    /// for application developers**. It is used for program maintenance.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Requester address, a PDA for program-owned requesters
+    ///
+    /// # Emits
+    ///
+    /// * [`AllowlistUpdatedEvent`]
+    pub fn remove_from_allowlist(_ctx: Context<RemoveFromAllowlist>, key: Pubkey) -> Result<()> {
+        emit!(AllowlistUpdatedEvent {
+            key,
+            allowed: false,
+        });
+
+        Ok(())
+    }
+
     /// Request a signature from the MPC network on a 32-byte payload.
     ///
     /// The payload is typically a transaction hash that needs to be signed.
     /// The MPC network will respond with a signature via [`respond`].
     ///
+    /// When `signature_request` is passed, the request is tracked in a
+    /// [`SignatureRequest`] PDA keyed by its request id (see [`crate::request_id`]),
+    /// paid for by the fee payer on top of the deposit. [`respond`] uses it to
+    /// verify the returned signature on-chain and then closes it, refunding the
+    /// rent to the fee payer less the rent of the [`ResponseRecord`] it funds.
+    /// Signing the same request again while it is still pending, e.g. after a
+    /// [`respond_error`] or a timeout, retries it and keeps the existing PDA.
+    /// Once [`respond`] has answered the request, its [`ResponseRecord`]
+    /// rejects signing it again, so a tracked request must pass
+    /// `response_record` as well. Requests without `signature_request` are not
+    /// verified on-chain.
+    ///
+    /// # Program-Owned Keys
+    ///
+    /// The requester may be a PDA that the calling program signs for with
+    /// `invoke_signed`. The derived key then belongs to the program, and no user can
+    /// request signatures for it directly (see [`crate::address::program_derived_public_key`]).
+    /// A PDA requester holding data cannot pay the deposit, so such requests must
+    /// pass a `fee_payer`.
+    ///
     /// # Arguments
     ///
     /// * `payload` - 32-byte data to sign (typically a transaction hash)
-    /// * `key_version` - MPC key version to use
+    /// * `key_version` - MPC key version to use, see [`KeyVersionRegistry`]
     /// * `path` - Derivation path for the user's key (e.g., `"my_wallet"`)
     /// * `algo` - Reserved for future use (pass empty string `""`)
     /// * `dest` - Reserved for future use (pass empty string `""`)
     /// * `params` - Reserved for future use (pass empty string `""`)
     ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InsufficientDeposit`] - Insufficient deposit, including
+    ///   the rent of a new `signature_request`
+    /// * [`ChainSignaturesError::UnknownKeyVersion`] - `key_version` is not registered
+    /// * [`ChainSignaturesError::RetiredKeyVersion`] - `key_version` no longer accepts requests
+    /// * [`ChainSignaturesError::NotAllowlisted`] - Permissioned mode and the requester is not allowlisted
+    /// * [`ChainSignaturesError::RateLimited`] - The requester, or all requesters together, exceeded the rate limit, see [`set_rate_limit`]
+    /// * [`ChainSignaturesError::InvalidRequestAccount`] - `signature_request` is not the request PDA
+    /// * [`ChainSignaturesError::InvalidResponseRecord`] - `response_record` is missing for a
+    ///   tracked request, or is not the record PDA
+    /// * [`ChainSignaturesError::AlreadyResponded`] - The request was already answered
+    ///
     /// # Emits
     ///
     /// * [`SignatureRequestedEvent`]
@@ -163,13 +475,33 @@ pub mod chain_signatures {
             None => requester.to_account_info(),
         };
 
+        let tracking_rent = match &ctx.accounts.signature_request {
+            Some(account) if account.owner != &crate::ID => {
+                Rent::get()?.minimum_balance(SignatureRequest::SPACE)
+            }
+            _ => 0,
+        };
         require!(
-            payer.lamports() >= program_state.signature_deposit,
+            payer.lamports() >= program_state.signature_deposit + tracking_rent,
             ChainSignaturesError::InsufficientDeposit
         );
+        check_key_version(&ctx.accounts.key_version_registry, key_version)?;
+        let top_level_program = top_level_program(ctx.accounts.instructions.as_ref())?;
+        check_allowlist(
+            program_state,
+            ctx.accounts.allowlist_entry.as_deref(),
+            requester.key,
+        )?;
+        enforce_rate_limit(
+            &ctx.accounts.rate_limit_config,
+            ctx.accounts.rate_limit.as_deref(),
+            requester.key,
+            &payer,
+            &system_program.to_account_info(),
+        )?;
 
         let transfer_instruction = anchor_lang::system_program::Transfer {
-            from: payer,
+            from: payer.clone(),
             to: program_state.to_account_info(),
         };
 
@@ -178,7 +510,45 @@ pub mod chain_signatures {
             program_state.signature_deposit,
         )?;
 
-        emit_cpi!(SignatureRequestedEvent {
+        let request_id = request_id::sign_request_id(
+            requester.key,
+            &payload,
+            &path,
+            key_version,
+            &program_state.chain_id,
+            &algo,
+            &dest,
+            &params,
+        );
+        if let Some(response_record) = &ctx.accounts.response_record {
+            check_not_responded(&request_id, response_record)?;
+        }
+        if let Some(signature_request) = &ctx.accounts.signature_request {
+            require!(
+                ctx.accounts.response_record.is_some(),
+                ChainSignaturesError::InvalidResponseRecord
+            );
+            track_signature_request(
+                &request_id,
+                signature_request,
+                &payer,
+                &system_program.to_account_info(),
+                SignatureRequest {
+                    sender: *requester.key,
+                    rent_payer: *payer.key,
+                    payload,
+                    key_version,
+                    epsilon: derivation::derive_epsilon(
+                        &program_state.chain_id,
+                        requester.key,
+                        &path,
+                    ),
+                    bump: 0,
+                },
+            )?;
+        }
+
+        let event = SignatureRequestedEvent {
             sender: *requester.key,
             payload,
             key_version,
@@ -188,11 +558,12 @@ pub mod chain_signatures {
             algo,
             dest,
             params,
-            fee_payer: match &ctx.accounts.fee_payer {
-                Some(payer) => Some(*payer.key),
-                None => None,
-            },
-        });
+            fee_payer: ctx.accounts.fee_payer.as_ref().map(|payer| *payer.key),
+            top_level_program,
+        };
+        #[cfg(feature = "log-events")]
+        emit!(event);
+        emit_cpi!(event);
 
         Ok(())
     }
@@ -207,11 +578,14 @@ pub mod chain_signatures {
     ///
     /// Chain-agnostic lifecycle reference: <https://docs.sig.network/architecture/sign-bidirectional>
     ///
+    /// The requester may be a program PDA signing through `invoke_signed`, as
+    /// described for [`sign`].
+    ///
     /// # Arguments
     ///
     /// * `serialized_transaction` - serialized unsigned transaction for destination chain
     /// * `caip2_id` - CAIP-2 chain identifier (e.g., `"eip155:1"` for Ethereum mainnet)
-    /// * `key_version` - MPC key version to use
+    /// * `key_version` - MPC key version to use, see [`KeyVersionRegistry`]
     /// * `path` - Derivation path for signing key
     /// * `algo` - Reserved for future use (pass empty string `""`)
     /// * `dest` - Reserved for future use (pass empty string `""`)
@@ -227,7 +601,15 @@ pub mod chain_signatures {
     /// # Errors
     ///
     /// * [`ChainSignaturesError::InvalidTransaction`] - Empty transaction data
+    /// * [`ChainSignaturesError::ChainIdTooLong`] - `caip2_id` is longer than [`MAX_CHAIN_ID_LEN`]
+    /// * [`ChainSignaturesError::InvalidChainId`] - `caip2_id` is not a CAIP-2 id
+    /// * [`ChainSignaturesError::RequestTooLarge`] - The transaction and schemas add up to more than [`MAX_REQUEST_BUFFER_LEN`]
     /// * [`ChainSignaturesError::InsufficientDeposit`] - Insufficient deposit
+    /// * [`ChainSignaturesError::UnknownKeyVersion`] - `key_version` is not registered
+    /// * [`ChainSignaturesError::RetiredKeyVersion`] - `key_version` no longer accepts requests
+    /// * [`ChainSignaturesError::NotAllowlisted`] - Permissioned mode and the requester is not allowlisted
+    /// * [`ChainSignaturesError::RateLimited`] - The requester, or all requesters together, exceeded the rate limit, see [`set_rate_limit`]
+    /// * [`ChainSignaturesError::InvalidSchema`] - Malformed schema, see [`schema`]
     pub fn sign_bidirectional(
         ctx: Context<SignBidirectional>,
         serialized_transaction: Vec<u8>,
@@ -254,11 +636,28 @@ pub mod chain_signatures {
             payer.lamports() >= program_state.signature_deposit,
             ChainSignaturesError::InsufficientDeposit
         );
+        check_key_version(&ctx.accounts.key_version_registry, key_version)?;
+        let top_level_program = top_level_program(ctx.accounts.instructions.as_ref())?;
+        check_allowlist(
+            program_state,
+            ctx.accounts.allowlist_entry.as_deref(),
+            requester.key,
+        )?;
+        enforce_rate_limit(
+            &ctx.accounts.rate_limit_config,
+            ctx.accounts.rate_limit.as_deref(),
+            requester.key,
+            &payer,
+            &system_program.to_account_info(),
+        )?;
 
-        require!(
-            !serialized_transaction.is_empty(),
-            ChainSignaturesError::InvalidTransaction
-        );
+        check_bidirectional_payload(
+            &serialized_transaction,
+            &caip2_id,
+            &output_deserialization_schema,
+            &respond_serialization_schema,
+            |result| result,
+        )?;
 
         let transfer_instruction = anchor_lang::system_program::Transfer {
             from: payer,
@@ -270,7 +669,7 @@ pub mod chain_signatures {
             program_state.signature_deposit,
         )?;
 
-        emit_cpi!(SignBidirectionalEvent {
+        let event = SignBidirectionalEvent {
             sender: *requester.key,
             serialized_transaction,
             caip2_id,
@@ -282,112 +681,578 @@ pub mod chain_signatures {
             params,
             program_id,
             output_deserialization_schema,
-            respond_serialization_schema
-        });
+            respond_serialization_schema,
+            top_level_program,
+            chain_id: program_state.chain_id.clone(),
+        };
+        #[cfg(feature = "log-events")]
+        emit!(event);
+        emit_cpi!(event);
 
         Ok(())
     }
 
-    /// Respond to signature requests with generated signatures.
+    /// Quote a [`sign_bidirectional`] request without submitting it. View
+    /// function, meant to be simulated: nothing is transferred or counted
+    /// against the rate limit.
     ///
-    /// Called by MPC responders after signature generation. Supports batched
-    /// requests where each signature is linked to its request via `request_id`.
+    /// Takes the arguments of [`sign_bidirectional`] and runs its checks
+    /// against the same accounts, with `requester` and `fee_payer` read
+    /// without signing. `_program_id` plays no part in the checks or the
+    /// request id.
     ///
-    /// # Security Note
+    /// # Returns
+    ///
+    /// [`SignBidirectionalQuote`] with the deposit, the request id and the
+    /// errors of every check the request fails.
+    pub fn quote_sign_bidirectional(
+        ctx: Context<QuoteSignBidirectional>,
+        serialized_transaction: Vec<u8>,
+        caip2_id: String,
+        key_version: u32,
+        path: String,
+        algo: String,
+        dest: String,
+        params: String,
+        _program_id: Pubkey,
+        output_deserialization_schema: Vec<u8>,
+        respond_serialization_schema: Vec<u8>,
+    ) -> Result<SignBidirectionalQuote> {
+        let program_state = &ctx.accounts.program_state;
+        let requester = &ctx.accounts.requester;
+        let mut failures = vec![];
+
+        let payer = match &ctx.accounts.fee_payer {
+            Some(fee_payer) => fee_payer.to_account_info(),
+            None => requester.to_account_info(),
+        };
+
+        if payer.lamports() < program_state.signature_deposit {
+            failures.push(ChainSignaturesError::InsufficientDeposit);
+        }
+        record_failure(
+            check_key_version(&ctx.accounts.key_version_registry, key_version),
+            &mut failures,
+        )?;
+        record_failure(
+            check_allowlist(
+                program_state,
+                ctx.accounts.allowlist_entry.as_deref(),
+                requester.key,
+            ),
+            &mut failures,
+        )?;
+        record_failure(
+            check_rate_limit(
+                &ctx.accounts.rate_limit_config,
+                ctx.accounts.rate_limit.as_deref(),
+                requester.key,
+            ),
+            &mut failures,
+        )?;
+        check_bidirectional_payload(
+            &serialized_transaction,
+            &caip2_id,
+            &output_deserialization_schema,
+            &respond_serialization_schema,
+            |result| record_failure(result, &mut failures),
+        )?;
+
+        Ok(SignBidirectionalQuote {
+            deposit: program_state.signature_deposit,
+            request_id: request_id::sign_bidirectional_request_id(
+                requester.key,
+                &serialized_transaction,
+                &caip2_id,
+                key_version,
+                &path,
+                &algo,
+                &dest,
+                &params,
+            ),
+            failures,
+        })
+    }
+
+    /// Create a [`RequestBuffer`] to upload a request too large for
+    /// [`sign_bidirectional`] instruction data.
     ///
-    /// **Any address can call this function.** Clients must verify signature
-    /// validity off-chain before trusting the response.
+    /// The buffer holds the serialized transaction followed by the output and
+    /// respond schemas. It is filled with [`write_request_buffer`] and consumed
+    /// by [`sign_bidirectional_from_buffer`], which closes it and refunds the
+    /// rent to `payer`. [`close_request_buffer`] abandons it instead.
     ///
     /// # Arguments
     ///
-    /// * `request_ids` - Array of 32-byte request identifiers
-    /// * `signatures` - Corresponding ECDSA signatures
+    /// * `buffer_id` - Identifier distinguishing concurrent buffers of the same owner
+    /// * `transaction_len` - Length of the serialized transaction in bytes
+    /// * `output_deserialization_schema_len` - Length of the output schema in bytes
+    /// * `respond_serialization_schema_len` - Length of the respond schema in bytes
     ///
-    /// # Emits
+    /// # Errors
     ///
-    /// * [`SignatureRespondedEvent`] for each signature
-    pub fn respond(
-        ctx: Context<Respond>,
-        request_ids: Vec<[u8; 32]>,
-        signatures: Vec<Signature>,
+    /// * [`ChainSignaturesError::RequestBufferTooLarge`] - The lengths add up to more than [`MAX_REQUEST_BUFFER_LEN`]
+    pub fn init_request_buffer(
+        ctx: Context<InitRequestBuffer>,
+        buffer_id: u64,
+        transaction_len: u32,
+        output_deserialization_schema_len: u32,
+        respond_serialization_schema_len: u32,
     ) -> Result<()> {
+        let len = transaction_len as usize
+            + output_deserialization_schema_len as usize
+            + respond_serialization_schema_len as usize;
         require!(
-            request_ids.len() == signatures.len(),
-            ChainSignaturesError::InvalidInputLength
+            len <= MAX_REQUEST_BUFFER_LEN,
+            ChainSignaturesError::RequestBufferTooLarge
         );
 
-        for i in 0..request_ids.len() {
-            emit_cpi!(SignatureRespondedEvent {
-                request_id: request_ids[i],
-                responder: *ctx.accounts.responder.key,
-                signature: signatures[i].clone(),
-            });
-        }
+        let request_buffer = &ctx.accounts.request_buffer;
+        create_pda_account(
+            &ctx.accounts.payer,
+            request_buffer,
+            &ctx.accounts.system_program,
+            RequestBuffer::space(len),
+            &[
+                REQUEST_BUFFER_SEED,
+                ctx.accounts.owner.key.as_ref(),
+                &buffer_id.to_le_bytes(),
+                &[ctx.bumps.request_buffer],
+            ],
+        )?;
+
+        let buffer = RequestBuffer {
+            rent_payer: *ctx.accounts.payer.key,
+            bump: ctx.bumps.request_buffer,
+            transaction_len,
+            output_deserialization_schema_len,
+            respond_serialization_schema_len,
+            data: vec![0; len],
+        };
+        buffer.try_serialize(&mut &mut request_buffer.try_borrow_mut_data()?[..])?;
 
         Ok(())
     }
 
-    /// Report signature generation errors from the MPC network.
-    ///
-    /// # Warning: Debugging Only
-    ///
-    /// This function is **solely for debugging purposes** and should not be used
-    /// in production or relied upon for any business logic. Error events are
-    /// informational only and are not cryptographically verified.
-    ///
-    /// # Security Note
+    /// Write `bytes` into a [`RequestBuffer`] at `offset`.
     ///
-    /// **Any address can call this function.** Do not rely on error events
-    /// for business logic decisions.
+    /// Offsets index the concatenation of the serialized transaction, the
+    /// output schema and the respond schema. Bytes that are never written stay
+    /// zero.
     ///
     /// # Arguments
     ///
-    /// * `errors` - Array of error responses with request IDs and messages
+    /// * `buffer_id` - Identifier passed to [`init_request_buffer`]
+    /// * `offset` - Position of the first byte within the buffer
+    /// * `bytes` - Bytes to write
     ///
-    /// # Emits
+    /// # Errors
     ///
-    /// * [`SignatureErrorEvent`] for each error
-    pub fn respond_error(ctx: Context<RespondError>, errors: Vec<ErrorResponse>) -> Result<()> {
-        for error in errors {
-            emit!(SignatureErrorEvent {
-                request_id: error.request_id,
-                responder: *ctx.accounts.responder.key,
-                error: error.error_message,
-            });
-        }
+    /// * [`ChainSignaturesError::InvalidRequestBuffer`] - `request_buffer` is not the owner's buffer `buffer_id`
+    /// * [`ChainSignaturesError::RequestBufferOverflow`] - The write extends past the end of the buffer
+    pub fn write_request_buffer(
+        ctx: Context<WriteRequestBuffer>,
+        buffer_id: u64,
+        offset: u32,
+        bytes: Vec<u8>,
+    ) -> Result<()> {
+        let request_buffer = &ctx.accounts.request_buffer;
+        let buffer = RequestBuffer::load_header(request_buffer, ctx.accounts.owner.key, buffer_id)?;
+
+        let range = (offset as usize)..(offset as usize + bytes.len());
+        require!(
+            range.end <= buffer.payload_len(),
+            ChainSignaturesError::RequestBufferOverflow
+        );
+        request_buffer.try_borrow_mut_data()?[RequestBuffer::PAYLOAD_OFFSET..][range]
+            .copy_from_slice(&bytes);
 
         Ok(())
     }
 
-    /// Get the current signature deposit amount. View function.
+    /// Close a [`RequestBuffer`] that will not be consumed, refunding its rent
+    /// to the `rent_payer` that created it.
     ///
-    /// # Returns
+    /// Abandoned uploads otherwise keep their rent locked, since only
+    /// [`sign_bidirectional_from_buffer`] closes a buffer.
     ///
-    /// Current signature deposit in lamports.
-    pub fn get_signature_deposit(ctx: Context<GetSignatureDeposit>) -> Result<u64> {
-        let program_state = &ctx.accounts.program_state;
-        Ok(program_state.signature_deposit)
+    /// # Arguments
+    ///
+    /// * `buffer_id` - Identifier passed to [`init_request_buffer`]
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidRequestBuffer`] - `request_buffer` is not the owner's buffer `buffer_id`
+    /// * [`ChainSignaturesError::InvalidRentPayer`] - `rent_payer` is not the buffer's `rent_payer`
+    pub fn close_request_buffer(ctx: Context<CloseRequestBuffer>, buffer_id: u64) -> Result<()> {
+        let request_buffer = &ctx.accounts.request_buffer;
+        let buffer = RequestBuffer::load_header(request_buffer, ctx.accounts.owner.key, buffer_id)?;
+        require_keys_eq!(
+            *ctx.accounts.rent_payer.key,
+            buffer.rent_payer,
+            ChainSignaturesError::InvalidRentPayer
+        );
+
+        close_pda_account(request_buffer, &ctx.accounts.rent_payer)
     }
 
-    /// Finalize a bidirectional flow with execution results from the destination chain.
+    /// Request a bidirectional signature like [`sign_bidirectional`], reading
+    /// the serialized transaction and schemas from a [`RequestBuffer`].
     ///
-    /// Called by MPC responders after observing transaction confirmation on the
-    /// destination chain. The signature proves the authenticity of the output.
+    /// The buffer is closed and its rent refunded to the `rent_payer` that
+    /// created it.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - Identifier passed to [`init_request_buffer`]
+    ///
+    /// The other arguments are those of [`sign_bidirectional`].
+    ///
+    /// # Emits
+    ///
+    /// * [`SignBidirectionalEvent`]
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidRequestBuffer`] - `request_buffer` is not the requester's buffer `buffer_id`
+    /// * [`ChainSignaturesError::InvalidRentPayer`] - `rent_payer` is not the buffer's `rent_payer`
+    ///
+    /// And the errors of [`sign_bidirectional`].
+    pub fn sign_bidirectional_from_buffer(
+        ctx: Context<SignBidirectionalFromBuffer>,
+        buffer_id: u64,
+        caip2_id: String,
+        key_version: u32,
+        path: String,
+        algo: String,
+        dest: String,
+        params: String,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        let program_state = &ctx.accounts.program_state;
+        let requester = &ctx.accounts.requester;
+        let system_program = &ctx.accounts.system_program;
+
+        let payer = match &ctx.accounts.fee_payer {
+            Some(fee_payer) => fee_payer.to_account_info(),
+            None => requester.to_account_info(),
+        };
+
+        require!(
+            payer.lamports() >= program_state.signature_deposit,
+            ChainSignaturesError::InsufficientDeposit
+        );
+        check_key_version(&ctx.accounts.key_version_registry, key_version)?;
+        let top_level_program = top_level_program(ctx.accounts.instructions.as_ref())?;
+        check_allowlist(
+            program_state,
+            ctx.accounts.allowlist_entry.as_deref(),
+            requester.key,
+        )?;
+        enforce_rate_limit(
+            &ctx.accounts.rate_limit_config,
+            ctx.accounts.rate_limit.as_deref(),
+            requester.key,
+            &payer,
+            &system_program.to_account_info(),
+        )?;
+
+        let request_buffer = &ctx.accounts.request_buffer;
+        let buffer = RequestBuffer::load_header(request_buffer, requester.key, buffer_id)?;
+        require_keys_eq!(
+            *ctx.accounts.rent_payer.key,
+            buffer.rent_payer,
+            ChainSignaturesError::InvalidRentPayer
+        );
+
+        // The payload is validated in place and copied only into the event.
+        let (serialized_transaction, output_deserialization_schema, respond_serialization_schema) = {
+            let data = request_buffer.try_borrow_data()?;
+            let (
+                serialized_transaction,
+                output_deserialization_schema,
+                respond_serialization_schema,
+            ) = buffer.split_payload(&data[RequestBuffer::PAYLOAD_OFFSET..]);
+
+            check_bidirectional_payload(
+                serialized_transaction,
+                &caip2_id,
+                output_deserialization_schema,
+                respond_serialization_schema,
+                |result| result,
+            )?;
+
+            (
+                serialized_transaction.to_vec(),
+                output_deserialization_schema.to_vec(),
+                respond_serialization_schema.to_vec(),
+            )
+        };
+        close_pda_account(request_buffer, &ctx.accounts.rent_payer)?;
+
+        let transfer_instruction = anchor_lang::system_program::Transfer {
+            from: payer,
+            to: program_state.to_account_info(),
+        };
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(system_program.to_account_info(), transfer_instruction),
+            program_state.signature_deposit,
+        )?;
+
+        let event = SignBidirectionalEvent {
+            sender: *requester.key,
+            serialized_transaction,
+            caip2_id,
+            key_version,
+            deposit: program_state.signature_deposit,
+            path,
+            algo,
+            dest,
+            params,
+            program_id,
+            output_deserialization_schema,
+            respond_serialization_schema,
+            top_level_program,
+            chain_id: program_state.chain_id.clone(),
+        };
+        #[cfg(feature = "log-events")]
+        emit!(event);
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    /// Respond to signature requests with generated signatures.
+    ///
+    /// Called by MPC responders after signature generation. Supports batched
+    /// requests where each signature is linked to its request via `request_id`.
+    ///
+    /// Signatures for requests made through [`sign`] are verified on-chain: the
+    /// signature must recover, over the tracked payload, to the key derived from
+    /// the root public key and the request's sender and path. The root public key
+    /// is the [`KeyVersionRegistry`] entry of the request's key version, or the
+    /// [`RootPublicKey`] for versions that are not registered.
+    ///
+    /// A verified signature creates a [`ResponseRecord`] PDA for the request id,
+    /// and any later `respond` or [`sign`] for the same id is rejected. Only
+    /// verified signatures are recorded, so a junk response sent before the
+    /// request lands cannot block the genuine one. The record is funded from
+    /// the rent of the [`SignatureRequest`], which the requester paid at sign
+    /// time, so responders pay nothing. The [`SignatureRequest`] is then closed
+    /// and the rest of its rent refunded to the `rent_payer` it recorded.
+    ///
+    /// # Security Note
+    ///
+    /// **Any address can call this function.** Signatures for requests without a
+    /// tracked [`SignatureRequest`] (e.g. [`sign_bidirectional`]) are neither
+    /// verified nor deduplicated on-chain: recording them would let anyone
+    /// block a request with a junk response. Clients must verify them
+    /// off-chain and keep the first valid response.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_ids` - Array of 32-byte request identifiers
+    /// * `signatures` - Corresponding ECDSA signatures
+    ///
+    /// # Remaining Accounts
+    ///
+    /// Three accounts per request id, in the same order as `request_ids`:
+    ///
+    /// 1. The writable [`SignatureRequest`] PDA (seeds: `[b"request", request_id]`),
+    ///    which may be uninitialized for requests that are not tracked
+    /// 2. The writable [`ResponseRecord`] PDA (seeds: `[b"response", request_id]`)
+    /// 3. The writable [`SignatureRequest::rent_payer`], which receives the rent
+    ///    of the closed request; any account for requests that are not tracked
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidInputLength`] - Mismatched array or account counts
+    /// * [`ChainSignaturesError::InvalidRequestAccount`] - Request account is not the expected PDA
+    /// * [`ChainSignaturesError::InvalidResponseRecord`] - Record account is not the expected PDA
+    /// * [`ChainSignaturesError::AlreadyResponded`] - Request id was already answered
+    /// * [`ChainSignaturesError::MissingRootPublicKey`] - Root public key account not provided
+    /// * [`ChainSignaturesError::InvalidSignature`] - Signature does not match the derived key
+    /// * [`ChainSignaturesError::InvalidRentPayer`] - Rent payer is not the request's `rent_payer`
+    ///
+    /// # Emits
+    ///
+    /// * [`SignatureRespondedEvent`] for each signature
+    pub fn respond<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Respond<'info>>,
+        request_ids: Vec<[u8; 32]>,
+        signatures: Vec<Signature>,
+    ) -> Result<()> {
+        require!(
+            request_ids.len() == signatures.len(),
+            ChainSignaturesError::InvalidInputLength
+        );
+        require!(
+            ctx.remaining_accounts.len() == request_ids.len() * 3,
+            ChainSignaturesError::InvalidInputLength
+        );
+
+        let responder = ctx.accounts.responder.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let root_public_key = ctx.accounts.root_public_key.as_deref();
+        let key_version_registry = load_key_version_registry(&ctx.accounts.key_version_registry)?;
+
+        for ((request_id, signature), accounts) in request_ids
+            .into_iter()
+            .zip(signatures)
+            .zip(ctx.remaining_accounts.chunks_exact(3))
+        {
+            check_not_responded(&request_id, &accounts[1])?;
+            let tracked = verify_tracked_signature(
+                &request_id,
+                &accounts[0],
+                &signature,
+                root_public_key,
+                key_version_registry.as_ref(),
+            )?;
+            if let Some(request) = tracked {
+                require_keys_eq!(
+                    *accounts[2].key,
+                    request.rent_payer,
+                    ChainSignaturesError::InvalidRentPayer
+                );
+                move_lamports(
+                    &accounts[0],
+                    &accounts[1],
+                    Rent::get()?.minimum_balance(ResponseRecord::SPACE),
+                )?;
+                record_response(&request_id, &accounts[1], &responder, &system_program)?;
+                close_pda_account(&accounts[0], &accounts[2])?;
+            }
+
+            let event = SignatureRespondedEvent {
+                request_id,
+                responder: *ctx.accounts.responder.key,
+                signature,
+            };
+            #[cfg(feature = "log-events")]
+            emit!(event);
+            emit_cpi!(event);
+        }
+
+        Ok(())
+    }
+
+    /// Report signature generation errors from the MPC network.
+    ///
+    /// # Warning: Debugging Only
+    ///
+    /// This function is **solely for debugging purposes** and should not be used
+    /// in production or relied upon for any business logic. Error events are
+    /// informational only and are not cryptographically verified.
+    ///
+    /// # Security Note
+    ///
+    /// **Any address can call this function.** Do not rely on error events
+    /// for business logic decisions.
+    ///
+    /// Errors cannot be reported for a request id that already received a
+    /// signature via [`respond`]. The [`SignatureRequest`] of a `sign` request
+    /// stays open, since errors are not verified, so the requester can retry it
+    /// by signing the same request again.
+    ///
+    /// # Arguments
+    ///
+    /// * `errors` - Array of error responses with request IDs, error codes and
+    ///   optional details of at most [`MAX_ERROR_DETAIL_LEN`] bytes
+    ///
+    /// # Remaining Accounts
+    ///
+    /// One [`ResponseRecord`] PDA per error, in the same order
+    /// (seeds: `[b"response", request_id]`).
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidInputLength`] - Mismatched account count
+    /// * [`ChainSignaturesError::ErrorDetailTooLong`] - Detail exceeds [`MAX_ERROR_DETAIL_LEN`]
+    /// * [`ChainSignaturesError::InvalidResponseRecord`] - Record account is not the expected PDA
+    /// * [`ChainSignaturesError::AlreadyResponded`] - Request id was already answered
+    ///
+    /// # Emits
+    ///
+    /// * [`SignatureErrorEvent`] for each error
+    pub fn respond_error<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RespondError<'info>>,
+        errors: Vec<ErrorResponse>,
+    ) -> Result<()> {
+        require!(
+            ctx.remaining_accounts.len() == errors.len(),
+            ChainSignaturesError::InvalidInputLength
+        );
+
+        for (error, record) in errors.into_iter().zip(ctx.remaining_accounts.iter()) {
+            if let Some(detail) = &error.detail {
+                require!(
+                    detail.len() <= MAX_ERROR_DETAIL_LEN,
+                    ChainSignaturesError::ErrorDetailTooLong
+                );
+            }
+            check_not_responded(&error.request_id, record)?;
+
+            let event = SignatureErrorEvent {
+                request_id: error.request_id,
+                responder: *ctx.accounts.responder.key,
+                code: error.code,
+                detail: error.detail,
+            };
+            #[cfg(feature = "log-events")]
+            emit!(event);
+            emit_cpi!(event);
+        }
+
+        Ok(())
+    }
+
+    /// Get the current signature deposit amount. View function.
+    ///
+    /// # Returns
+    ///
+    /// Current signature deposit in lamports.
+    pub fn get_signature_deposit(ctx: Context<GetSignatureDeposit>) -> Result<u64> {
+        let program_state = &ctx.accounts.program_state;
+        Ok(program_state.signature_deposit)
+    }
+
+    /// Finalize a bidirectional flow with execution results from the destination chain.
+    ///
+    /// Called by MPC responders after observing transaction confirmation on the
+    /// destination chain. The signature proves the authenticity of the output.
+    ///
+    /// # Security Note
+    ///
+    /// **Any address can call this function.** The signature is not verified
+    /// on-chain and responses are not deduplicated, so a request id may
+    /// receive several conflicting events and
+    /// [`ChainSignaturesError::AlreadyResponded`] is never returned. Clients
+    /// must verify the signature, e.g. with `evm::verify_response` of the
+    /// `evm` feature, and keep the first valid response.
     ///
     /// # Arguments
     ///
     /// * `request_id` - Original 32-byte request identifier
+    /// * `status` - Outcome of the transaction on the destination chain
     /// * `serialized_output` - Serialized execution output per `respond_serialization_schema`
-    /// * `signature` - ECDSA signature over `keccak256(request_id || serialized_output)`
+    /// * `revert_data` - Raw revert data, only for [`ExecutionStatus::Reverted`]
+    /// * `signature` - ECDSA signature over
+    ///   [`bidirectional_response_hash`](crate::response::bidirectional_response_hash)
     ///
     /// # Output Format
     ///
-    /// For **successful transactions**:
+    /// For [`ExecutionStatus::Success`]:
     /// - Contract call: Serialized return value per schema
     /// - Simple transfer: Empty success indicator
     ///
-    /// For **failed transactions**:
-    /// - Magic prefix `0xdeadbeef` followed by failure indicator
+    /// For any other status `serialized_output` is empty.
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::UnexpectedRevertData`] - Revert data for a non-reverted status
+    /// * [`ChainSignaturesError::UnexpectedOutput`] - Output for a non-successful status
     ///
     /// # Emits
     ///
@@ -395,15 +1260,31 @@ pub mod chain_signatures {
     pub fn respond_bidirectional(
         ctx: Context<ReadRespond>,
         request_id: [u8; 32],
+        status: ExecutionStatus,
         serialized_output: Vec<u8>,
+        revert_data: Option<Vec<u8>>,
         signature: Signature,
     ) -> Result<()> {
-        emit!(RespondBidirectionalEvent {
+        require!(
+            revert_data.is_none() || status == ExecutionStatus::Reverted,
+            ChainSignaturesError::UnexpectedRevertData
+        );
+        require!(
+            status == ExecutionStatus::Success || serialized_output.is_empty(),
+            ChainSignaturesError::UnexpectedOutput
+        );
+
+        let event = RespondBidirectionalEvent {
             request_id,
             responder: *ctx.accounts.responder.key,
+            status,
             serialized_output,
+            revert_data,
             signature,
-        });
+        };
+        #[cfg(feature = "log-events")]
+        emit!(event);
+        emit_cpi!(event);
 
         Ok(())
     }
@@ -420,6 +1301,296 @@ pub struct ProgramState {
     pub signature_deposit: u64,
     /// CAIP-2 chain identifier (e.g., "solana:mainnet").
     pub chain_id: String,
+    /// Whether requests are restricted to the allowlist.
+    pub permissioned: bool,
+}
+
+impl ProgramState {
+    /// Account size including the discriminator for a `chain_id` of
+    /// `chain_id_len` bytes.
+    pub const fn space(chain_id_len: usize) -> usize {
+        8 + 32 + 8 + 4 + chain_id_len + 1
+    }
+}
+
+/// Bytes reserved for [`ProgramState::chain_id`] by
+/// [`chain_signatures::initialize`], and the maximum length of the `caip2_id`
+/// of a bidirectional request.
+pub const MAX_CHAIN_ID_LEN: usize = 128;
+
+/// MPC root public key that response signatures are verified against.
+///
+/// Seeds: `[b"root-public-key"]`
+#[account]
+pub struct RootPublicKey {
+    /// Uncompressed secp256k1 public key (`x || y`, without `0x04` prefix).
+    pub public_key: [u8; 64],
+}
+
+impl RootPublicKey {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 64;
+}
+
+/// Maximum number of versions in the [`KeyVersionRegistry`].
+pub const MAX_KEY_VERSIONS: usize = 16;
+
+/// MPC root public keys per key version, managed by the admin via
+/// [`chain_signatures::set_key_version`].
+///
+/// Until the registry is created every key version is accepted and signatures
+/// are verified against the [`RootPublicKey`].
+///
+/// Seeds: `[b"key-version-registry"]`
+#[account]
+pub struct KeyVersionRegistry {
+    /// Registered versions, at most [`MAX_KEY_VERSIONS`].
+    pub versions: Vec<KeyVersionEntry>,
+}
+
+impl KeyVersionRegistry {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 4 + MAX_KEY_VERSIONS * KeyVersionEntry::SPACE;
+
+    /// Entry of `key_version`, if registered.
+    pub fn get(&self, key_version: u32) -> Option<&KeyVersionEntry> {
+        self.versions
+            .iter()
+            .find(|entry| entry.key_version == key_version)
+    }
+}
+
+/// A registered MPC key version.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyVersionEntry {
+    /// MPC key version.
+    pub key_version: u32,
+    /// Uncompressed secp256k1 root public key (`x || y`, without `0x04` prefix).
+    pub public_key: [u8; 64],
+    /// Whether the version accepts new requests.
+    pub active: bool,
+    /// Slot from which the version no longer accepts new requests.
+    pub deprecation_slot: Option<u64>,
+}
+
+impl KeyVersionEntry {
+    /// Serialized size.
+    pub const SPACE: usize = 4 + 64 + 1 + 9;
+
+    /// Whether the version no longer accepts new requests at `slot`.
+    pub fn is_retired(&self, slot: u64) -> bool {
+        !self.active
+            || self
+                .deprecation_slot
+                .is_some_and(|deprecation_slot| slot >= deprecation_slot)
+    }
+}
+
+/// Allows a requester to make requests in permissioned mode.
+/// Created by [`chain_signatures::add_to_allowlist`].
+///
+/// Seeds: `[b"allowlist", key]`
+#[account]
+pub struct AllowlistEntry {
+    /// Requester address.
+    pub key: Pubkey,
+}
+
+impl AllowlistEntry {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 32;
+}
+
+/// Request rate limit set by the admin via [`chain_signatures::set_rate_limit`].
+///
+/// Requests are not limited until the config is created.
+///
+/// Seeds: `[b"rate-limit-config"]`
+#[account]
+pub struct RateLimitConfig {
+    /// Requests allowed per requester per window, zero for no limit.
+    pub max_requests: u32,
+    /// Window length in slots.
+    pub window_slots: u64,
+    /// Requests allowed across all requesters per window, zero for no limit.
+    pub global_max_requests: u32,
+    /// First slot of the current global window.
+    pub global_window_start: u64,
+    /// Requests made by all requesters in the current global window.
+    pub global_count: u32,
+}
+
+impl RateLimitConfig {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 4 + 8 + 4 + 8 + 4;
+}
+
+/// Requests counted against the rate limit in the current window.
+///
+/// Keyed by the requester, for direct and CPI requests alike. Created on the
+/// first limited request, paid for by the fee payer.
+///
+/// Seeds: `[b"rate-limit", requester]`
+#[account]
+pub struct RequesterRateLimit {
+    /// First slot of the current window.
+    pub window_start: u64,
+    /// Requests made in the current window.
+    pub count: u32,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl RequesterRateLimit {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 8 + 4 + 1;
+}
+
+/// A request made via [`chain_signatures::sign`], tracked until it is answered
+/// by [`chain_signatures::respond`].
+///
+/// Seeds: `[b"request", request_id]`
+#[account]
+pub struct SignatureRequest {
+    /// Solana address of the requester.
+    pub sender: Pubkey,
+    /// Account that paid the rent and receives it back, less the rent of the
+    /// [`ResponseRecord`], when [`chain_signatures::respond`] closes the
+    /// request.
+    pub rent_payer: Pubkey,
+    /// 32-byte payload to be signed.
+    pub payload: [u8; 32],
+    /// MPC key version used for signing.
+    pub key_version: u32,
+    /// Derivation tweak of the requester's key (see [`derivation`]).
+    pub epsilon: [u8; 32],
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl SignatureRequest {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 4 + 32 + 1;
+}
+
+/// Maximum payload of a bidirectional request in bytes: the serialized
+/// transaction and both schemas, whether passed directly or through a
+/// [`RequestBuffer`].
+pub const MAX_REQUEST_BUFFER_LEN: usize = 8 * 1024;
+
+/// Payload of a [`chain_signatures::sign_bidirectional_from_buffer`] request,
+/// uploaded in chunks via [`chain_signatures::write_request_buffer`].
+///
+/// Seeds: `[b"request-buffer", owner, buffer_id.to_le_bytes()]`
+#[account]
+pub struct RequestBuffer {
+    /// Account that paid the rent and receives it back when the buffer is
+    /// consumed.
+    pub rent_payer: Pubkey,
+    /// Bump of the PDA, so that it is verified without a search.
+    pub bump: u8,
+    /// Length of the serialized transaction at the start of `data`.
+    pub transaction_len: u32,
+    /// Length of the output schema following the transaction.
+    pub output_deserialization_schema_len: u32,
+    /// Length of the respond schema at the end of `data`.
+    pub respond_serialization_schema_len: u32,
+    /// Serialized transaction, output schema and respond schema, concatenated.
+    pub data: Vec<u8>,
+}
+
+impl RequestBuffer {
+    /// Account size including the discriminator for a payload of `len` bytes.
+    pub const fn space(len: usize) -> usize {
+        8 + 32 + 1 + 4 + 4 + 4 + 4 + len
+    }
+
+    /// Offset of the payload in the account data, after the length prefix of
+    /// `data`.
+    const PAYLOAD_OFFSET: usize = Self::space(0);
+
+    /// Checks that `account` is the buffer `buffer_id` of `owner` and reads
+    /// its fields without copying the payload, which stays in the account:
+    /// `data` is left empty.
+    fn load_header(account: &AccountInfo, owner: &Pubkey, buffer_id: u64) -> Result<Self> {
+        require_keys_eq!(
+            *account.owner,
+            crate::ID,
+            ChainSignaturesError::InvalidRequestBuffer
+        );
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= Self::PAYLOAD_OFFSET && data.starts_with(Self::DISCRIMINATOR),
+            ChainSignaturesError::InvalidRequestBuffer
+        );
+
+        let mut fields = &data[8..Self::PAYLOAD_OFFSET - 4];
+        let buffer = Self {
+            rent_payer: AnchorDeserialize::deserialize(&mut fields)?,
+            bump: AnchorDeserialize::deserialize(&mut fields)?,
+            transaction_len: AnchorDeserialize::deserialize(&mut fields)?,
+            output_deserialization_schema_len: AnchorDeserialize::deserialize(&mut fields)?,
+            respond_serialization_schema_len: AnchorDeserialize::deserialize(&mut fields)?,
+            data: Vec::new(),
+        };
+        let expected = Pubkey::create_program_address(
+            &[
+                REQUEST_BUFFER_SEED,
+                owner.as_ref(),
+                &buffer_id.to_le_bytes(),
+                &[buffer.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| ChainSignaturesError::InvalidRequestBuffer)?;
+        require_keys_eq!(
+            *account.key,
+            expected,
+            ChainSignaturesError::InvalidRequestBuffer
+        );
+
+        Ok(buffer)
+    }
+
+    /// Length of the payload: the serialized transaction and both schemas.
+    fn payload_len(&self) -> usize {
+        self.transaction_len as usize
+            + self.output_deserialization_schema_len as usize
+            + self.respond_serialization_schema_len as usize
+    }
+
+    /// Splits `payload` into the serialized transaction, output schema and
+    /// respond schema.
+    fn split_payload<'a>(&self, payload: &'a [u8]) -> (&'a [u8], &'a [u8], &'a [u8]) {
+        let (serialized_transaction, schemas) = payload.split_at(self.transaction_len as usize);
+        let (output_deserialization_schema, respond_serialization_schema) =
+            schemas.split_at(self.output_deserialization_schema_len as usize);
+        (
+            serialized_transaction,
+            output_deserialization_schema,
+            respond_serialization_schema,
+        )
+    }
+}
+
+/// Marks a request id as answered by [`chain_signatures::respond`].
+///
+/// Funded from the rent of the closed [`SignatureRequest`], so the requester
+/// pays for it. It is never closed, since the request id could otherwise be
+/// signed and answered again.
+///
+/// Seeds: `[b"response", request_id]`
+#[account]
+pub struct ResponseRecord {
+    /// Responder that delivered the signature.
+    pub responder: Pubkey,
+    /// Slot in which the signature was delivered.
+    pub slot: u64,
+}
+
+impl ResponseRecord {
+    /// Account size including the discriminator.
+    pub const SPACE: usize = 8 + 32 + 8;
 }
 
 /// A point on the secp256k1 elliptic curve in affine coordinates.
@@ -463,13 +1634,60 @@ pub struct Signature {
     pub recovery_id: u8,
 }
 
+/// Result of [`chain_signatures::quote_sign_bidirectional`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignBidirectionalQuote {
+    /// Deposit the request would transfer, in lamports.
+    pub deposit: u64,
+    /// Request id of the request, see [`SignBidirectionalEvent::request_id`].
+    pub request_id: [u8; 32],
+    /// Errors of the checks the request fails, in the order
+    /// `sign_bidirectional` runs them; empty if it would succeed.
+    pub failures: Vec<ChainSignaturesError>,
+}
+
 /// Error information for failed signature requests.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct ErrorResponse {
     /// Identifier of the failed request
     pub request_id: [u8; 32],
-    /// Human-readable error description
-    pub error_message: String,
+    /// Reason the request failed
+    pub code: SignatureErrorCode,
+    /// Optional human-readable context, at most [`MAX_ERROR_DETAIL_LEN`] bytes
+    pub detail: Option<String>,
+}
+
+/// Outcome of a bidirectional transaction on the destination chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    /// The transaction was included and executed successfully
+    Success,
+    /// The transaction was included but reverted
+    Reverted,
+    /// The transaction was never included and is no longer pending
+    Dropped,
+    /// Another transaction spending the same nonce or inputs was included instead
+    Replaced,
+}
+
+/// Reason a signature request could not be fulfilled.
+///
+/// Clients should branch on the code; the accompanying `detail` string is
+/// informational only.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureErrorCode {
+    /// The request payload or transaction could not be signed as submitted
+    InvalidTransaction,
+    /// The destination chain (CAIP-2 id) is not supported by the MPC network
+    UnsupportedChain,
+    /// The destination output could not be decoded with the request schema
+    DeserializationFailed,
+    /// The signed transaction reverted on the destination chain
+    DestinationReverted,
+    /// The MPC network gave up waiting for the request to complete
+    Timeout,
+    /// Any failure not covered by the other codes
+    Other,
 }
 
 #[derive(Accounts)]
@@ -477,7 +1695,7 @@ pub struct Initialize<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + 32 + 8 + 4 + 128, // discriminator + admin + deposit + string length + max chain_id length
+        space = ProgramState::space(MAX_CHAIN_ID_LEN),
         seeds = [b"program-state"],
         bump
     )]
@@ -501,6 +1719,111 @@ pub struct AdminOnly<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct SetRootPublicKey<'info> {
+    #[account(
+        seeds = [b"program-state"],
+        bump,
+        has_one = admin @ ChainSignaturesError::Unauthorized
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = RootPublicKey::SPACE,
+        seeds = [b"root-public-key"],
+        bump
+    )]
+    pub root_public_key: Account<'info, RootPublicKey>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetKeyVersion<'info> {
+    #[account(
+        seeds = [b"program-state"],
+        bump,
+        has_one = admin @ ChainSignaturesError::Unauthorized
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = KeyVersionRegistry::SPACE,
+        seeds = [b"key-version-registry"],
+        bump
+    )]
+    pub key_version_registry: Account<'info, KeyVersionRegistry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(key: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(
+        seeds = [b"program-state"],
+        bump,
+        has_one = admin @ ChainSignaturesError::Unauthorized
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = admin,
+        space = AllowlistEntry::SPACE,
+        seeds = [ALLOWLIST_SEED, key.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(key: Pubkey)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(
+        seeds = [b"program-state"],
+        bump,
+        has_one = admin @ ChainSignaturesError::Unauthorized
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [ALLOWLIST_SEED, key.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateLimit<'info> {
+    #[account(
+        seeds = [b"program-state"],
+        bump,
+        has_one = admin @ ChainSignaturesError::Unauthorized
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = RateLimitConfig::SPACE,
+        seeds = [b"rate-limit-config"],
+        bump
+    )]
+    pub rate_limit_config: Account<'info, RateLimitConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct WithdrawFunds<'info> {
     #[account(
@@ -527,11 +1850,38 @@ pub struct WithdrawFunds<'info> {
 pub struct Sign<'info> {
     #[account(mut, seeds = [b"program-state"], bump)]
     pub program_state: Account<'info, ProgramState>,
+    /// CHECK: The [`KeyVersionRegistry`] PDA, which is uninitialized until the
+    /// admin registers a key version.
+    #[account(seeds = [b"key-version-registry"], bump)]
+    pub key_version_registry: UncheckedAccount<'info>,
     #[account(mut)]
     pub requester: Signer<'info>,
     #[account(mut)]
     pub fee_payer: Option<Signer<'info>>,
+    /// CHECK: The [`SignatureRequest`] PDA of the request id, required for
+    /// on-chain verification of the response. Validated and initialized in the
+    /// sign function.
+    #[account(mut)]
+    pub signature_request: Option<UncheckedAccount<'info>>,
+    /// CHECK: The [`AllowlistEntry`] PDA of the requester,
+    /// required in permissioned mode. Validated in the sign function.
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+    /// CHECK: The [`RateLimitConfig`] PDA, which is uninitialized until the
+    /// admin sets a rate limit. Holds the global request count.
+    #[account(mut, seeds = [b"rate-limit-config"], bump)]
+    pub rate_limit_config: UncheckedAccount<'info>,
+    /// CHECK: The [`RequesterRateLimit`] PDA of the requester, required while rate limiting is enabled. Validated and
+    /// initialized in the sign function.
+    #[account(mut)]
+    pub rate_limit: Option<UncheckedAccount<'info>>,
     pub system_program: Program<'info, System>,
+    /// CHECK: The instructions sysvar, read to report the top-level program of
+    /// CPI requests in the request event.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+    /// CHECK: The [`ResponseRecord`] PDA of the request id, required with
+    /// `signature_request`. Validated in the sign function.
+    pub response_record: Option<UncheckedAccount<'info>>,
 }
 
 #[event_cpi]
@@ -539,20 +1889,150 @@ pub struct Sign<'info> {
 pub struct SignBidirectional<'info> {
     #[account(mut, seeds = [b"program-state"], bump)]
     pub program_state: Account<'info, ProgramState>,
+    /// CHECK: The [`KeyVersionRegistry`] PDA, which is uninitialized until the
+    /// admin registers a key version.
+    #[account(seeds = [b"key-version-registry"], bump)]
+    pub key_version_registry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub requester: Signer<'info>,
+    #[account(mut)]
+    pub fee_payer: Option<Signer<'info>>,
+    /// CHECK: The [`AllowlistEntry`] PDA of the requester,
+    /// required in permissioned mode. Validated in the sign function.
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+    /// CHECK: The [`RateLimitConfig`] PDA, which is uninitialized until the
+    /// admin sets a rate limit. Holds the global request count.
+    #[account(mut, seeds = [b"rate-limit-config"], bump)]
+    pub rate_limit_config: UncheckedAccount<'info>,
+    /// CHECK: The [`RequesterRateLimit`] PDA of the requester, required while rate limiting is enabled. Validated and
+    /// initialized in the sign function.
+    #[account(mut)]
+    pub rate_limit: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: The instructions sysvar, read to report the top-level program of
+    /// CPI requests in the request event.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteSignBidirectional<'info> {
+    #[account(seeds = [b"program-state"], bump)]
+    pub program_state: Account<'info, ProgramState>,
+    /// CHECK: The [`KeyVersionRegistry`] PDA, which is uninitialized until the
+    /// admin registers a key version.
+    #[account(seeds = [b"key-version-registry"], bump)]
+    pub key_version_registry: UncheckedAccount<'info>,
+    /// CHECK: Requester of the quoted request; only read.
+    pub requester: UncheckedAccount<'info>,
+    /// CHECK: Fee payer of the quoted request; only its balance is read.
+    pub fee_payer: Option<UncheckedAccount<'info>>,
+    /// CHECK: The [`AllowlistEntry`] PDA of the requester.
+    /// Validated in the quote function.
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+    /// CHECK: The [`RateLimitConfig`] PDA, which is uninitialized until the
+    /// admin sets a rate limit.
+    #[account(seeds = [b"rate-limit-config"], bump)]
+    pub rate_limit_config: UncheckedAccount<'info>,
+    /// CHECK: The [`RequesterRateLimit`] PDA of the requester. Validated in
+    /// the quote function.
+    pub rate_limit: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(buffer_id: u64)]
+pub struct InitRequestBuffer<'info> {
+    /// Requester that writes and consumes the buffer.
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: The [`RequestBuffer`] PDA, created in the handler once its size
+    /// is validated.
+    #[account(
+        mut,
+        seeds = [REQUEST_BUFFER_SEED, owner.key().as_ref(), &buffer_id.to_le_bytes()],
+        bump
+    )]
+    pub request_buffer: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WriteRequestBuffer<'info> {
+    pub owner: Signer<'info>,
+    /// CHECK: The [`RequestBuffer`] PDA of `owner`, validated in the handler
+    /// so that the chunk is written in place.
+    #[account(mut)]
+    pub request_buffer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRequestBuffer<'info> {
+    pub owner: Signer<'info>,
+    /// CHECK: The [`RequestBuffer`] PDA of `owner`, validated and closed in the
+    /// handler.
+    #[account(mut)]
+    pub request_buffer: UncheckedAccount<'info>,
+    /// CHECK: Receives the rent of the closed `request_buffer`; must be its
+    /// `rent_payer`. Validated in the handler.
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SignBidirectionalFromBuffer<'info> {
+    #[account(mut, seeds = [b"program-state"], bump)]
+    pub program_state: Account<'info, ProgramState>,
+    /// CHECK: The [`KeyVersionRegistry`] PDA, which is uninitialized until the
+    /// admin registers a key version.
+    #[account(seeds = [b"key-version-registry"], bump)]
+    pub key_version_registry: UncheckedAccount<'info>,
     #[account(mut)]
     pub requester: Signer<'info>,
     #[account(mut)]
     pub fee_payer: Option<Signer<'info>>,
+    /// CHECK: The [`AllowlistEntry`] PDA of the requester,
+    /// required in permissioned mode. Validated in the sign function.
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+    /// CHECK: The [`RateLimitConfig`] PDA, which is uninitialized until the
+    /// admin sets a rate limit. Holds the global request count.
+    #[account(mut, seeds = [b"rate-limit-config"], bump)]
+    pub rate_limit_config: UncheckedAccount<'info>,
+    /// CHECK: The [`RequesterRateLimit`] PDA of the requester, required while rate limiting is enabled. Validated and
+    /// initialized in the sign function.
+    #[account(mut)]
+    pub rate_limit: Option<UncheckedAccount<'info>>,
+    /// CHECK: The [`RequestBuffer`] PDA of the requester, validated and closed
+    /// in the handler so that the payload is read in place.
+    #[account(mut)]
+    pub request_buffer: UncheckedAccount<'info>,
+    /// CHECK: Receives the rent of the closed `request_buffer`; must be its
+    /// `rent_payer`. Validated in the handler.
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
+    /// CHECK: The instructions sysvar, read to report the top-level program of
+    /// CPI requests in the request event.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
     pub instructions: Option<AccountInfo<'info>>,
 }
 
 #[event_cpi]
 #[derive(Accounts)]
 pub struct Respond<'info> {
+    #[account(mut)]
     pub responder: Signer<'info>,
+    #[account(seeds = [b"root-public-key"], bump)]
+    pub root_public_key: Option<Account<'info, RootPublicKey>>,
+    /// CHECK: The [`KeyVersionRegistry`] PDA, which is uninitialized until the
+    /// admin registers a key version.
+    #[account(seeds = [b"key-version-registry"], bump)]
+    pub key_version_registry: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct RespondError<'info> {
     pub responder: Signer<'info>,
@@ -564,6 +2044,7 @@ pub struct GetSignatureDeposit<'info> {
     pub program_state: Account<'info, ProgramState>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ReadRespond<'info> {
     pub responder: Signer<'info>,
@@ -596,6 +2077,11 @@ pub struct SignatureRequestedEvent {
     pub params: String,
     /// Optional separate fee payer account.
     pub fee_payer: Option<Pubkey>,
+    /// Program of the top-level instruction when it is not this program, read
+    /// from the instructions sysvar; `None` for direct calls or when the
+    /// sysvar is not passed. Under nested CPI this is the outermost program,
+    /// not necessarily the immediate caller, so it is informational only.
+    pub top_level_program: Option<Pubkey>,
 }
 
 /// Emitted when a bidirectional cross-chain request is made via
@@ -612,6 +2098,10 @@ pub struct SignatureRequestedEvent {
 /// 2. Store the pending tx in backlog for observation
 /// 3. Monitor destination chain for confirmation
 /// 4. Call [`chain_signatures::respond_bidirectional`] with results
+///
+/// The event carries every input of the request id, see
+/// [`SignBidirectionalEvent::request_id`], and the source `chain_id` the
+/// signing key is derived with.
 #[event]
 pub struct SignBidirectionalEvent {
     /// Solana address of the requester.
@@ -638,6 +2128,33 @@ pub struct SignBidirectionalEvent {
     pub output_deserialization_schema: Vec<u8>,
     /// Schema for serializing response to source chain (JSON-encoded).
     pub respond_serialization_schema: Vec<u8>,
+    /// Program of the top-level instruction when it is not this program, read
+    /// from the instructions sysvar; `None` for direct calls or when the
+    /// sysvar is not passed. Under nested CPI this is the outermost program,
+    /// not necessarily the immediate caller, so it is informational only.
+    pub top_level_program: Option<Pubkey>,
+    /// CAIP-2 chain identifier of this program (e.g., "solana:mainnet").
+    pub chain_id: String,
+}
+
+impl SignBidirectionalEvent {
+    /// Request id of the request, as computed by
+    /// [`sign_bidirectional_request_id`](request_id::sign_bidirectional_request_id).
+    ///
+    /// Bitcoin requests are answered per input instead, see
+    /// [`bitcoin::input_request_id`] and [`bitcoin::aggregate_request_id`].
+    pub fn request_id(&self) -> [u8; 32] {
+        request_id::sign_bidirectional_request_id(
+            &self.sender,
+            &self.serialized_transaction,
+            &self.caip2_id,
+            self.key_version,
+            &self.path,
+            &self.algo,
+            &self.dest,
+            &self.params,
+        )
+    }
 }
 
 /// Emitted when the MPC network returns a signature via [`chain_signatures::respond`].
@@ -670,7 +2187,7 @@ pub struct SignatureRespondedEvent {
 ///
 /// # Event Type
 ///
-/// Regular event (emitted via `emit!`)
+/// CPI event (emitted via `emit_cpi!`)
 ///
 /// # Security Warning
 ///
@@ -682,8 +2199,10 @@ pub struct SignatureErrorEvent {
     pub request_id: [u8; 32],
     /// Address of the MPC responder. Error events are not cryptographically verified.
     pub responder: Pubkey,
-    /// Human-readable error description.
-    pub error: String,
+    /// Reason the request failed.
+    pub code: SignatureErrorCode,
+    /// Optional human-readable context, at most [`MAX_ERROR_DETAIL_LEN`] bytes.
+    pub detail: Option<String>,
 }
 
 /// Emitted when the MPC network returns execution results for a bidirectional
@@ -691,21 +2210,23 @@ pub struct SignatureErrorEvent {
 ///
 /// # Event Type
 ///
-/// Regular event (emitted via `emit!`)
+/// CPI event (emitted via `emit_cpi!`)
 ///
 /// # Output Format
 ///
-/// **Successful transactions:**
+/// **[`ExecutionStatus::Success`]:**
 /// - Contract call: Return value serialized per `respond_serialization_schema`
 /// - Simple transfer: Empty success indicator
 ///
-/// **Failed transactions:**
-/// - Magic prefix `0xdeadbeef` followed by failure indicator
+/// **Any other status:** empty output. Reverted transactions may carry the raw
+/// revert data in `revert_data`.
 ///
 /// # Signature Verification
 ///
-/// The signature is computed over `keccak256(request_id || serialized_output)` using
-/// the special derivation path `"solana response key"`. See module-level docs for
+/// The signature is computed over
+/// [`bidirectional_response_hash`](crate::response::bidirectional_response_hash),
+/// which covers the status, output and revert data, using the special
+/// derivation path `"solana response key"`. See module-level docs for
 /// verification procedure.
 ///
 /// # Security Warning
@@ -718,10 +2239,15 @@ pub struct RespondBidirectionalEvent {
     pub request_id: [u8; 32],
     /// Address of the MPC responder. Clients must verify the signature was produced by the MPC.
     pub responder: Pubkey,
+    /// Outcome of the transaction on the destination chain.
+    pub status: ExecutionStatus,
     /// Serialized execution output per `respond_serialization_schema`.
-    /// Check for `0xdeadbeef` prefix to detect failures.
+    /// Empty unless `status` is [`ExecutionStatus::Success`].
     pub serialized_output: Vec<u8>,
-    /// ECDSA signature over `keccak256(request_id || serialized_output)`.
+    /// Raw revert data of a reverted transaction, if the destination chain reports any.
+    pub revert_data: Option<Vec<u8>>,
+    /// ECDSA signature over
+    /// [`bidirectional_response_hash`](crate::response::bidirectional_response_hash).
     pub signature: Signature,
 }
 
@@ -735,6 +2261,71 @@ pub struct DepositUpdatedEvent {
     pub new_deposit: u64,
 }
 
+/// Emitted when the admin updates the chain id via
+/// [`chain_signatures::update_chain_id`].
+#[event]
+pub struct ChainIdUpdatedEvent {
+    /// Previous CAIP-2 chain identifier.
+    pub old_chain_id: String,
+    /// New CAIP-2 chain identifier.
+    pub new_chain_id: String,
+}
+
+/// Emitted when the admin sets the MPC root public key via
+/// [`chain_signatures::set_root_public_key`].
+#[event]
+pub struct RootPublicKeyUpdatedEvent {
+    /// Previous root public key (all zeros if none was set).
+    pub old_public_key: [u8; 64],
+    /// New root public key.
+    pub new_public_key: [u8; 64],
+}
+
+/// Emitted when the admin registers or updates a key version via
+/// [`chain_signatures::set_key_version`].
+#[event]
+pub struct KeyVersionUpdatedEvent {
+    /// MPC key version.
+    pub key_version: u32,
+    /// Root public key of the version.
+    pub public_key: [u8; 64],
+    /// Whether the version accepts new requests.
+    pub active: bool,
+    /// Slot from which the version no longer accepts new requests.
+    pub deprecation_slot: Option<u64>,
+}
+
+/// Emitted when the admin toggles permissioned mode via
+/// [`chain_signatures::set_permissioned`].
+#[event]
+pub struct PermissionedModeUpdatedEvent {
+    /// Whether requests are restricted to the allowlist.
+    pub permissioned: bool,
+}
+
+/// Emitted when the admin adds or removes an allowlist entry via
+/// [`chain_signatures::add_to_allowlist`] or
+/// [`chain_signatures::remove_from_allowlist`].
+#[event]
+pub struct AllowlistUpdatedEvent {
+    /// Requester address.
+    pub key: Pubkey,
+    /// Whether the key was added (`true`) or removed (`false`).
+    pub allowed: bool,
+}
+
+/// Emitted when the admin sets the rate limit via
+/// [`chain_signatures::set_rate_limit`].
+#[event]
+pub struct RateLimitUpdatedEvent {
+    /// Requests allowed per requester per window, zero for no limit.
+    pub max_requests: u32,
+    /// Window length in slots.
+    pub window_slots: u64,
+    /// Requests allowed across all requesters per window, zero for no limit.
+    pub global_max_requests: u32,
+}
+
 /// Emitted when the admin withdraws funds via [`chain_signatures::withdraw_funds`].
 #[event]
 pub struct FundsWithdrawnEvent {
@@ -745,6 +2336,7 @@ pub struct FundsWithdrawnEvent {
 }
 
 #[error_code]
+#[derive(AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
 pub enum ChainSignaturesError {
     #[msg("Insufficient deposit amount")]
     InsufficientDeposit,
@@ -758,6 +2350,590 @@ pub enum ChainSignaturesError {
     InvalidRecipient,
     #[msg("Invalid transaction data")]
     InvalidTransaction,
+    /// Reserved: no longer returned since the instructions sysvar became
+    /// optional, kept so that later error codes keep their values.
     #[msg("Missing instruction sysvar")]
     MissingInstructionSysvar,
+    #[msg("Request has already been responded to")]
+    AlreadyResponded,
+    #[msg("Invalid response record account")]
+    InvalidResponseRecord,
+    #[msg("Invalid signature request account")]
+    InvalidRequestAccount,
+    #[msg("Missing root public key account")]
+    MissingRootPublicKey,
+    #[msg("Invalid root public key")]
+    InvalidRootPublicKey,
+    #[msg("Signature does not match the derived key")]
+    InvalidSignature,
+    #[msg("Error detail is too long")]
+    ErrorDetailTooLong,
+    #[msg("Revert data is only allowed for reverted transactions")]
+    UnexpectedRevertData,
+    #[msg("Invalid CAIP-2 chain id")]
+    InvalidChainId,
+    #[msg("Response output does not match the respond schema")]
+    InvalidResponseOutput,
+    #[msg("Malformed serialization schema")]
+    InvalidSchema,
+    #[msg("Invalid address prefix")]
+    InvalidAddressPrefix,
+    #[msg("Key version is not registered")]
+    UnknownKeyVersion,
+    #[msg("Key version is retired")]
+    RetiredKeyVersion,
+    #[msg("Key version registry is full")]
+    KeyVersionRegistryFull,
+    #[msg("Request rate limit exceeded")]
+    RateLimited,
+    #[msg("Invalid rate limit")]
+    InvalidRateLimit,
+    #[msg("Invalid rate limit account")]
+    InvalidRateLimitAccount,
+    #[msg("Requester is not allowlisted")]
+    NotAllowlisted,
+    #[msg("Request buffer exceeds the maximum size")]
+    RequestBufferTooLarge,
+    #[msg("Write exceeds the request buffer")]
+    RequestBufferOverflow,
+    #[msg("Rent payer does not match the account")]
+    InvalidRentPayer,
+    #[msg("Output is only allowed for successful transactions")]
+    UnexpectedOutput,
+    #[msg("Chain id exceeds the maximum length")]
+    ChainIdTooLong,
+    #[msg("Invalid request buffer account")]
+    InvalidRequestBuffer,
+    #[msg("Serialized transaction and schemas exceed the maximum request size")]
+    RequestTooLarge,
+}
+
+/// Maximum length in bytes of [`ErrorResponse::detail`].
+pub const MAX_ERROR_DETAIL_LEN: usize = 256;
+
+/// Seed prefix of the [`SignatureRequest`] PDA.
+pub const SIGNATURE_REQUEST_SEED: &[u8] = b"request";
+
+/// Seed prefix of the [`ResponseRecord`] PDA.
+pub const RESPONSE_RECORD_SEED: &[u8] = b"response";
+
+/// Seed prefix of the [`RequesterRateLimit`] PDA.
+pub const RATE_LIMIT_SEED: &[u8] = b"rate-limit";
+
+/// Seed prefix of the [`AllowlistEntry`] PDA.
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+/// Seed prefix of the [`RequestBuffer`] PDA.
+pub const REQUEST_BUFFER_SEED: &[u8] = b"request-buffer";
+
+/// Checks that `chain_id` is a CAIP-2 chain id: a namespace of 3 to 8
+/// lowercase letters, digits or `-`, a `:` and a reference of 1 to 32
+/// letters, digits, `-` or `_`, and that it fits the [`MAX_CHAIN_ID_LEN`]
+/// bytes reserved for it.
+fn validate_chain_id(chain_id: &str) -> Result<()> {
+    require!(
+        chain_id.len() <= MAX_CHAIN_ID_LEN,
+        ChainSignaturesError::ChainIdTooLong
+    );
+    let (namespace, reference) = chain_id
+        .split_once(':')
+        .ok_or(ChainSignaturesError::InvalidChainId)?;
+    require!(
+        (3..=8).contains(&namespace.len())
+            && namespace
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+            && (1..=32).contains(&reference.len())
+            && reference
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'),
+        ChainSignaturesError::InvalidChainId
+    );
+    Ok(())
+}
+
+/// Checks that `record` is the [`ResponseRecord`] PDA of `request_id` and that
+/// no response has been recorded yet. Returns the PDA bump.
+fn check_not_responded(request_id: &[u8; 32], record: &AccountInfo) -> Result<u8> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[RESPONSE_RECORD_SEED, request_id], &crate::ID);
+    require_keys_eq!(
+        *record.key,
+        expected,
+        ChainSignaturesError::InvalidResponseRecord
+    );
+    require!(
+        record.owner != &crate::ID,
+        ChainSignaturesError::AlreadyResponded
+    );
+
+    Ok(bump)
+}
+
+/// Creates the [`ResponseRecord`] PDA of `request_id`, failing if one exists.
+/// `responder` only pays the part of the rent the record does not hold yet.
+fn record_response<'info>(
+    request_id: &[u8; 32],
+    record: &AccountInfo<'info>,
+    responder: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let bump = check_not_responded(request_id, record)?;
+
+    create_pda_account(
+        responder,
+        record,
+        system_program,
+        ResponseRecord::SPACE,
+        &[RESPONSE_RECORD_SEED, request_id, &[bump]],
+    )?;
+
+    let data = ResponseRecord {
+        responder: *responder.key,
+        slot: Clock::get()?.slot,
+    };
+    data.try_serialize(&mut &mut record.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
+
+/// Creates the [`SignatureRequest`] PDA of `request_id`. A request that is
+/// still pending is left as is, so signing it again retries it.
+fn track_signature_request<'info>(
+    request_id: &[u8; 32],
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    request: SignatureRequest,
+) -> Result<()> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[SIGNATURE_REQUEST_SEED, request_id], &crate::ID);
+    require_keys_eq!(
+        *account.key,
+        expected,
+        ChainSignaturesError::InvalidRequestAccount
+    );
+    if account.owner == &crate::ID {
+        return Ok(());
+    }
+
+    create_pda_account(
+        payer,
+        account,
+        system_program,
+        SignatureRequest::SPACE,
+        &[SIGNATURE_REQUEST_SEED, request_id, &[bump]],
+    )?;
+
+    let request = SignatureRequest { bump, ..request };
+    request.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
+
+/// Reads the [`KeyVersionRegistry`] PDA, `None` while it is uninitialized.
+///
+/// Callers outside this program must check that `account` is the registry
+/// PDA (seeds: `[b"key-version-registry"]`).
+pub fn load_key_version_registry(account: &AccountInfo) -> Result<Option<KeyVersionRegistry>> {
+    if account.owner != &crate::ID {
+        return Ok(None);
+    }
+    Ok(Some(KeyVersionRegistry::try_deserialize(
+        &mut &account.try_borrow_data()?[..],
+    )?))
+}
+
+/// Root public key that signs requests of `key_version`: its
+/// [`KeyVersionRegistry`] entry, or the [`RootPublicKey`] for versions that are
+/// not registered. Programs deriving response keys, e.g. with
+/// `evm::response_address`, must use it so that they follow key rotations.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::MissingRootPublicKey`] - `key_version` is not
+///   registered and `root_public_key` is `None`
+pub fn key_version_root_public_key(
+    key_version_registry: Option<&KeyVersionRegistry>,
+    root_public_key: Option<&RootPublicKey>,
+    key_version: u32,
+) -> Result<[u8; 64]> {
+    match key_version_registry.and_then(|registry| registry.get(key_version)) {
+        Some(entry) => Ok(entry.public_key),
+        None => Ok(root_public_key
+            .ok_or(ChainSignaturesError::MissingRootPublicKey)?
+            .public_key),
+    }
+}
+
+/// Checks that `key_version` accepts new requests. Every version is accepted
+/// while the [`KeyVersionRegistry`] is uninitialized.
+fn check_key_version(registry: &AccountInfo, key_version: u32) -> Result<()> {
+    let Some(registry) = load_key_version_registry(registry)? else {
+        return Ok(());
+    };
+    let entry = registry
+        .get(key_version)
+        .ok_or(ChainSignaturesError::UnknownKeyVersion)?;
+    require!(
+        !entry.is_retired(Clock::get()?.slot),
+        ChainSignaturesError::RetiredKeyVersion
+    );
+
+    Ok(())
+}
+
+/// Program of the top-level instruction, read from the instructions sysvar,
+/// when it is not this program. `None` for direct calls or without the
+/// sysvar.
+fn top_level_program(instructions: Option<&AccountInfo>) -> Result<Option<Pubkey>> {
+    let Some(instructions) = instructions else {
+        return Ok(None);
+    };
+    let current = get_instruction_relative(0, instructions)?;
+
+    Ok((current.program_id != crate::ID).then_some(current.program_id))
+}
+
+/// In permissioned mode, checks that the requester has an [`AllowlistEntry`].
+fn check_allowlist(
+    program_state: &ProgramState,
+    allowlist_entry: Option<&AccountInfo>,
+    requester: &Pubkey,
+) -> Result<()> {
+    if !program_state.permissioned {
+        return Ok(());
+    }
+
+    let entry = allowlist_entry.ok_or(ChainSignaturesError::NotAllowlisted)?;
+    let (expected, _) =
+        Pubkey::find_program_address(&[ALLOWLIST_SEED, requester.as_ref()], &crate::ID);
+    require!(
+        *entry.key == expected && entry.owner == &crate::ID,
+        ChainSignaturesError::NotAllowlisted
+    );
+
+    Ok(())
+}
+
+/// Counts a request against the global count in the [`RateLimitConfig`] and
+/// against the [`RequesterRateLimit`] of its requester, creating the latter on
+/// first use.
+fn enforce_rate_limit<'info>(
+    config_account: &AccountInfo,
+    rate_limit: Option<&AccountInfo<'info>>,
+    requester: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if config_account.owner != &crate::ID {
+        return Ok(());
+    }
+    let mut config = RateLimitConfig::try_deserialize(&mut &config_account.try_borrow_data()?[..])?;
+    let slot = Clock::get()?.slot;
+
+    if config.global_max_requests > 0 {
+        if slot
+            >= config
+                .global_window_start
+                .saturating_add(config.window_slots)
+        {
+            config.global_window_start = slot;
+            config.global_count = 0;
+        }
+        require!(
+            config.global_count < config.global_max_requests,
+            ChainSignaturesError::RateLimited
+        );
+        config.global_count += 1;
+        config.try_serialize(&mut &mut config_account.try_borrow_mut_data()?[..])?;
+    }
+    if config.max_requests == 0 {
+        return Ok(());
+    }
+
+    let account = rate_limit.ok_or(ChainSignaturesError::InvalidRateLimitAccount)?;
+    let (expected, bump) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, requester.as_ref()], &crate::ID);
+    require_keys_eq!(
+        *account.key,
+        expected,
+        ChainSignaturesError::InvalidRateLimitAccount
+    );
+
+    let mut state = if account.owner == &crate::ID {
+        RequesterRateLimit::try_deserialize(&mut &account.try_borrow_data()?[..])?
+    } else {
+        create_pda_account(
+            payer,
+            account,
+            system_program,
+            RequesterRateLimit::SPACE,
+            &[RATE_LIMIT_SEED, requester.as_ref(), &[bump]],
+        )?;
+        RequesterRateLimit {
+            window_start: 0,
+            count: 0,
+            bump,
+        }
+    };
+
+    if slot >= state.window_start.saturating_add(config.window_slots) {
+        state.window_start = slot;
+        state.count = 0;
+    }
+    require!(
+        state.count < config.max_requests,
+        ChainSignaturesError::RateLimited
+    );
+    state.count += 1;
+    state.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
+
+/// Checks, without counting it, that a request from `requester` stays within
+/// the rate limits enforced by [`enforce_rate_limit`].
+fn check_rate_limit(
+    config: &AccountInfo,
+    rate_limit: Option<&AccountInfo>,
+    requester: &Pubkey,
+) -> Result<()> {
+    if config.owner != &crate::ID {
+        return Ok(());
+    }
+    let config = RateLimitConfig::try_deserialize(&mut &config.try_borrow_data()?[..])?;
+    let slot = Clock::get()?.slot;
+
+    let global_window_expired = slot
+        >= config
+            .global_window_start
+            .saturating_add(config.window_slots);
+    require!(
+        config.global_max_requests == 0
+            || global_window_expired
+            || config.global_count < config.global_max_requests,
+        ChainSignaturesError::RateLimited
+    );
+    if config.max_requests == 0 {
+        return Ok(());
+    }
+
+    let account = rate_limit.ok_or(ChainSignaturesError::InvalidRateLimitAccount)?;
+    let (expected, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, requester.as_ref()], &crate::ID);
+    require_keys_eq!(
+        *account.key,
+        expected,
+        ChainSignaturesError::InvalidRateLimitAccount
+    );
+    if account.owner != &crate::ID {
+        return Ok(());
+    }
+
+    let state = RequesterRateLimit::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    let window_expired = slot >= state.window_start.saturating_add(config.window_slots);
+    require!(
+        window_expired || state.count < config.max_requests,
+        ChainSignaturesError::RateLimited
+    );
+
+    Ok(())
+}
+
+/// Errors of the checks [`chain_signatures::quote_sign_bidirectional`] runs.
+const QUOTED_FAILURES: [ChainSignaturesError; 11] = [
+    ChainSignaturesError::InsufficientDeposit,
+    ChainSignaturesError::UnknownKeyVersion,
+    ChainSignaturesError::RetiredKeyVersion,
+    ChainSignaturesError::NotAllowlisted,
+    ChainSignaturesError::RateLimited,
+    ChainSignaturesError::InvalidRateLimitAccount,
+    ChainSignaturesError::ChainIdTooLong,
+    ChainSignaturesError::InvalidChainId,
+    ChainSignaturesError::InvalidTransaction,
+    ChainSignaturesError::RequestTooLarge,
+    ChainSignaturesError::InvalidSchema,
+];
+
+/// Adds the error of a failed check to `failures`. Other errors, such as
+/// failed account reads, are returned instead.
+fn record_failure(result: Result<()>, failures: &mut Vec<ChainSignaturesError>) -> Result<()> {
+    let Err(error) = result else {
+        return Ok(());
+    };
+    let failure = QUOTED_FAILURES
+        .into_iter()
+        .find(|failure| error == Error::from(*failure))
+        .ok_or(error)?;
+    failures.push(failure);
+    Ok(())
+}
+
+/// Runs the checks of the payload of a bidirectional request in order,
+/// passing each result to `report`, which returns it to fail on the first
+/// error or records it to run every check.
+fn check_bidirectional_payload(
+    serialized_transaction: &[u8],
+    caip2_id: &str,
+    output_deserialization_schema: &[u8],
+    respond_serialization_schema: &[u8],
+    mut report: impl FnMut(Result<()>) -> Result<()>,
+) -> Result<()> {
+    report(validate_chain_id(caip2_id))?;
+    report(if serialized_transaction.is_empty() {
+        err!(ChainSignaturesError::InvalidTransaction)
+    } else {
+        Ok(())
+    })?;
+    let len = serialized_transaction.len()
+        + output_deserialization_schema.len()
+        + respond_serialization_schema.len();
+    report(if len > MAX_REQUEST_BUFFER_LEN {
+        err!(ChainSignaturesError::RequestTooLarge)
+    } else {
+        Ok(())
+    })?;
+    report(schema::validate_schemas(
+        caip2_id,
+        output_deserialization_schema,
+        respond_serialization_schema,
+    ))
+}
+
+/// Verifies `signature` against the [`SignatureRequest`] of `request_id` and
+/// returns the request, or `None` when it is not tracked.
+///
+/// Requests that are not tracked (the PDA is uninitialized) are not verified.
+fn verify_tracked_signature(
+    request_id: &[u8; 32],
+    account: &AccountInfo,
+    signature: &Signature,
+    root_public_key: Option<&RootPublicKey>,
+    key_version_registry: Option<&KeyVersionRegistry>,
+) -> Result<Option<SignatureRequest>> {
+    if account.owner != &crate::ID {
+        let (expected, _) =
+            Pubkey::find_program_address(&[SIGNATURE_REQUEST_SEED, request_id], &crate::ID);
+        require_keys_eq!(
+            *account.key,
+            expected,
+            ChainSignaturesError::InvalidRequestAccount
+        );
+        return Ok(None);
+    }
+
+    let request = SignatureRequest::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    let expected = Pubkey::create_program_address(
+        &[SIGNATURE_REQUEST_SEED, request_id, &[request.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| ChainSignaturesError::InvalidRequestAccount)?;
+    require_keys_eq!(
+        *account.key,
+        expected,
+        ChainSignaturesError::InvalidRequestAccount
+    );
+
+    let root_public_key =
+        key_version_root_public_key(key_version_registry, root_public_key, request.key_version)?;
+    let derived = derivation::derive_public_key(&root_public_key, &request.epsilon)?;
+    let recovered = derivation::recover_public_key(&request.payload, signature)?;
+    require!(derived == recovered, ChainSignaturesError::InvalidSignature);
+
+    Ok(Some(request))
+}
+
+/// Closes a program-owned PDA, moving its lamports to `destination`.
+fn close_pda_account(account: &AccountInfo, destination: &AccountInfo) -> Result<()> {
+    let lamports = account.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    account.assign(&anchor_lang::system_program::ID);
+    account.resize(0)?;
+    Ok(())
+}
+
+/// Moves `lamports` out of the program-owned `from` account.
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Creates a program-owned PDA, tolerating accounts that were pre-funded
+/// with lamports by a third party.
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    use anchor_lang::system_program::{
+        allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+    };
+
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer = &[signer_seeds];
+
+    if account.lamports() == 0 {
+        return create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                signer,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer,
+        ),
+        space as u64,
+    )?;
+    assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            signer,
+        ),
+        &crate::ID,
+    )
 }
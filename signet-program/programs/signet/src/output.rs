@@ -0,0 +1,475 @@
+//! # Respond Output Decoding
+//!
+//! `serialized_output` of a [`RespondBidirectionalEvent`](crate::RespondBidirectionalEvent)
+//! is Borsh-encoded with the `respond_serialization_schema` of the matching
+//! [`SignBidirectionalEvent`](crate::SignBidirectionalEvent). [`decode_output`]
+//! decodes it against that schema into a [`BorshValue`] tree, so indexers can
+//! render the result of any request without knowing its Rust type:
+//!
+//! ```rust
+//! use chain_signatures::output::{decode_output, BorshValue};
+//!
+//! let schema = br#"{"struct":{"success":"bool","amount":"u64"}}"#;
+//! let value = decode_output(schema, &[1, 42, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+//! assert_eq!(
+//!     serde_json::to_string(&value).unwrap(),
+//!     r#"{"success":true,"amount":42}"#
+//! );
+//!
+//! let error = decode_output(schema, &[2]).unwrap_err();
+//! assert_eq!(error.to_string(), "at `success`: invalid bool 2");
+//! ```
+//!
+//! A [`DecodeError`] names the field path where decoding failed. On-chain it
+//! converts to [`InvalidResponseOutput`](crate::ChainSignaturesError::InvalidResponseOutput).
+use crate::schema::{parse_borsh_schema, BorshType};
+use crate::ChainSignaturesError;
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::Serialize;
+use std::fmt;
+
+/// Decoded Borsh value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BorshValue {
+    Bool(bool),
+    /// Any unsigned integer up to `u128`
+    Unsigned(u128),
+    /// Any signed integer up to `i128`
+    Signed(i128),
+    F32(f32),
+    F64(f64),
+    String(String),
+    /// `Vec<u8>` or `[u8; N]`
+    Bytes(Vec<u8>),
+    Array(Vec<BorshValue>),
+    Option(Option<Box<BorshValue>>),
+    Struct(Vec<(String, BorshValue)>),
+    Enum(String, Box<BorshValue>),
+}
+
+/// Reason a value could not be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The schema bytes are not a valid Borsh schema
+    InvalidSchema,
+    /// The output ended while `needed` more bytes were expected
+    UnexpectedEnd {
+        needed: usize,
+    },
+    InvalidBool(u8),
+    InvalidOptionTag(u8),
+    /// Discriminant beyond the number of enum variants
+    InvalidEnumVariant(u8),
+    InvalidUtf8,
+    /// NaN float, which Borsh does not encode
+    InvalidFloat,
+    /// Bytes left over after the value
+    TrailingBytes(usize),
+    /// Non-empty sequence of a type that encodes to no bytes, which would
+    /// decode any number of elements from no input
+    ZeroSizedElement,
+}
+
+/// Mismatch between an output and its schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Dotted field path, with `[i]` for sequence elements; empty at the root
+    pub path: String,
+    pub kind: DecodeErrorKind,
+}
+
+/// Decodes `output` with the JSON Borsh schema `schema`.
+pub fn decode_output(schema: &[u8], output: &[u8]) -> Result<BorshValue, DecodeError> {
+    let ty = parse_borsh_schema(schema).map_err(|_| DecodeError {
+        path: String::new(),
+        kind: DecodeErrorKind::InvalidSchema,
+    })?;
+    decode_value(&ty, output)
+}
+
+/// Decodes `output` as a value of `ty`, rejecting trailing bytes.
+pub fn decode_value(ty: &BorshType, output: &[u8]) -> Result<BorshValue, DecodeError> {
+    let mut decoder = Decoder {
+        input: output,
+        path: Vec::new(),
+    };
+    let value = decoder.value(ty)?;
+    if !decoder.input.is_empty() {
+        return Err(decoder.error(DecodeErrorKind::TrailingBytes(decoder.input.len())));
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    path: Vec<String>,
+}
+
+impl<'a> Decoder<'a> {
+    fn value(&mut self, ty: &BorshType) -> Result<BorshValue, DecodeError> {
+        Ok(match ty {
+            BorshType::Bool => match self.byte()? {
+                0 => BorshValue::Bool(false),
+                1 => BorshValue::Bool(true),
+                tag => return Err(self.error(DecodeErrorKind::InvalidBool(tag))),
+            },
+            BorshType::U8 => BorshValue::Unsigned(self.byte()?.into()),
+            BorshType::U16 => BorshValue::Unsigned(u16::from_le_bytes(self.array()?).into()),
+            BorshType::U32 => BorshValue::Unsigned(u32::from_le_bytes(self.array()?).into()),
+            BorshType::U64 => BorshValue::Unsigned(u64::from_le_bytes(self.array()?).into()),
+            BorshType::U128 => BorshValue::Unsigned(u128::from_le_bytes(self.array()?)),
+            BorshType::I8 => BorshValue::Signed(i8::from_le_bytes(self.array()?).into()),
+            BorshType::I16 => BorshValue::Signed(i16::from_le_bytes(self.array()?).into()),
+            BorshType::I32 => BorshValue::Signed(i32::from_le_bytes(self.array()?).into()),
+            BorshType::I64 => BorshValue::Signed(i64::from_le_bytes(self.array()?).into()),
+            BorshType::I128 => BorshValue::Signed(i128::from_le_bytes(self.array()?)),
+            BorshType::F32 => {
+                let value = f32::from_le_bytes(self.array()?);
+                if value.is_nan() {
+                    return Err(self.error(DecodeErrorKind::InvalidFloat));
+                }
+                BorshValue::F32(value)
+            }
+            BorshType::F64 => {
+                let value = f64::from_le_bytes(self.array()?);
+                if value.is_nan() {
+                    return Err(self.error(DecodeErrorKind::InvalidFloat));
+                }
+                BorshValue::F64(value)
+            }
+            BorshType::String => {
+                let len = self.len()?;
+                let bytes = self.take(len)?;
+                let string = std::str::from_utf8(bytes)
+                    .map_err(|_| self.error(DecodeErrorKind::InvalidUtf8))?;
+                BorshValue::String(string.to_string())
+            }
+            BorshType::Vec(inner) => {
+                let len = self.len()?;
+                self.sequence(inner, len)?
+            }
+            BorshType::Array(inner, len) => self.sequence(inner, *len as usize)?,
+            BorshType::Option(inner) => match self.byte()? {
+                0 => BorshValue::Option(None),
+                1 => BorshValue::Option(Some(Box::new(self.value(inner)?))),
+                tag => return Err(self.error(DecodeErrorKind::InvalidOptionTag(tag))),
+            },
+            BorshType::Struct(fields) => {
+                let mut values = Vec::with_capacity(fields.len());
+                for (name, ty) in fields {
+                    self.path.push(name.clone());
+                    values.push((name.clone(), self.value(ty)?));
+                    self.path.pop();
+                }
+                BorshValue::Struct(values)
+            }
+            BorshType::Enum(variants) => {
+                let discriminant = self.byte()?;
+                let (name, ty) = variants
+                    .get(discriminant as usize)
+                    .ok_or_else(|| self.error(DecodeErrorKind::InvalidEnumVariant(discriminant)))?;
+                self.path.push(name.clone());
+                let value = self.value(ty)?;
+                self.path.pop();
+                BorshValue::Enum(name.clone(), Box::new(value))
+            }
+        })
+    }
+
+    fn sequence(&mut self, inner: &BorshType, len: usize) -> Result<BorshValue, DecodeError> {
+        if *inner == BorshType::U8 {
+            return Ok(BorshValue::Bytes(self.take(len)?.to_vec()));
+        }
+
+        if len > 0 && is_zero_sized(inner) {
+            return Err(self.error(DecodeErrorKind::ZeroSizedElement));
+        }
+
+        let mut values = Vec::with_capacity(len.min(self.input.len()));
+        for i in 0..len {
+            self.path.push(format!("[{i}]"));
+            values.push(self.value(inner)?);
+            self.path.pop();
+        }
+        Ok(BorshValue::Array(values))
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.input.len() < len {
+            return Err(self.error(DecodeErrorKind::UnexpectedEnd {
+                needed: len - self.input.len(),
+            }));
+        }
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(bytes)
+    }
+
+    fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        let mut path = String::new();
+        for segment in &self.path {
+            if !path.is_empty() && !segment.starts_with('[') {
+                path.push('.');
+            }
+            path.push_str(segment);
+        }
+        DecodeError { path, kind }
+    }
+}
+
+/// Whether every value of `ty` encodes to no bytes.
+fn is_zero_sized(ty: &BorshType) -> bool {
+    match ty {
+        BorshType::Array(inner, len) => *len == 0 || is_zero_sized(inner),
+        BorshType::Struct(fields) => fields.iter().all(|(_, ty)| is_zero_sized(ty)),
+        _ => false,
+    }
+}
+
+impl fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSchema => f.write_str("invalid Borsh schema"),
+            Self::UnexpectedEnd { needed } => {
+                write!(f, "unexpected end of output, {needed} more bytes expected")
+            }
+            Self::InvalidBool(tag) => write!(f, "invalid bool {tag}"),
+            Self::InvalidOptionTag(tag) => write!(f, "invalid option tag {tag}"),
+            Self::InvalidEnumVariant(tag) => write!(f, "invalid enum variant {tag}"),
+            Self::InvalidUtf8 => f.write_str("invalid UTF-8 string"),
+            Self::InvalidFloat => f.write_str("NaN float"),
+            Self::TrailingBytes(len) => write!(f, "{len} trailing bytes"),
+            Self::ZeroSizedElement => f.write_str("sequence of zero-sized elements"),
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "at `{}`: {}", self.path, self.kind)
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for anchor_lang::error::Error {
+    fn from(_: DecodeError) -> Self {
+        ChainSignaturesError::InvalidResponseOutput.into()
+    }
+}
+
+/// Serializes to JSON-friendly values: bytes as `0x`-prefixed hex, options as
+/// `null` or the value, and enums as `{variant: value}`.
+impl Serialize for BorshValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Bool(value) => serializer.serialize_bool(*value),
+            Self::Unsigned(value) => serializer.serialize_u128(*value),
+            Self::Signed(value) => serializer.serialize_i128(*value),
+            Self::F32(value) => serializer.serialize_f32(*value),
+            Self::F64(value) => serializer.serialize_f64(*value),
+            Self::String(value) => serializer.serialize_str(value),
+            Self::Bytes(bytes) => {
+                let mut hex = String::with_capacity(2 + 2 * bytes.len());
+                hex.push_str("0x");
+                for byte in bytes {
+                    hex.push_str(&format!("{byte:02x}"));
+                }
+                serializer.serialize_str(&hex)
+            }
+            Self::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            Self::Option(value) => match value {
+                Some(value) => value.serialize(serializer),
+                None => serializer.serialize_none(),
+            },
+            Self::Struct(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (name, value) in fields {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+            Self::Enum(variant, value) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(variant, value)?;
+                map.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::*;
+
+    #[derive(AnchorSerialize)]
+    struct Memo {
+        text: String,
+    }
+
+    #[derive(AnchorSerialize)]
+    enum Outcome {
+        Ok(u64),
+        Err(String),
+    }
+
+    #[derive(AnchorSerialize)]
+    struct SwapResult {
+        amount_out: u128,
+        delta: i32,
+        hashes: Vec<[u8; 4]>,
+        memo: Option<Memo>,
+        outcome: Outcome,
+    }
+
+    const SCHEMA: &[u8] = br#"{"struct":{"amount_out":"u128","delta":"i32","hashes":{"array":{"type":{"array":{"type":"u8","len":4}}}},"memo":{"option":{"struct":{"text":"string"}}},"outcome":{"enum":[{"struct":{"Ok":"u64"}},{"struct":{"Err":"string"}}]}}}"#;
+
+    fn swap_result() -> Vec<u8> {
+        borsh::to_vec(&SwapResult {
+            amount_out: u128::MAX,
+            delta: -5,
+            hashes: vec![[1, 2, 3, 4], [5, 6, 7, 8]],
+            memo: Some(Memo {
+                text: "hi".to_string(),
+            }),
+            outcome: Outcome::Err("slippage".to_string()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn decodes_nested_value() {
+        let value = decode_output(SCHEMA, &swap_result()).unwrap();
+
+        assert_eq!(
+            value,
+            BorshValue::Struct(vec![
+                ("amount_out".to_string(), BorshValue::Unsigned(u128::MAX)),
+                ("delta".to_string(), BorshValue::Signed(-5)),
+                (
+                    "hashes".to_string(),
+                    BorshValue::Array(vec![
+                        BorshValue::Bytes(vec![1, 2, 3, 4]),
+                        BorshValue::Bytes(vec![5, 6, 7, 8]),
+                    ])
+                ),
+                (
+                    "memo".to_string(),
+                    BorshValue::Option(Some(Box::new(BorshValue::Struct(vec![(
+                        "text".to_string(),
+                        BorshValue::String("hi".to_string())
+                    )]))))
+                ),
+                (
+                    "outcome".to_string(),
+                    BorshValue::Enum(
+                        "Err".to_string(),
+                        Box::new(BorshValue::String("slippage".to_string()))
+                    )
+                ),
+            ])
+        );
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"amount_out":340282366920938463463374607431768211455,"delta":-5,"hashes":["0x01020304","0x05060708"],"memo":{"text":"hi"},"outcome":{"Err":"slippage"}}"#
+        );
+    }
+
+    #[test]
+    fn decodes_enum_variant() {
+        let schema = br#"{"enum":[{"struct":{"Ok":"u64"}},{"struct":{"Err":"string"}}]}"#;
+        let value = decode_output(schema, &borsh::to_vec(&Outcome::Ok(7)).unwrap()).unwrap();
+
+        assert_eq!(
+            value,
+            BorshValue::Enum("Ok".to_string(), Box::new(BorshValue::Unsigned(7)))
+        );
+    }
+
+    #[test]
+    fn reports_path_of_mismatch() {
+        let output = swap_result();
+
+        let error = decode_output(SCHEMA, &output[..output.len() - 3]).unwrap_err();
+        assert_eq!(error.path, "outcome.Err");
+        assert_eq!(error.kind, DecodeErrorKind::UnexpectedEnd { needed: 3 });
+
+        let error = decode_output(SCHEMA, &output[..16 + 4 + 4 + 6]).unwrap_err();
+        assert_eq!(error.path, "hashes[1]");
+        assert_eq!(
+            error.to_string(),
+            "at `hashes[1]`: unexpected end of output, 2 more bytes expected"
+        );
+
+        let mut invalid = output.clone();
+        invalid[16 + 4 + 4 + 8] = 2;
+        let error = decode_output(SCHEMA, &invalid).unwrap_err();
+        assert_eq!(error.path, "memo");
+        assert_eq!(error.kind, DecodeErrorKind::InvalidOptionTag(2));
+
+        let mut extended = output;
+        extended.push(0);
+        let error = decode_output(SCHEMA, &extended).unwrap_err();
+        assert_eq!(error.path, "");
+        assert_eq!(error.kind, DecodeErrorKind::TrailingBytes(1));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let error = decode_output(br#"{"enum":[{"struct":{"A":"u8"}}]}"#, &[1, 0]).unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::InvalidEnumVariant(1));
+
+        let error = decode_output(br#""string""#, &[2, 0, 0, 0, 0xff, 0xfe]).unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::InvalidUtf8);
+
+        let error = decode_output(br#""f64""#, &f64::NAN.to_le_bytes()).unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::InvalidFloat);
+
+        let error = decode_output(br#""u257""#, &[]).unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::InvalidSchema);
+
+        let error =
+            decode_output(br#"{"array":{"type":"u64"}}"#, &[0xff, 0xff, 0xff, 0xff]).unwrap_err();
+        assert_eq!(error.path, "[0]");
+    }
+
+    #[test]
+    fn rejects_sequences_of_zero_sized_elements() {
+        let error = decode_output(br#"{"array":{"type":{"struct":{}},"len":4294967295}}"#, &[])
+            .unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::ZeroSizedElement);
+
+        let error = decode_output(
+            br#"{"array":{"type":{"struct":{"a":{"array":{"type":"u64","len":0}}}}}}"#,
+            &u32::MAX.to_le_bytes(),
+        )
+        .unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::ZeroSizedElement);
+
+        let value = decode_output(br#"{"array":{"type":{"struct":{}}}}"#, &[0, 0, 0, 0]).unwrap();
+        assert_eq!(value, BorshValue::Array(vec![]));
+    }
+}
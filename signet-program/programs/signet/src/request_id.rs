@@ -0,0 +1,120 @@
+//! # Request ID Computation
+//!
+//! Request ids link a request event to the responses delivered for it. They
+//! are computed the same way by the program, the MPC network and the client
+//! SDKs (`getRequestIdRespond` in `signet.js`).
+//!
+//! For [`sign`](crate::chain_signatures::sign) the id is the keccak256 hash of
+//! the standard Solidity ABI encoding of
+//!
+//! ```text
+//! (string sender, bytes payload, string path, uint32 key_version,
+//!  string chain_id, string algo, string dest, string params)
+//! ```
+//!
+//! where `sender` is the base58 address of the requester and `chain_id` is the
+//! CAIP-2 id of this program's chain.
+//!
+//! For [`sign_bidirectional`](crate::chain_signatures::sign_bidirectional) the
+//! id is the keccak256 hash of the packed encoding (`abi.encodePacked`) of
+//!
+//! ```text
+//! (string sender, bytes serialized_transaction, string caip2_id,
+//!  uint32 key_version, string path, string algo, string dest, string params)
+//! ```
+//!
+//! where `caip2_id` is the destination chain.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+/// Computes the request id of a [`sign`](crate::chain_signatures::sign) request.
+pub fn sign_request_id(
+    sender: &Pubkey,
+    payload: &[u8],
+    path: &str,
+    key_version: u32,
+    chain_id: &str,
+    algo: &str,
+    dest: &str,
+    params: &str,
+) -> [u8; 32] {
+    let sender = sender.to_string();
+    let mut key_version_word = [0u8; 32];
+    key_version_word[28..].copy_from_slice(&key_version.to_be_bytes());
+
+    let encoded = abi_encode(&[
+        AbiToken::Dynamic(sender.as_bytes()),
+        AbiToken::Dynamic(payload),
+        AbiToken::Dynamic(path.as_bytes()),
+        AbiToken::Word(key_version_word),
+        AbiToken::Dynamic(chain_id.as_bytes()),
+        AbiToken::Dynamic(algo.as_bytes()),
+        AbiToken::Dynamic(dest.as_bytes()),
+        AbiToken::Dynamic(params.as_bytes()),
+    ]);
+
+    keccak::hash(&encoded).to_bytes()
+}
+
+/// Computes the request id of a
+/// [`sign_bidirectional`](crate::chain_signatures::sign_bidirectional) request.
+pub fn sign_bidirectional_request_id(
+    sender: &Pubkey,
+    serialized_transaction: &[u8],
+    caip2_id: &str,
+    key_version: u32,
+    path: &str,
+    algo: &str,
+    dest: &str,
+    params: &str,
+) -> [u8; 32] {
+    let sender = sender.to_string();
+    keccak::hashv(&[
+        sender.as_bytes(),
+        serialized_transaction,
+        caip2_id.as_bytes(),
+        &key_version.to_be_bytes(),
+        path.as_bytes(),
+        algo.as_bytes(),
+        dest.as_bytes(),
+        params.as_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// A single value of an ABI-encoded tuple.
+enum AbiToken<'a> {
+    /// A static value already encoded as one 32-byte word.
+    Word([u8; 32]),
+    /// A `string` or `bytes` value.
+    Dynamic(&'a [u8]),
+}
+
+/// Standard (non-packed) Solidity ABI encoding of a tuple.
+fn abi_encode(tokens: &[AbiToken]) -> Vec<u8> {
+    let head_len = tokens.len() * 32;
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = Vec::new();
+
+    for token in tokens {
+        match token {
+            AbiToken::Word(word) => head.extend_from_slice(word),
+            AbiToken::Dynamic(bytes) => {
+                head.extend_from_slice(&abi_word((head_len + tail.len()) as u64));
+                tail.extend_from_slice(&abi_word(bytes.len() as u64));
+                tail.extend_from_slice(bytes);
+                tail.resize(tail.len().next_multiple_of(32), 0);
+            }
+        }
+    }
+
+    head.extend_from_slice(&tail);
+    head
+}
+
+/// Encodes an unsigned integer as a big-endian 32-byte ABI word.
+fn abi_word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
@@ -0,0 +1,49 @@
+//! # Response Hashing
+//!
+//! The MPC network signs every
+//! [`respond_bidirectional`](crate::chain_signatures::respond_bidirectional)
+//! response with the requester's `"solana response key"`. The signed message is
+//! the keccak256 hash of the Borsh encoding of
+//!
+//! ```text
+//! (request_id: [u8; 32], status: ExecutionStatus, serialized_output: Vec<u8>,
+//!  revert_data: Option<Vec<u8>>)
+//! ```
+//!
+//! so the execution status and revert data are authenticated together with
+//! the output.
+use crate::ExecutionStatus;
+use anchor_lang::solana_program::keccak;
+
+/// Derivation path of the key that signs bidirectional responses.
+pub const RESPONSE_KEY_PATH: &str = "solana response key";
+
+/// Computes the message hash signed for a bidirectional response.
+pub fn bidirectional_response_hash(
+    request_id: &[u8; 32],
+    status: ExecutionStatus,
+    serialized_output: &[u8],
+    revert_data: Option<&[u8]>,
+) -> [u8; 32] {
+    let mut message = Vec::with_capacity(
+        32 + 1 + 4 + serialized_output.len() + 1 + revert_data.map_or(0, |data| 4 + data.len()),
+    );
+    message.extend_from_slice(request_id);
+    message.push(status as u8);
+    push_bytes(&mut message, serialized_output);
+    match revert_data {
+        Some(data) => {
+            message.push(1);
+            push_bytes(&mut message, data);
+        }
+        None => message.push(0),
+    }
+
+    keccak::hash(&message).to_bytes()
+}
+
+/// Appends `bytes` as a Borsh `Vec<u8>` (little-endian `u32` length prefix).
+fn push_bytes(message: &mut Vec<u8>, bytes: &[u8]) {
+    message.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    message.extend_from_slice(bytes);
+}
@@ -0,0 +1,800 @@
+//! # Serialization Schemas
+//!
+//! `sign_bidirectional` takes two JSON schemas as raw bytes:
+//!
+//! | Schema | Format | Describes |
+//! |--------|--------|-----------|
+//! | `output_deserialization_schema` | Destination chain specific, ABI for EVM | The destination chain output |
+//! | `respond_serialization_schema` | Borsh | `serialized_output` of the response |
+//!
+//! [`AbiParam`] and [`BorshType`] model both formats and round-trip through
+//! serde, so schemas can be built in Rust instead of hand-written JSON:
+//!
+//! ```rust
+//! use chain_signatures::schema::{AbiParam, AbiType, BorshType};
+//!
+//! let output_schema = AbiParam::schema_bytes(&[AbiParam::new("", AbiType::Bool)]);
+//! assert_eq!(output_schema, br#"[{"name":"","type":"bool"}]"#);
+//!
+//! let respond_schema = BorshType::Struct(vec![
+//!     ("amount_out".to_string(), BorshType::U128),
+//!     ("memo".to_string(), BorshType::Option(Box::new(BorshType::String))),
+//! ])
+//! .schema_bytes();
+//! assert_eq!(
+//!     respond_schema,
+//!     br#"{"struct":{"amount_out":"u128","memo":{"option":"string"}}}"#
+//! );
+//! ```
+//!
+//! Borsh schemas use the `borsh-js` notation the MPC network serializes with.
+//! The field-list notation `[{"name":"amount_out","type":"u128"}]` and the
+//! `bytes` alias of `Vec<u8>` are accepted as well and parse to the same types.
+//! [`ToBorshSchema`] derives them from Rust types, and
+//! [`borsh_schema_struct!`](crate::borsh_schema_struct) implements it for a
+//! struct together with its definition:
+//!
+//! ```rust
+//! use chain_signatures::borsh_schema_struct;
+//! use chain_signatures::schema::ToBorshSchema;
+//!
+//! borsh_schema_struct! {
+//!     pub struct SwapResult {
+//!         pub amount_out: u128,
+//!         pub fee: u128,
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     SwapResult::schema_bytes(),
+//!     br#"{"struct":{"amount_out":"u128","fee":"u128"}}"#
+//! );
+//! ```
+//!
+//! # On-chain Validation
+//!
+//! `sign_bidirectional` parses both schemas with [`validate_schemas`] and
+//! rejects malformed ones with
+//! [`InvalidSchema`](crate::ChainSignaturesError::InvalidSchema). The respond
+//! schema is always Borsh. The output schema is parsed as ABI for `eip155`
+//! chains and as Borsh for `bip122`, `polkadot` and `solana` chains; other
+//! namespaces define their own formats and are not checked. Schema bytes end
+//! at the first NUL byte, so NUL-padded buffers are accepted, but any other
+//! byte after the terminator is rejected.
+use crate::ChainSignaturesError;
+use anchor_lang::prelude::*;
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A named parameter of an ABI schema, in the JSON ABI notation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawAbiParam", into = "RawAbiParam")]
+pub struct AbiParam {
+    pub name: String,
+    pub ty: AbiType,
+    /// Solidity type name, e.g. `struct Pool.Slot0`
+    pub internal_type: Option<String>,
+}
+
+/// Solidity ABI type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AbiType {
+    Bool,
+    Address,
+    String,
+    Bytes,
+    /// `bytes1` to `bytes32`
+    FixedBytes(usize),
+    /// `uint8` to `uint256`
+    Uint(usize),
+    /// `int8` to `int256`
+    Int(usize),
+    /// `T[]`
+    Array(Box<AbiType>),
+    /// `T[N]`
+    FixedArray(Box<AbiType>, usize),
+    /// `tuple`, with its components
+    Tuple(Vec<AbiParam>),
+}
+
+/// Borsh type in `borsh-js` schema notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BorshType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    String,
+    /// Length-prefixed sequence, `{"array":{"type":T}}`
+    Vec(Box<BorshType>),
+    /// Fixed-length sequence, `{"array":{"type":T,"len":N}}`
+    Array(Box<BorshType>, u32),
+    /// `{"option":T}`
+    Option(Box<BorshType>),
+    /// Fields in declaration order, `{"struct":{name:T,...}}`
+    Struct(Vec<(String, BorshType)>),
+    /// Variants in discriminant order, `{"enum":[{"struct":{name:T}},...]}`
+    Enum(Vec<(String, BorshType)>),
+}
+
+/// Rust types with a known Borsh schema.
+pub trait ToBorshSchema {
+    fn borsh_schema() -> BorshType;
+
+    /// JSON bytes of the schema, ready for `respond_serialization_schema`.
+    fn schema_bytes() -> Vec<u8> {
+        Self::borsh_schema().schema_bytes()
+    }
+}
+
+/// Defines a struct and implements [`ToBorshSchema`] for it.
+///
+/// Attributes, including derives, are passed through to the struct.
+#[macro_export]
+macro_rules! borsh_schema_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::schema::ToBorshSchema for $name {
+            fn borsh_schema() -> $crate::schema::BorshType {
+                $crate::schema::BorshType::Struct(vec![$((
+                    stringify!($field).to_string(),
+                    <$ty as $crate::schema::ToBorshSchema>::borsh_schema(),
+                )),*])
+            }
+        }
+    };
+}
+
+/// Parses an ABI schema.
+pub fn parse_abi_schema(bytes: &[u8]) -> Result<Vec<AbiParam>> {
+    serde_json::from_slice(trim_nul(bytes)?).map_err(|_| ChainSignaturesError::InvalidSchema.into())
+}
+
+/// Parses a Borsh schema.
+pub fn parse_borsh_schema(bytes: &[u8]) -> Result<BorshType> {
+    serde_json::from_slice(trim_nul(bytes)?).map_err(|_| ChainSignaturesError::InvalidSchema.into())
+}
+
+/// Checks the schemas of a `sign_bidirectional` request to `caip2_id`.
+pub fn validate_schemas(
+    caip2_id: &str,
+    output_deserialization_schema: &[u8],
+    respond_serialization_schema: &[u8],
+) -> Result<()> {
+    parse_borsh_schema(respond_serialization_schema)?;
+    match caip2_id.split_once(':').map(|(namespace, _)| namespace) {
+        Some("eip155") => {
+            parse_abi_schema(output_deserialization_schema)?;
+        }
+        Some("bip122" | "polkadot" | "solana") => {
+            parse_borsh_schema(output_deserialization_schema)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn trim_nul(bytes: &[u8]) -> Result<&[u8]> {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    require!(
+        bytes[end..].iter().all(|b| *b == 0),
+        ChainSignaturesError::InvalidSchema
+    );
+    Ok(&bytes[..end])
+}
+
+impl AbiParam {
+    pub fn new(name: &str, ty: AbiType) -> Self {
+        Self {
+            name: name.to_string(),
+            ty,
+            internal_type: None,
+        }
+    }
+
+    /// JSON bytes of a parameter list, ready for
+    /// `output_deserialization_schema`.
+    pub fn schema_bytes(params: &[AbiParam]) -> Vec<u8> {
+        serde_json::to_vec(params).expect("ABI schemas always serialize")
+    }
+}
+
+impl AbiType {
+    /// Parses a type string such as `uint256[2][]`. `components` are the
+    /// members of the innermost tuple and must be empty for other types.
+    pub fn parse(ty: &str, components: Vec<AbiParam>) -> std::result::Result<Self, String> {
+        if let Some(prefix) = ty.strip_suffix(']') {
+            let (inner, size) = prefix
+                .rsplit_once('[')
+                .ok_or_else(|| format!("invalid ABI type `{ty}`"))?;
+            let inner = Box::new(Self::parse(inner, components)?);
+            return match size {
+                "" => Ok(Self::Array(inner)),
+                size => match size.parse() {
+                    Ok(len) if len > 0 => Ok(Self::FixedArray(inner, len)),
+                    _ => Err(format!("invalid array length in `{ty}`")),
+                },
+            };
+        }
+
+        if ty == "tuple" {
+            return Ok(Self::Tuple(components));
+        }
+        if !components.is_empty() {
+            return Err(format!("`{ty}` has no components"));
+        }
+
+        let bits = |digits: &str| match digits {
+            "" => Some(256),
+            digits => digits
+                .parse()
+                .ok()
+                .filter(|bits| *bits % 8 == 0 && (8..=256).contains(bits)),
+        };
+        let parsed = match ty {
+            "bool" => Some(Self::Bool),
+            "address" => Some(Self::Address),
+            "string" => Some(Self::String),
+            "bytes" => Some(Self::Bytes),
+            _ => {
+                if let Some(digits) = ty.strip_prefix("uint") {
+                    bits(digits).map(Self::Uint)
+                } else if let Some(digits) = ty.strip_prefix("int") {
+                    bits(digits).map(Self::Int)
+                } else if let Some(digits) = ty.strip_prefix("bytes") {
+                    digits
+                        .parse()
+                        .ok()
+                        .filter(|len| (1..=32).contains(len))
+                        .map(Self::FixedBytes)
+                } else {
+                    None
+                }
+            }
+        };
+        parsed.ok_or_else(|| format!("unknown ABI type `{ty}`"))
+    }
+
+    /// Members of the innermost tuple, empty for non-tuple types.
+    pub fn components(&self) -> &[AbiParam] {
+        match self {
+            Self::Tuple(components) => components,
+            Self::Array(inner) | Self::FixedArray(inner, _) => inner.components(),
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for AbiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool => f.write_str("bool"),
+            Self::Address => f.write_str("address"),
+            Self::String => f.write_str("string"),
+            Self::Bytes => f.write_str("bytes"),
+            Self::FixedBytes(len) => write!(f, "bytes{len}"),
+            Self::Uint(bits) => write!(f, "uint{bits}"),
+            Self::Int(bits) => write!(f, "int{bits}"),
+            Self::Array(inner) => write!(f, "{inner}[]"),
+            Self::FixedArray(inner, len) => write!(f, "{inner}[{len}]"),
+            Self::Tuple(_) => f.write_str("tuple"),
+        }
+    }
+}
+
+/// JSON ABI parameter as it appears on the wire.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAbiParam {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    components: Vec<AbiParam>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    internal_type: Option<String>,
+}
+
+impl TryFrom<RawAbiParam> for AbiParam {
+    type Error = String;
+
+    fn try_from(raw: RawAbiParam) -> std::result::Result<Self, String> {
+        Ok(Self {
+            name: raw.name,
+            ty: AbiType::parse(&raw.ty, raw.components)?,
+            internal_type: raw.internal_type,
+        })
+    }
+}
+
+impl From<AbiParam> for RawAbiParam {
+    fn from(param: AbiParam) -> Self {
+        Self {
+            name: param.name,
+            ty: param.ty.to_string(),
+            components: param.ty.components().to_vec(),
+            internal_type: param.internal_type,
+        }
+    }
+}
+
+impl BorshType {
+    /// JSON bytes of the schema, ready for `respond_serialization_schema`.
+    pub fn schema_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Borsh schemas always serialize")
+    }
+
+    fn primitive_name(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Bool => "bool",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::U128 => "u128",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::I128 => "i128",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::String => "string",
+            _ => return None,
+        })
+    }
+
+    fn from_primitive_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => Self::Bool,
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "u128" => Self::U128,
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "i128" => Self::I128,
+            "f32" => Self::F32,
+            "f64" => Self::F64,
+            "string" => Self::String,
+            _ => return None,
+        })
+    }
+}
+
+impl Serialize for BorshType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if let Some(name) = self.primitive_name() {
+            return serializer.serialize_str(name);
+        }
+
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self {
+            Self::Vec(inner) => map.serialize_entry(
+                "array",
+                &ArraySpec {
+                    ty: inner,
+                    len: None,
+                },
+            )?,
+            Self::Array(inner, len) => map.serialize_entry(
+                "array",
+                &ArraySpec {
+                    ty: inner,
+                    len: Some(*len),
+                },
+            )?,
+            Self::Option(inner) => map.serialize_entry("option", inner)?,
+            Self::Struct(fields) => map.serialize_entry("struct", &FieldsRef(fields))?,
+            Self::Enum(variants) => {
+                let variants: Vec<BorshType> = variants
+                    .iter()
+                    .map(|variant| BorshType::Struct(vec![variant.clone()]))
+                    .collect();
+                map.serialize_entry("enum", &variants)?
+            }
+            _ => unreachable!("primitives are serialized as strings"),
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for BorshType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(BorshTypeVisitor)
+    }
+}
+
+struct BorshTypeVisitor;
+
+impl<'de> Visitor<'de> for BorshTypeVisitor {
+    type Value = BorshType;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a borsh-js schema")
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> std::result::Result<BorshType, E> {
+        if name == "bytes" {
+            return Ok(BorshType::Vec(Box::new(BorshType::U8)));
+        }
+        BorshType::from_primitive_name(name)
+            .ok_or_else(|| E::custom(format!("unknown Borsh type `{name}`")))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<BorshType, A::Error> {
+        let mut fields = Fields(Vec::new());
+        while let Some(field) = seq.next_element::<NamedField>()? {
+            fields.push::<A::Error>(field.name, field.ty)?;
+        }
+        Ok(BorshType::Struct(fields.0))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<BorshType, A::Error> {
+        let kind: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::custom("empty Borsh type"))?;
+        let ty = match kind.as_str() {
+            "array" => {
+                let spec: ArraySpecOwned = map.next_value()?;
+                match spec.len {
+                    Some(len) => BorshType::Array(Box::new(spec.ty), len),
+                    None => BorshType::Vec(Box::new(spec.ty)),
+                }
+            }
+            "option" => BorshType::Option(Box::new(map.next_value()?)),
+            "struct" => BorshType::Struct(map.next_value::<Fields>()?.0),
+            "enum" => {
+                let variants: Vec<BorshType> = map.next_value()?;
+                let variants = variants
+                    .into_iter()
+                    .map(|variant| match variant {
+                        BorshType::Struct(mut fields) if fields.len() == 1 => Ok(fields.remove(0)),
+                        _ => Err(de::Error::custom(
+                            "enum variants must be single-field structs",
+                        )),
+                    })
+                    .collect::<std::result::Result<_, _>>()?;
+                BorshType::Enum(variants)
+            }
+            kind => return Err(de::Error::custom(format!("unknown Borsh type `{kind}`"))),
+        };
+        if map.next_key::<String>()?.is_some() {
+            return Err(de::Error::custom("a Borsh type has exactly one key"));
+        }
+        Ok(ty)
+    }
+}
+
+#[derive(Serialize)]
+struct ArraySpec<'a> {
+    #[serde(rename = "type")]
+    ty: &'a BorshType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    len: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ArraySpecOwned {
+    #[serde(rename = "type")]
+    ty: BorshType,
+    len: Option<u32>,
+}
+
+/// Entry of the field-list notation.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NamedField {
+    name: String,
+    #[serde(rename = "type")]
+    ty: BorshType,
+}
+
+/// Struct fields, kept in declaration order.
+struct Fields(Vec<(String, BorshType)>);
+
+impl Fields {
+    fn push<E: de::Error>(&mut self, name: String, ty: BorshType) -> std::result::Result<(), E> {
+        if self.0.iter().any(|(existing, _)| *existing == name) {
+            return Err(E::custom(format!("duplicate field `{name}`")));
+        }
+        self.0.push((name, ty));
+        Ok(())
+    }
+}
+
+struct FieldsRef<'a>(&'a [(String, BorshType)]);
+
+impl Serialize for FieldsRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, ty) in self.0 {
+            map.serialize_entry(name, ty)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Fields {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct FieldsVisitor;
+
+        impl<'de> Visitor<'de> for FieldsVisitor {
+            type Value = Fields;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of field names to Borsh types")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Fields, A::Error> {
+                let mut fields = Fields(Vec::new());
+                while let Some((name, ty)) = map.next_entry::<String, BorshType>()? {
+                    fields.push::<A::Error>(name, ty)?;
+                }
+                Ok(fields)
+            }
+        }
+
+        deserializer.deserialize_map(FieldsVisitor)
+    }
+}
+
+macro_rules! impl_primitive_schema {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(impl ToBorshSchema for $ty {
+            fn borsh_schema() -> BorshType {
+                BorshType::$variant
+            }
+        })*
+    };
+}
+
+impl_primitive_schema! {
+    bool => Bool,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    u128 => U128,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    i128 => I128,
+    f32 => F32,
+    f64 => F64,
+    String => String,
+}
+
+impl<T: ToBorshSchema> ToBorshSchema for Vec<T> {
+    fn borsh_schema() -> BorshType {
+        BorshType::Vec(Box::new(T::borsh_schema()))
+    }
+}
+
+impl<T: ToBorshSchema, const N: usize> ToBorshSchema for [T; N] {
+    fn borsh_schema() -> BorshType {
+        BorshType::Array(Box::new(T::borsh_schema()), N as u32)
+    }
+}
+
+impl<T: ToBorshSchema> ToBorshSchema for Option<T> {
+    fn borsh_schema() -> BorshType {
+        BorshType::Option(Box::new(T::borsh_schema()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip_abi(json: &str) -> Vec<AbiParam> {
+        let params = parse_abi_schema(json.as_bytes()).unwrap();
+        assert_eq!(AbiParam::schema_bytes(&params), json.as_bytes());
+        params
+    }
+
+    fn round_trip_borsh(json: &str) -> BorshType {
+        let ty = parse_borsh_schema(json.as_bytes()).unwrap();
+        assert_eq!(ty.schema_bytes(), json.as_bytes());
+        ty
+    }
+
+    #[test]
+    fn abi_schema_round_trips() {
+        let params = round_trip_abi(r#"[{"name":"","type":"bool"}]"#);
+        assert_eq!(params, vec![AbiParam::new("", AbiType::Bool)]);
+
+        let params = round_trip_abi(
+            r#"[{"name":"amounts","type":"uint256[2][]"},{"name":"key","type":"bytes32"},{"name":"delta","type":"int128"}]"#,
+        );
+        assert_eq!(
+            params[0].ty,
+            AbiType::Array(Box::new(AbiType::FixedArray(
+                Box::new(AbiType::Uint(256)),
+                2
+            )))
+        );
+        assert_eq!(params[1].ty, AbiType::FixedBytes(32));
+        assert_eq!(params[2].ty, AbiType::Int(128));
+
+        assert_eq!(AbiType::parse("uint", vec![]), Ok(AbiType::Uint(256)));
+    }
+
+    #[test]
+    fn abi_schema_round_trips_nested_tuples() {
+        let params = round_trip_abi(
+            r#"[{"name":"pools","type":"tuple[]","components":[{"name":"token","type":"address"},{"name":"fees","type":"tuple","components":[{"name":"bps","type":"uint16"}]}],"internalType":"struct Pool[]"}]"#,
+        );
+        let components = params[0].ty.components();
+        assert_eq!(components[0].ty, AbiType::Address);
+        assert_eq!(
+            components[1].ty,
+            AbiType::Tuple(vec![AbiParam::new("bps", AbiType::Uint(16))])
+        );
+    }
+
+    #[test]
+    fn abi_schema_rejects_unknown_types() {
+        for json in [
+            r#"[{"name":"","type":"uint257"}]"#,
+            r#"[{"name":"","type":"bytes33"}]"#,
+            r#"[{"name":"","type":"bool[0]"}]"#,
+            r#"[{"name":"","type":"boolean"}]"#,
+            r#"[{"name":"","type":"bool","components":[{"name":"","type":"bool"}]}]"#,
+            r#"{"name":"","type":"bool"}"#,
+        ] {
+            assert!(parse_abi_schema(json.as_bytes()).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn borsh_schema_round_trips() {
+        assert_eq!(round_trip_borsh(r#""bool""#), BorshType::Bool);
+
+        let ty = round_trip_borsh(
+            r#"{"struct":{"success":"bool","amounts":{"array":{"type":"u128"}},"hash":{"array":{"type":"u8","len":32}},"memo":{"option":{"struct":{"text":"string"}}}}}"#,
+        );
+        assert_eq!(
+            ty,
+            BorshType::Struct(vec![
+                ("success".to_string(), BorshType::Bool),
+                (
+                    "amounts".to_string(),
+                    BorshType::Vec(Box::new(BorshType::U128))
+                ),
+                (
+                    "hash".to_string(),
+                    BorshType::Array(Box::new(BorshType::U8), 32)
+                ),
+                (
+                    "memo".to_string(),
+                    BorshType::Option(Box::new(BorshType::Struct(vec![(
+                        "text".to_string(),
+                        BorshType::String
+                    )])))
+                ),
+            ])
+        );
+
+        round_trip_borsh(r#"{"enum":[{"struct":{"ok":"u64"}},{"struct":{"err":"string"}}]}"#);
+    }
+
+    #[test]
+    fn borsh_schema_rejects_typos() {
+        for json in [
+            r#""boool""#,
+            r#"{"struct":{"a":"u129"}}"#,
+            r#"{"structs":{"a":"u8"}}"#,
+            r#"{"struct":{"a":"u8","a":"u16"}}"#,
+            r#"{"array":{"type":"u8","length":32}}"#,
+            r#"{"option":"u8","struct":{}}"#,
+            r#"{"enum":["u8"]}"#,
+            "",
+        ] {
+            assert!(parse_borsh_schema(json.as_bytes()).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn borsh_schema_accepts_field_list_notation() {
+        assert_eq!(
+            parse_borsh_schema(
+                br#"[{"name":"amount_out","type":"u128"},{"name":"output","type":"bytes"}]"#
+            )
+            .unwrap(),
+            BorshType::Struct(vec![
+                ("amount_out".to_string(), BorshType::U128),
+                (
+                    "output".to_string(),
+                    BorshType::Vec(Box::new(BorshType::U8))
+                ),
+            ])
+        );
+        assert!(
+            parse_borsh_schema(br#"[{"name":"a","type":"u8"},{"name":"a","type":"u8"}]"#).is_err()
+        );
+    }
+
+    #[test]
+    fn schema_bytes_end_at_nul() {
+        let mut padded = br#"{"struct":{"success":"bool"}}"#.to_vec();
+        padded.resize(64, 0);
+        assert!(parse_borsh_schema(&padded).is_ok());
+    }
+
+    #[test]
+    fn schema_bytes_reject_data_after_nul() {
+        let mut smuggled = br#"{"struct":{"success":"bool"}}"#.to_vec();
+        smuggled.push(0);
+        smuggled.extend_from_slice(br#"{"struct":{}}"#);
+        assert!(parse_borsh_schema(&smuggled).is_err());
+
+        let mut abi = br#"[{"name":"","type":"bool"}]"#.to_vec();
+        abi.extend_from_slice(&[0, 0, 1]);
+        assert!(parse_abi_schema(&abi).is_err());
+    }
+
+    #[test]
+    fn validates_output_schema_by_namespace() {
+        let abi = br#"[{"name":"","type":"bool"}]"#;
+        let borsh = crate::bitcoin::RESPOND_SERIALIZATION_SCHEMA;
+
+        assert!(validate_schemas("eip155:1", abi, borsh).is_ok());
+        assert!(validate_schemas("eip155:1", borsh, borsh).is_err());
+        assert!(validate_schemas("eip155:1", abi, br#"[{"name":"to","type":"address"}]"#).is_err());
+        assert!(validate_schemas(
+            "bip122:000000000019d6689c085ae165831e93",
+            crate::bitcoin::OUTPUT_DESERIALIZATION_SCHEMA,
+            borsh
+        )
+        .is_ok());
+        assert!(validate_schemas("cosmos:cosmoshub-4", b"0x00", borsh).is_ok());
+    }
+
+    borsh_schema_struct! {
+        #[allow(dead_code)]
+        struct SwapResult {
+            amount_out: u128,
+            fees: Vec<u64>,
+            memo: Option<String>,
+            hash: [u8; 32],
+        }
+    }
+
+    #[test]
+    fn derives_borsh_schema_from_rust_type() {
+        assert_eq!(
+            SwapResult::schema_bytes(),
+            br#"{"struct":{"amount_out":"u128","fees":{"array":{"type":"u64"}},"memo":{"option":"string"},"hash":{"array":{"type":"u8","len":32}}}}"#
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! # Solana → Solana Destination Chain Integration
+//!
+//! Helpers for requesting Solana transactions signed by an MPC-derived
+//! Ed25519 key through `sign_bidirectional` with a `solana:` CAIP-2 id, and
+//! for consuming the MPC response.
+//!
+//! # Derived Address
+//!
+//! The MPC network derives Ed25519 keys with the same epsilon as secp256k1
+//! keys (see [`derivation`](crate::derivation)), adding the tweak to its
+//! Ed25519 root key as in the `derive_public_key_edwards_point_ed25519`
+//! function of the MPC node's key derivation (`crypto-shared/src/kdf.rs` in
+//! <https://github.com/near/mpc>):
+//!
+//! ```text
+//! epsilon      = keccak256("sig.network v2.0.0 epsilon derivation:{chain_id}:{sender}:{path}")
+//! derived_key  = root_public_key + (epsilon mod l) * B
+//! ```
+//!
+//! where `epsilon` is read as a little-endian integer, `l` is the order of the
+//! Ed25519 base point `B`, and the derived key is the Solana address.
+//! [`derived_address`] computes it. The point arithmetic costs a large share
+//! of a transaction's compute budget, so programs should derive the address
+//! once and store it. The test vectors in
+//! `test-vectors/ed25519_derived_addresses.json` use the RFC 8032 test key
+//! `d75a9801…511a` as root and the epsilons of `derived_addresses.json`.
+//!
+//! # Building the Request
+//!
+//! `serialized_transaction` is a serialized v0 message whose fee payer or
+//! signer is the derived address. [`compile_message`] produces it:
+//!
+//! ```rust
+//! use anchor_lang::prelude::Pubkey;
+//! use anchor_lang::solana_program::hash::Hash;
+//! use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+//! use chain_signatures::solana_dest::{compile_message, derived_address};
+//! # let ed25519_root = Pubkey::from_str_const("FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z");
+//! # let (requester, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+//! # let (system_program, recent_blockhash) = (Pubkey::default(), Hash::default());
+//! # let chain_id = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+//!
+//! let vault = derived_address(&ed25519_root, chain_id, &requester, "vault")?;
+//! let transfer = Instruction::new_with_bytes(
+//!     system_program,
+//!     &[2, 0, 0, 0, 0xe8, 0x03, 0, 0, 0, 0, 0, 0],
+//!     vec![AccountMeta::new(vault, true), AccountMeta::new(recipient, false)],
+//! );
+//! let message = compile_message(&vault, &[transfer], &[], recent_blockhash)?;
+//! # assert_eq!(message[0], 0x80);
+//! # Ok::<(), anchor_lang::error::Error>(())
+//! ```
+//!
+//! The calling program then passes `message` to `sign_bidirectional` over CPI
+//! with a `solana:` CAIP-2 id (`solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1` for
+//! devnet), [`ALGO`] as `algo`, its own program id as `program_id`, and
+//! [`OUTPUT_DESERIALIZATION_SCHEMA`] / [`RESPOND_SERIALIZATION_SCHEMA`] as the
+//! schemas.
+//!
+//! # Signatures
+//!
+//! Ed25519 signs the serialized message itself. The responder packs the
+//! 64-byte signature `R || S` into a [`Signature`] with the compressed `R` in
+//! `big_r.x`; [`ed25519_signature`] unpacks it.
+//!
+//! # Response
+//!
+//! A confirmed transaction is reported as [`ExecutionOutput`] serialized with
+//! [`RESPOND_SERIALIZATION_SCHEMA`]: its logs and return data. A failed
+//! transaction is reported with [`ExecutionStatus::Reverted`] and the
+//! `TransactionError` rendered as UTF-8 text in `revert_data`.
+//! [`decode_outcome`] handles both.
+use crate::derivation::derive_epsilon;
+use crate::request_id::sign_bidirectional_request_id;
+use crate::{ChainSignaturesError, ExecutionStatus, Signature};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::message::{v0, AddressLookupTableAccount, VersionedMessage};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::Scalar;
+
+/// CAIP-2 namespace of Solana clusters.
+pub const CAIP2_NAMESPACE: &str = "solana";
+
+/// `algo` of Ed25519 requests.
+pub const ALGO: &str = "eddsa";
+
+/// `output_deserialization_schema` for Solana requests, matching [`ExecutionOutput`].
+pub const OUTPUT_DESERIALIZATION_SCHEMA: &[u8] = br#"{"struct":{"logs":{"array":{"type":"string"}},"return_data":{"option":{"struct":{"program_id":{"array":{"type":"u8","len":32}},"data":{"array":{"type":"u8"}}}}}}}"#;
+
+/// `respond_serialization_schema` for Solana requests, matching [`ExecutionOutput`].
+pub const RESPOND_SERIALIZATION_SCHEMA: &[u8] = OUTPUT_DESERIALIZATION_SCHEMA;
+
+crate::borsh_schema_struct! {
+    /// Return data set by the last program that called `set_return_data`.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct ReturnData {
+        pub program_id: [u8; 32],
+        pub data: Vec<u8>,
+    }
+}
+
+crate::borsh_schema_struct! {
+    /// `respond_bidirectional` output of a successful Solana request.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct ExecutionOutput {
+        /// Program log messages of the transaction
+        pub logs: Vec<String>,
+        pub return_data: Option<ReturnData>,
+    }
+}
+
+/// Decoded `respond_bidirectional` result of a Solana request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolanaOutcome {
+    pub success: bool,
+    /// Logs and return data, for successful transactions
+    pub output: Option<ExecutionOutput>,
+    /// `TransactionError` text, for failed transactions
+    pub error: Option<String>,
+}
+
+/// Computes `root_public_key + (epsilon mod l) * B` on Ed25519.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidRootPublicKey`] - `root_public_key` is not
+///   a valid Ed25519 point
+pub fn derive_ed25519_public_key(
+    root_public_key: &[u8; 32],
+    epsilon: &[u8; 32],
+) -> Result<[u8; 32]> {
+    let root = CompressedEdwardsY(*root_public_key)
+        .decompress()
+        .ok_or(ChainSignaturesError::InvalidRootPublicKey)?;
+    let tweak = Scalar::from_bytes_mod_order(*epsilon);
+
+    Ok((root + EdwardsPoint::mul_base(&tweak))
+        .compress()
+        .to_bytes())
+}
+
+/// Solana address of `sender`'s Ed25519 key at `path`.
+///
+/// `chain_id` is the CAIP-2 id of the source chain, the `chain_id` of this
+/// program.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidRootPublicKey`] - `root_public_key` is not
+///   a valid Ed25519 point
+pub fn derived_address(
+    root_public_key: &Pubkey,
+    chain_id: &str,
+    sender: &Pubkey,
+    path: &str,
+) -> Result<Pubkey> {
+    let epsilon = derive_epsilon(chain_id, sender, path);
+    derive_ed25519_public_key(&root_public_key.to_bytes(), &epsilon).map(Pubkey::new_from_array)
+}
+
+/// Compiles and serializes a v0 message, the `serialized_transaction`
+/// expected by `sign_bidirectional`.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidTransaction`] - The instructions cannot be
+///   compiled into a message
+pub fn compile_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<Vec<u8>> {
+    let message = v0::Message::try_compile(
+        payer,
+        instructions,
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+    .map_err(|_| ChainSignaturesError::InvalidTransaction)?;
+
+    Ok(VersionedMessage::V0(message).serialize())
+}
+
+/// Request id of a Solana request for the serialized message `message`.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidChainId`] - `caip2_id` is not a `solana:` id
+pub fn request_id(
+    sender: &Pubkey,
+    message: &[u8],
+    caip2_id: &str,
+    key_version: u32,
+    path: &str,
+    algo: &str,
+    dest: &str,
+    params: &str,
+) -> Result<[u8; 32]> {
+    match caip2_id.split_once(':') {
+        Some((CAIP2_NAMESPACE, reference)) if !reference.is_empty() => {}
+        _ => return err!(ChainSignaturesError::InvalidChainId),
+    }
+    Ok(sign_bidirectional_request_id(
+        sender,
+        message,
+        caip2_id,
+        key_version,
+        path,
+        algo,
+        dest,
+        params,
+    ))
+}
+
+/// Unpacks the 64-byte Ed25519 signature `R || S` of a response.
+pub fn ed25519_signature(signature: &Signature) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&signature.big_r.x);
+    bytes[32..].copy_from_slice(&signature.s);
+    bytes
+}
+
+/// Decodes a verified `respond_bidirectional` for a Solana request.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidResponseOutput`] - Successful response whose
+///   output is not an [`ExecutionOutput`]
+pub fn decode_outcome(
+    status: ExecutionStatus,
+    serialized_output: &[u8],
+    revert_data: Option<&[u8]>,
+) -> Result<SolanaOutcome> {
+    match status {
+        ExecutionStatus::Success => {
+            let output = ExecutionOutput::try_from_slice(serialized_output)
+                .map_err(|_| ChainSignaturesError::InvalidResponseOutput)?;
+            Ok(SolanaOutcome {
+                success: true,
+                output: Some(output),
+                error: None,
+            })
+        }
+        _ => Ok(SolanaOutcome {
+            success: false,
+            output: None,
+            error: revert_data.map(|data| String::from_utf8_lossy(data).into_owned()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ToBorshSchema;
+    use crate::test_utils::hex;
+    use anchor_lang::solana_program::instruction::AccountMeta;
+    use anchor_lang::solana_program::system_program;
+    use serde::Deserialize;
+    use std::str::FromStr;
+
+    const VECTORS: &str = include_str!("../test-vectors/ed25519_derived_addresses.json");
+
+    #[derive(Deserialize)]
+    struct Vector {
+        root_public_key: String,
+        chain_id: String,
+        sender: String,
+        path: String,
+        epsilon: String,
+        derived_public_key: String,
+        address: String,
+    }
+
+    #[test]
+    fn derives_shared_vectors() {
+        let vectors: Vec<Vector> = serde_json::from_str(VECTORS).unwrap();
+        for vector in vectors {
+            let root = Pubkey::try_from(hex(&vector.root_public_key).as_slice()).unwrap();
+            let sender = Pubkey::from_str(&vector.sender).unwrap();
+            let epsilon: [u8; 32] = hex(&vector.epsilon).try_into().unwrap();
+
+            assert_eq!(
+                derive_epsilon(&vector.chain_id, &sender, &vector.path),
+                epsilon
+            );
+            assert_eq!(
+                derive_ed25519_public_key(&root.to_bytes(), &epsilon)
+                    .unwrap()
+                    .to_vec(),
+                hex(&vector.derived_public_key)
+            );
+            assert_eq!(
+                derived_address(&root, &vector.chain_id, &sender, &vector.path)
+                    .unwrap()
+                    .to_string(),
+                vector.address
+            );
+        }
+    }
+
+    #[test]
+    fn derived_key_matches_tweaked_secret() {
+        let root_secret = Scalar::from_bytes_mod_order([0x42; 32]);
+        let root =
+            Pubkey::new_from_array(EdwardsPoint::mul_base(&root_secret).compress().to_bytes());
+        let sender = Pubkey::new_unique();
+        let chain_id = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+
+        let epsilon = derive_epsilon(chain_id, &sender, "vault");
+        let derived_secret = root_secret + Scalar::from_bytes_mod_order(epsilon);
+
+        assert_eq!(
+            derived_address(&root, chain_id, &sender, "vault")
+                .unwrap()
+                .to_bytes(),
+            EdwardsPoint::mul_base(&derived_secret)
+                .compress()
+                .to_bytes()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_root_key() {
+        // y = 2 is not the y-coordinate of an Ed25519 point.
+        let mut invalid = [0u8; 32];
+        invalid[0] = 2;
+
+        assert!(derive_ed25519_public_key(&invalid, &[1; 32]).is_err());
+    }
+
+    #[test]
+    fn compiles_transfer_message() {
+        let payer = Pubkey::new_from_array([1; 32]);
+        let recipient = Pubkey::new_from_array([2; 32]);
+        let blockhash = Hash::new_from_array([3; 32]);
+        let mut data = vec![2, 0, 0, 0];
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        let transfer = Instruction::new_with_bytes(
+            system_program::ID,
+            &data,
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(recipient, false),
+            ],
+        );
+
+        let message = compile_message(&payer, &[transfer], &[], blockhash).unwrap();
+
+        let mut expected = vec![0x80, 1, 0, 1, 3];
+        expected.extend_from_slice(&[1; 32]);
+        expected.extend_from_slice(&[2; 32]);
+        expected.extend_from_slice(&[0; 32]);
+        expected.extend_from_slice(&[3; 32]);
+        expected.extend_from_slice(&[1, 2, 2, 0, 1, 12]);
+        expected.extend_from_slice(&data);
+        expected.push(0);
+        assert_eq!(message, expected);
+    }
+
+    #[test]
+    fn schemas_match_output_type() {
+        assert_eq!(
+            ExecutionOutput::schema_bytes(),
+            OUTPUT_DESERIALIZATION_SCHEMA
+        );
+        assert!(crate::schema::validate_schemas(
+            "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1",
+            OUTPUT_DESERIALIZATION_SCHEMA,
+            RESPOND_SERIALIZATION_SCHEMA
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn decodes_execution_results() {
+        let output = ExecutionOutput {
+            logs: vec!["Program log: ok".to_string()],
+            return_data: Some(ReturnData {
+                program_id: [9; 32],
+                data: vec![1, 2, 3],
+            }),
+        };
+
+        let outcome = decode_outcome(
+            ExecutionStatus::Success,
+            &output.try_to_vec().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert!(outcome.success);
+        assert_eq!(outcome.output, Some(output));
+
+        let outcome = decode_outcome(
+            ExecutionStatus::Reverted,
+            &[],
+            Some(b"InstructionError(0, Custom(1))"),
+        )
+        .unwrap();
+        assert!(!outcome.success);
+        assert_eq!(
+            outcome.error.as_deref(),
+            Some("InstructionError(0, Custom(1))")
+        );
+
+        assert!(decode_outcome(ExecutionStatus::Success, &[1], None).is_err());
+    }
+
+    #[test]
+    fn request_id_requires_solana_chain() {
+        let sender = Pubkey::new_unique();
+        let caip2 = "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1";
+
+        assert_eq!(
+            request_id(&sender, &[1, 2], caip2, 0, "vault", ALGO, "", "").unwrap(),
+            sign_bidirectional_request_id(&sender, &[1, 2], caip2, 0, "vault", ALGO, "", "")
+        );
+        assert!(request_id(&sender, &[1, 2], "eip155:1", 0, "vault", ALGO, "", "").is_err());
+    }
+}
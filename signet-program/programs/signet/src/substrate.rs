@@ -0,0 +1,725 @@
+//! # Solana → Substrate Destination Chain Integration
+//!
+//! Helpers for requesting Substrate (Polkadot, Kusama, parachains)
+//! extrinsics through `sign_bidirectional` with a `polkadot:` CAIP-2 id, and
+//! for consuming the MPC response.
+//!
+//! ```text
+//! ┌─────────────────────────────────────────────────────────────────────────┐
+//! │                       SOLANA → SUBSTRATE FLOW                           │
+//! ├─────────────────────────────────────────────────────────────────────────┤
+//! │                                                                         │
+//! │  Solana Program              MPC Network              Substrate         │
+//! │       │                           │                       │             │
+//! │       │ sign_bidirectional()      │                       │             │
+//! │       ├──────────────────────────►│                       │             │
+//! │       │                           │ Sign payload hash     │             │
+//! │       │◄──── SignatureResponded ──┤                       │             │
+//! │       │                           │                       │             │
+//! │       │          User submits signed extrinsic ──────────►│             │
+//! │       │                           │                       │             │
+//! │       │                           │◄── ExtrinsicSuccess ──┤             │
+//! │       │                           │    / ExtrinsicFailed  │             │
+//! │       │                           │                       │             │
+//! │       │◄─ RespondBidirectional ───┤                       │             │
+//! │       │   (dispatch result)       │                       │             │
+//! │                                                                         │
+//! └─────────────────────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! # Building the Request
+//!
+//! `serialized_transaction` is the SCALE-encoded signing payload of the
+//! extrinsic: the call, the signed extensions and their implicit data.
+//! [`SigningPayload::encode`] produces it for the signed extensions of current
+//! Polkadot SDK runtimes (`CheckMortality`, `CheckNonce`,
+//! `ChargeTransactionPayment` and `CheckMetadataHash`, plus the spec version,
+//! transaction version and genesis checks):
+//!
+//! ```rust
+//! use chain_signatures::substrate::{Era, SigningPayload};
+//! # let (transfer_call, nonce, current_block) = (vec![5, 3], 0, 1000);
+//! # let (genesis_hash, birth_block_hash) = ([0x91; 32], [0xab; 32]);
+//!
+//! let era = Era::mortal(64, current_block);
+//! assert_eq!(era.birth(current_block), 1000);
+//!
+//! let payload = SigningPayload {
+//!     call: transfer_call,                 // SCALE-encoded call
+//!     era,
+//!     nonce,
+//!     tip: 0,
+//!     metadata_hash: None,
+//!     spec_version: 1_003_000,
+//!     transaction_version: 26,
+//!     genesis_hash,
+//!     block_hash: birth_block_hash,        // hash of era.birth(current_block)
+//! };
+//! let serialized_transaction = payload.encode();
+//! # assert_eq!(serialized_transaction.len(), 2 + 2 + 1 + 1 + 1 + 8 + 64 + 1);
+//! ```
+//!
+//! The calling program then passes `serialized_transaction` to
+//! `sign_bidirectional` over CPI with a `polkadot:` CAIP-2 id
+//! (`polkadot:91b171bb158e2d3848fa23a9f1c25182` for Polkadot), its own program
+//! id as `program_id`, and [`OUTPUT_DESERIALIZATION_SCHEMA`] /
+//! [`RESPOND_SERIALIZATION_SCHEMA`] as the schemas.
+//!
+//! # Signatures
+//!
+//! Substrate signs the payload itself when it is at most
+//! [`MAX_UNHASHED_PAYLOAD_LEN`] bytes and its blake2-256 hash otherwise.
+//! ECDSA signatures (`MultiSignature::Ecdsa`) are then taken over the
+//! blake2-256 hash of that message, which [`SigningPayload::signing_hash`]
+//! computes. The signing account is [`ecdsa_account_id`] of the derived key.
+//!
+//! # Response
+//!
+//! A dispatched extrinsic has no return data. Success is reported as
+//! [`ExtrinsicOutput`] `{ success: true }` serialized with
+//! [`RESPOND_SERIALIZATION_SCHEMA`]. An `ExtrinsicFailed` event is reported
+//! with [`ExecutionStatus::Reverted`] and the SCALE-encoded `DispatchError` as
+//! `revert_data`. [`decode_outcome`] handles both.
+use crate::request_id::sign_bidirectional_request_id;
+use crate::{ChainSignaturesError, ExecutionStatus};
+use anchor_lang::prelude::*;
+
+/// CAIP-2 namespace of Substrate chains.
+pub const CAIP2_NAMESPACE: &str = "polkadot";
+
+/// `output_deserialization_schema` for Substrate requests.
+pub const OUTPUT_DESERIALIZATION_SCHEMA: &[u8] = br#"{"struct":{"success":"bool"}}"#;
+
+/// `respond_serialization_schema` for Substrate requests, matching [`ExtrinsicOutput`].
+pub const RESPOND_SERIALIZATION_SCHEMA: &[u8] = br#"{"struct":{"success":"bool"}}"#;
+
+/// Longest signing payload that is signed without hashing it first.
+pub const MAX_UNHASHED_PAYLOAD_LEN: usize = 256;
+
+crate::borsh_schema_struct! {
+    /// `respond_bidirectional` output of a successful Substrate request.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ExtrinsicOutput {
+        /// Whether the extrinsic dispatched successfully
+        pub success: bool,
+    }
+}
+
+/// Transaction lifetime of an extrinsic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Era {
+    /// Valid forever, anchored at the genesis block
+    Immortal,
+    /// Valid for `period` blocks from the birth block, see [`Era::mortal`]
+    Mortal { period: u64, phase: u64 },
+}
+
+/// SCALE payload signed for an extrinsic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SigningPayload {
+    /// SCALE-encoded call, starting with the pallet and call indices
+    pub call: Vec<u8>,
+    pub era: Era,
+    pub nonce: u64,
+    pub tip: u128,
+    /// Metadata hash checked by `CheckMetadataHash`, `None` to disable the check
+    pub metadata_hash: Option<[u8; 32]>,
+    pub spec_version: u32,
+    pub transaction_version: u32,
+    pub genesis_hash: [u8; 32],
+    /// Hash of the era's birth block, the genesis hash for immortal extrinsics
+    pub block_hash: [u8; 32],
+}
+
+/// Decoded `respond_bidirectional` result of a Substrate request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DispatchOutcome {
+    pub success: bool,
+    /// Why dispatch failed, for reverted extrinsics
+    pub error: Option<DispatchError>,
+}
+
+/// `sp_runtime::DispatchError` of a failed extrinsic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DispatchError {
+    /// Error of the pallet at `pallet_index`
+    Module { pallet_index: u8, error: [u8; 4] },
+    /// Any other variant by index, with the inner error code of the `Token`,
+    /// `Arithmetic`, `Transactional` and `Trie` variants
+    Other { variant: u8, code: Option<u8> },
+}
+
+impl Era {
+    /// Mortal era of at least `period` blocks starting at `current_block`.
+    ///
+    /// The period is rounded up to a power of two between 4 and 65536.
+    pub fn mortal(period: u64, current_block: u64) -> Self {
+        let period = period
+            .checked_next_power_of_two()
+            .unwrap_or(1 << 16)
+            .clamp(4, 1 << 16);
+        let quantize_factor = (period >> 12).max(1);
+        let phase = current_block % period / quantize_factor * quantize_factor;
+        Self::Mortal { period, phase }
+    }
+
+    /// First block of the era containing `current_block`, whose hash goes
+    /// into [`SigningPayload::block_hash`].
+    pub fn birth(&self, current_block: u64) -> u64 {
+        match *self {
+            Self::Immortal => 0,
+            Self::Mortal { period, phase } => {
+                (current_block.max(phase) - phase) / period * period + phase
+            }
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match *self {
+            Self::Immortal => out.push(0),
+            Self::Mortal { period, phase } => {
+                let quantize_factor = (period >> 12).max(1);
+                let encoded = (period.trailing_zeros() - 1).clamp(1, 15) as u16
+                    | ((phase / quantize_factor) << 4) as u16;
+                out.extend_from_slice(&encoded.to_le_bytes());
+            }
+        }
+    }
+}
+
+impl SigningPayload {
+    /// Encodes `call || extra || implicit`, the `serialized_transaction`
+    /// expected by `sign_bidirectional`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.call.len() + 128);
+        out.extend_from_slice(&self.call);
+
+        self.era.encode(&mut out);
+        encode_compact(&mut out, self.nonce.into());
+        encode_compact(&mut out, self.tip);
+        out.push(self.metadata_hash.is_some() as u8);
+
+        out.extend_from_slice(&self.spec_version.to_le_bytes());
+        out.extend_from_slice(&self.transaction_version.to_le_bytes());
+        out.extend_from_slice(&self.genesis_hash);
+        out.extend_from_slice(&self.block_hash);
+        match &self.metadata_hash {
+            Some(hash) => {
+                out.push(1);
+                out.extend_from_slice(hash);
+            }
+            None => out.push(0),
+        }
+        out
+    }
+
+    /// Computes the hash the MPC network signs for this payload.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let payload = self.encode();
+        if payload.len() > MAX_UNHASHED_PAYLOAD_LEN {
+            blake2_256(&blake2_256(&payload))
+        } else {
+            blake2_256(&payload)
+        }
+    }
+}
+
+impl DispatchError {
+    /// Decodes a SCALE-encoded `DispatchError`.
+    ///
+    /// # Errors
+    ///
+    /// * [`ChainSignaturesError::InvalidResponseOutput`] - Unknown variant or
+    ///   wrong length
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let error = match bytes {
+            [3, pallet_index, error @ ..] if error.len() == 4 => Self::Module {
+                pallet_index: *pallet_index,
+                error: error.try_into().unwrap(),
+            },
+            [variant @ (7 | 8 | 9 | 14), code] => Self::Other {
+                variant: *variant,
+                code: Some(*code),
+            },
+            [variant @ (0..=2 | 4..=6 | 10..=13)] => Self::Other {
+                variant: *variant,
+                code: None,
+            },
+            _ => return err!(ChainSignaturesError::InvalidResponseOutput),
+        };
+        Ok(error)
+    }
+}
+
+/// Appends `value` in SCALE compact encoding.
+pub fn encode_compact(out: &mut Vec<u8>, value: u128) {
+    match value {
+        0..=0x3f => out.push((value as u8) << 2),
+        0x40..=0x3fff => out.extend_from_slice(&((value as u16) << 2 | 0b01).to_le_bytes()),
+        0x4000..=0x3fff_ffff => out.extend_from_slice(&((value as u32) << 2 | 0b10).to_le_bytes()),
+        _ => {
+            let bytes = value.to_le_bytes();
+            let len = bytes.len() - bytes.iter().rev().take_while(|b| **b == 0).count();
+            out.push(((len as u8 - 4) << 2) | 0b11);
+            out.extend_from_slice(&bytes[..len]);
+        }
+    }
+}
+
+/// Substrate account id of an ECDSA key: the blake2-256 hash of the
+/// compressed public key (`x || y`, big-endian).
+pub fn ecdsa_account_id(public_key: &[u8; 64]) -> [u8; 32] {
+    let mut compressed = [0u8; 33];
+    compressed[0] = 0x02 | (public_key[63] & 1);
+    compressed[1..].copy_from_slice(&public_key[..32]);
+    blake2_256(&compressed)
+}
+
+/// Request id of a Substrate request.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidChainId`] - `caip2_id` is not a `polkadot:` id
+pub fn request_id(
+    sender: &Pubkey,
+    payload: &SigningPayload,
+    caip2_id: &str,
+    key_version: u32,
+    path: &str,
+    algo: &str,
+    dest: &str,
+    params: &str,
+) -> Result<[u8; 32]> {
+    match caip2_id.split_once(':') {
+        Some((CAIP2_NAMESPACE, reference)) if !reference.is_empty() => {}
+        _ => return err!(ChainSignaturesError::InvalidChainId),
+    }
+    Ok(sign_bidirectional_request_id(
+        sender,
+        &payload.encode(),
+        caip2_id,
+        key_version,
+        path,
+        algo,
+        dest,
+        params,
+    ))
+}
+
+/// Decodes a verified `respond_bidirectional` for a Substrate request.
+///
+/// # Errors
+///
+/// * [`ChainSignaturesError::InvalidResponseOutput`] - Successful response whose
+///   output is not an [`ExtrinsicOutput`], or revert data that is not a
+///   `DispatchError`
+pub fn decode_outcome(
+    status: ExecutionStatus,
+    serialized_output: &[u8],
+    revert_data: Option<&[u8]>,
+) -> Result<DispatchOutcome> {
+    match status {
+        ExecutionStatus::Success => {
+            let output = ExtrinsicOutput::try_from_slice(serialized_output)
+                .map_err(|_| ChainSignaturesError::InvalidResponseOutput)?;
+            Ok(DispatchOutcome {
+                success: output.success,
+                error: None,
+            })
+        }
+        ExecutionStatus::Reverted => Ok(DispatchOutcome {
+            success: false,
+            error: revert_data.map(DispatchError::decode).transpose()?,
+        }),
+        _ => Ok(DispatchOutcome {
+            success: false,
+            error: None,
+        }),
+    }
+}
+
+/// BLAKE2b with a 32-byte digest (RFC 7693), Substrate's `blake2_256`.
+pub fn blake2_256(data: &[u8]) -> [u8; 32] {
+    let mut h = BLAKE2B_IV;
+    h[0] ^= 0x0101_0000 ^ 32;
+
+    if data.is_empty() {
+        blake2b_compress(&mut h, &[0; 128], 0, true);
+    }
+    let mut offset = 0u128;
+    for (i, chunk) in data.chunks(128).enumerate() {
+        let mut block = [0u8; 128];
+        block[..chunk.len()].copy_from_slice(chunk);
+        offset += chunk.len() as u128;
+        blake2b_compress(&mut h, &block, offset, (i + 1) * 128 >= data.len());
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_exact_mut(8).zip(h) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09_e667_f3bc_c908,
+    0xbb67_ae85_84ca_a73b,
+    0x3c6e_f372_fe94_f82b,
+    0xa54f_f53a_5f1d_36f1,
+    0x510e_527f_ade6_82d1,
+    0x9b05_688c_2b3e_6c1f,
+    0x1f83_d9ab_fb41_bd6b,
+    0x5be0_cd19_137e_2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+fn blake2b_compress(h: &mut [u64; 8], block: &[u8; 128], offset: u128, last: bool) {
+    let mut m = [0u64; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= offset as u64;
+    v[13] ^= (offset >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    for round in 0..12 {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        blake2b_mix(&mut v, [0, 4, 8, 12], m[s[0]], m[s[1]]);
+        blake2b_mix(&mut v, [1, 5, 9, 13], m[s[2]], m[s[3]]);
+        blake2b_mix(&mut v, [2, 6, 10, 14], m[s[4]], m[s[5]]);
+        blake2b_mix(&mut v, [3, 7, 11, 15], m[s[6]], m[s[7]]);
+        blake2b_mix(&mut v, [0, 5, 10, 15], m[s[8]], m[s[9]]);
+        blake2b_mix(&mut v, [1, 6, 11, 12], m[s[10]], m[s[11]]);
+        blake2b_mix(&mut v, [2, 7, 8, 13], m[s[12]], m[s[13]]);
+        blake2b_mix(&mut v, [3, 4, 9, 14], m[s[14]], m[s[15]]);
+    }
+
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+}
+
+fn blake2b_mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4], x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ToBorshSchema;
+    use crate::test_utils::{hex, hex32};
+    use blake2::digest::consts::U32;
+    use blake2::{Blake2b, Digest};
+    use parity_scale_codec::{Compact, Encode};
+
+    /// Hash of Polkadot block 0, as in the `polkadot:91b171bb158e2d3848fa23a9f1c25182`
+    /// CAIP-2 id.
+    const POLKADOT_GENESIS: &str =
+        "91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3";
+
+    /// `Balances::transfer_keep_alive` of 1.2345678900 DOT to Alice: pallet 5,
+    /// call 3 in the Polkadot runtime, a `MultiAddress::Id` and a compact
+    /// balance.
+    const TRANSFER_CALL: &str =
+        "050300d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d07341cdcdf02";
+
+    fn transfer(nonce: u64, tip: u128, metadata_hash: Option<[u8; 32]>) -> SigningPayload {
+        SigningPayload {
+            call: hex(TRANSFER_CALL),
+            era: Era::Immortal,
+            nonce,
+            tip,
+            metadata_hash,
+            spec_version: 1_003_000,
+            transaction_version: 26,
+            genesis_hash: hex32(POLKADOT_GENESIS),
+            block_hash: hex32(POLKADOT_GENESIS),
+        }
+    }
+
+    /// `blake2_256` of the RustCrypto implementation.
+    fn reference_blake2_256(data: &[u8]) -> [u8; 32] {
+        Blake2b::<U32>::digest(data).into()
+    }
+
+    /// Signing payload encoded with `parity-scale-codec`, the codec used by
+    /// Substrate runtimes, from the SCALE-encoded `era`.
+    fn reference_encoding(payload: &SigningPayload, era: &[u8]) -> Vec<u8> {
+        let extra = (
+            Compact(payload.nonce),
+            Compact(payload.tip),
+            payload.metadata_hash.is_some() as u8,
+        );
+        let implicit = (
+            payload.spec_version,
+            payload.transaction_version,
+            payload.genesis_hash,
+            payload.block_hash,
+            payload.metadata_hash,
+        );
+        [
+            payload.call.clone(),
+            era.to_vec(),
+            extra.encode(),
+            implicit.encode(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn blake2_256_matches_rfc_vectors() {
+        assert_eq!(
+            blake2_256(b""),
+            hex32("0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8")
+        );
+        assert_eq!(
+            blake2_256(b"abc"),
+            hex32("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319")
+        );
+    }
+
+    #[test]
+    fn blake2_256_matches_reference_implementation() {
+        let data: Vec<u8> = (0..=600u32).map(|i| (i * 31 % 251) as u8).collect();
+        for len in [1, 127, 128, 129, 255, 256, 257, 384, 600] {
+            assert_eq!(
+                blake2_256(&data[..len]),
+                reference_blake2_256(&data[..len]),
+                "{len}"
+            );
+        }
+    }
+
+    #[test]
+    fn compact_encoding_matches_scale() {
+        for value in [
+            0,
+            1,
+            63,
+            64,
+            16_383,
+            16_384,
+            (1 << 30) - 1,
+            1 << 30,
+            u32::MAX.into(),
+            u64::MAX.into(),
+            1 << 70,
+            u128::MAX,
+        ] {
+            let mut out = Vec::new();
+            encode_compact(&mut out, value);
+            assert_eq!(out, Compact(value).encode(), "{value}");
+        }
+    }
+
+    /// Cases from the `generic::era` tests of `sp-runtime`
+    /// (<https://github.com/paritytech/polkadot-sdk/blob/master/substrate/primitives/runtime/src/generic/era.rs>).
+    #[test]
+    fn mortal_era_matches_sp_runtime() {
+        for (period, current, expected) in [
+            (64, 42, (64, 42)),
+            (32768, 20000, (32768, 20000)),
+            (200, 513, (256, 1)),
+            (2, 1, (4, 1)),
+            (4, 5, (4, 1)),
+            (1_000_000, 1_000_001, (65536, 1_000_001 % 65536 / 4 * 4)),
+        ] {
+            assert_eq!(
+                Era::mortal(period, current),
+                Era::Mortal {
+                    period: expected.0,
+                    phase: expected.1
+                }
+            );
+        }
+
+        let mut out = Vec::new();
+        Era::mortal(64, 42).encode(&mut out);
+        assert_eq!(out, [5 + 42 % 16 * 16, 42 / 16]);
+        let mut out = Vec::new();
+        Era::mortal(32768, 20000).encode(&mut out);
+        assert_eq!(out, [(14 + 2500 % 16 * 16) as u8, (2500 / 16) as u8]);
+
+        let era = Era::mortal(4, 6);
+        for current in 6..10 {
+            assert_eq!(era.birth(current), 6);
+        }
+        assert_ne!(era.birth(10), 6);
+        assert_ne!(era.birth(5), 6);
+        Era::mortal(4, 3).birth(1);
+    }
+
+    #[test]
+    fn immortal_transfer_payload() {
+        let payload = transfer(7, 0, None);
+
+        let expected = reference_encoding(&payload, &[0]);
+        assert_eq!(payload.encode(), expected);
+        assert_eq!(payload.signing_hash(), reference_blake2_256(&expected));
+    }
+
+    #[test]
+    fn payload_with_metadata_hash() {
+        let payload = transfer(300, 1 << 70, Some([0x11; 32]));
+
+        let expected = reference_encoding(&payload, &[0]);
+        assert_eq!(payload.encode(), expected);
+        assert_eq!(payload.signing_hash(), reference_blake2_256(&expected));
+    }
+
+    #[test]
+    fn long_payload_is_hashed_before_signing() {
+        let remark: Vec<u8> = (0..=255u8).chain(0..44).collect();
+        let mut call = vec![0, 0];
+        encode_compact(&mut call, remark.len() as u128);
+        call.extend(remark);
+
+        let payload = SigningPayload {
+            call,
+            era: Era::mortal(64, 42),
+            nonce: 1,
+            tip: 1_000_000,
+            metadata_hash: None,
+            spec_version: 1_003_000,
+            transaction_version: 26,
+            genesis_hash: hex32(POLKADOT_GENESIS),
+            block_hash: [0xab; 32],
+        };
+
+        let expected = reference_encoding(&payload, &[5 + 42 % 16 * 16, 42 / 16]);
+        assert_eq!(payload.encode(), expected);
+        assert!(expected.len() > MAX_UNHASHED_PAYLOAD_LEN);
+        assert_eq!(
+            payload.signing_hash(),
+            reference_blake2_256(&reference_blake2_256(&expected))
+        );
+    }
+
+    /// `//Alice` ECDSA development key, as printed by
+    /// `subkey inspect --scheme ecdsa //Alice`, uncompressed.
+    #[test]
+    fn ecdsa_account_id_of_alice() {
+        let public_key = hex(
+            "0a1091341fe5664bfa1782d5e04779689068c916b04cb365ec3153755684d9a1\
+             2da8d65fae6d63a4abca410b7e50d50cd95d36001c28712fd2adf944adb03b12",
+        );
+
+        assert_eq!(
+            ecdsa_account_id(&public_key.try_into().unwrap()),
+            hex32("01e552298e47454041ea31273b4b630c64c104e4514aa3643490b8aaca9cf8ed")
+        );
+    }
+
+    #[test]
+    fn request_id_requires_polkadot_namespace() {
+        let sender = Pubkey::new_unique();
+        let payload = transfer(0, 0, None);
+        let caip2_id = "polkadot:91b171bb158e2d3848fa23a9f1c25182";
+
+        assert_eq!(
+            request_id(&sender, &payload, caip2_id, 0, "path", "", "", "").unwrap(),
+            sign_bidirectional_request_id(
+                &sender,
+                &payload.encode(),
+                caip2_id,
+                0,
+                "path",
+                "",
+                "",
+                ""
+            )
+        );
+        assert!(request_id(&sender, &payload, "eip155:1", 0, "path", "", "", "").is_err());
+    }
+
+    #[test]
+    fn schemas_match_output_type() {
+        assert_eq!(
+            ExtrinsicOutput::schema_bytes(),
+            RESPOND_SERIALIZATION_SCHEMA
+        );
+        assert!(crate::schema::validate_schemas(
+            "polkadot:91b171bb158e2d3848fa23a9f1c25182",
+            OUTPUT_DESERIALIZATION_SCHEMA,
+            RESPOND_SERIALIZATION_SCHEMA
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn decodes_dispatch_results() {
+        assert_eq!(
+            decode_outcome(ExecutionStatus::Success, &[1], None).unwrap(),
+            DispatchOutcome {
+                success: true,
+                error: None
+            }
+        );
+
+        // `Balances::InsufficientBalance` on Polkadot
+        assert_eq!(
+            decode_outcome(ExecutionStatus::Reverted, &[], Some(&hex("030502000000"))).unwrap(),
+            DispatchOutcome {
+                success: false,
+                error: Some(DispatchError::Module {
+                    pallet_index: 5,
+                    error: [2, 0, 0, 0]
+                })
+            }
+        );
+        assert_eq!(
+            decode_outcome(ExecutionStatus::Reverted, &[], Some(&[7, 0]))
+                .unwrap()
+                .error,
+            Some(DispatchError::Other {
+                variant: 7,
+                code: Some(0)
+            })
+        );
+        assert_eq!(
+            decode_outcome(ExecutionStatus::Reverted, &[], Some(&[2]))
+                .unwrap()
+                .error,
+            Some(DispatchError::Other {
+                variant: 2,
+                code: None
+            })
+        );
+        assert_eq!(
+            decode_outcome(ExecutionStatus::Dropped, &[], None).unwrap(),
+            DispatchOutcome {
+                success: false,
+                error: None
+            }
+        );
+
+        assert!(decode_outcome(ExecutionStatus::Success, &[], None).is_err());
+        assert!(decode_outcome(ExecutionStatus::Reverted, &[], Some(&[3, 5])).is_err());
+        assert!(decode_outcome(ExecutionStatus::Reverted, &[], Some(&[15])).is_err());
+    }
+}
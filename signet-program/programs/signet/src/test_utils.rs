@@ -0,0 +1,14 @@
+//! Helpers shared by the unit tests of the destination chain modules.
+
+/// Decodes a hex string without a `0x` prefix.
+pub fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Decodes a 32-byte hex string without a `0x` prefix.
+pub fn hex32(s: &str) -> [u8; 32] {
+    hex(s).try_into().unwrap()
+}
@@ -0,0 +1,451 @@
+//! In-process runtime for testing the Signet programs without a validator.
+//!
+//! [`Svm`] runs transactions natively against the Anchor entrypoints of
+//! `chain_signatures` and `proxy_test_cpi`. Accounts are serialized in the
+//! runtime's input layout, so reallocation, assignment and closing behave as
+//! on-chain, and the `solana_program` syscall stubs are replaced to provide:
+//!
+//! * cross-program invocations, with signer and writable privilege checks and
+//!   PDA signers derived from the caller's seeds
+//! * the system program instructions Anchor uses (`Transfer`,
+//!   `CreateAccount`, `Allocate` and `Assign`)
+//! * the `Clock` and `Rent` sysvars, the instructions sysvar and return data
+//!
+//! After each invocation the runtime rules on account changes are enforced:
+//! only the owner may debit lamports or modify data, read-only accounts must
+//! not change and lamports are conserved. A transaction fails if it leaves a
+//! writable account with a balance below its rent-exempt minimum. The
+//! runtime's size limits apply as well: the invocation stack height, the
+//! instruction data and account counts of CPIs, account growth per
+//! instruction and the maximum account size.
+//!
+//! Transactions are atomic and charge no fees. Signatures are not verified;
+//! the accounts passed as signers are trusted to have signed.
+//!
+//! Programs run as native code, so compute units, heap and stack usage are not
+//! metered: an instruction that passes here can still exceed its compute
+//! budget or the 4 KiB BPF stack frame on-chain. Those limits are covered by
+//! the `anchor test` suite and the benchmarks, which run the compiled programs
+//! on a validator.
+//!
+//! This runtime stands in for an SVM that loads the compiled
+//! `target/deploy/*.so` programs, such as LiteSVM or Mollusk, which would also
+//! meter compute units. Moving to one needs the SBF toolchain
+//! (`cargo build-sbf`) to produce those programs and a release built against
+//! the Solana 2.x crates Anchor 0.31 uses; neither is available to this
+//! workspace yet. The tests only use [`Svm`]'s transaction and account
+//! methods, so such a move is confined to this crate.
+
+mod runtime;
+pub mod signet;
+
+use std::collections::{HashMap, HashSet};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::Event;
+
+use runtime::{Meta, Runtime};
+
+/// Anchor entrypoint of a program, as generated by `#[program]`.
+pub type Entrypoint = for<'a> fn(&'a Pubkey, &'a [AccountInfo<'a>], &'a [u8]) -> ProgramResult;
+
+/// Owner of executable program accounts.
+const BPF_LOADER_UPGRADEABLE: Pubkey =
+    Pubkey::from_str_const("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// Owner of sysvar accounts.
+const SYSVAR: Pubkey = Pubkey::from_str_const("Sysvar1111111111111111111111111111111111111");
+
+/// Owner of native programs.
+const NATIVE_LOADER: Pubkey = Pubkey::from_str_const("NativeLoader1111111111111111111111111111111");
+
+/// State of an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self {
+            lamports: 0,
+            data: Vec::new(),
+            owner: anchor_lang::system_program::ID,
+            executable: false,
+        }
+    }
+}
+
+impl Account {
+    /// Deserializes the Anchor account stored in the data.
+    pub fn deserialize<T: AccountDeserialize>(&self) -> T {
+        T::try_deserialize(&mut self.data.as_slice()).expect("invalid account data")
+    }
+}
+
+/// Reasons a transaction fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SvmError {
+    /// A program returned an error.
+    Program(ProgramError),
+    /// The instruction targets a program that is not loaded.
+    UnsupportedProgramId(Pubkey),
+    /// A CPI names an account the caller did not pass.
+    MissingAccount(Pubkey),
+    /// A CPI requests a signer or writable privilege the caller lacks.
+    PrivilegeEscalation(Pubkey),
+    /// A program debited lamports from an account it does not own.
+    ExternalAccountLamportSpend(Pubkey),
+    /// A program modified the data of an account it does not own.
+    ExternalAccountDataModified(Pubkey),
+    /// A program changed the lamports of a read-only account.
+    ReadonlyLamportChange(Pubkey),
+    /// A program modified the data of a read-only account.
+    ReadonlyDataModified(Pubkey),
+    /// A program reassigned an account it does not own or that still holds data.
+    ModifiedProgramId(Pubkey),
+    /// A program changed whether an account is executable.
+    ExecutableModified(Pubkey),
+    /// The lamports of the instruction's accounts do not add up.
+    UnbalancedInstruction,
+    /// The transaction left an account below its rent-exempt minimum.
+    InsufficientFundsForRent(Pubkey),
+    /// A CPI would exceed the maximum invocation stack height.
+    CallDepth,
+    /// A CPI passes more instruction data, accounts or account infos than the
+    /// runtime accepts.
+    CpiLimitExceeded,
+}
+
+/// The error of a failed Anchor `require!`, as returned by [`Svm::process`].
+pub fn anchor_error(code: impl Into<u32>) -> SvmError {
+    SvmError::Program(ProgramError::Custom(code.into()))
+}
+
+/// Event emitted through `emit_cpi!`.
+#[derive(Clone, Debug)]
+pub struct CpiEvent {
+    /// Program that emitted the event.
+    pub program_id: Pubkey,
+    /// Discriminator followed by the borsh-encoded event.
+    pub data: Vec<u8>,
+}
+
+/// Outcome of a successful transaction.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionMeta {
+    /// Events emitted through `emit_cpi!`, in order.
+    pub events: Vec<CpiEvent>,
+    /// Return data of the last instruction that set it.
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+impl TransactionMeta {
+    /// Decodes the emitted events of type `E`.
+    pub fn events<E: Event>(&self) -> Vec<E> {
+        self.events
+            .iter()
+            .filter_map(|event| event.data.strip_prefix(E::DISCRIMINATOR))
+            .map(|data| E::try_from_slice(data).expect("invalid event data"))
+            .collect()
+    }
+
+    /// Decodes the return data.
+    pub fn return_value<T: AnchorDeserialize>(&self) -> Option<T> {
+        let (_, data) = self.return_data.as_ref()?;
+        Some(T::try_from_slice(data).expect("invalid return data"))
+    }
+}
+
+/// In-process runtime with `chain_signatures` and `proxy_test_cpi` loaded.
+#[derive(Clone)]
+pub struct Svm {
+    accounts: HashMap<Pubkey, Account>,
+    programs: HashMap<Pubkey, Entrypoint>,
+    slot: u64,
+}
+
+impl Default for Svm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Svm {
+    pub fn new() -> Self {
+        runtime::install_stubs();
+
+        let mut svm = Self {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+            slot: 1,
+        };
+        svm.set_account(
+            anchor_lang::system_program::ID,
+            Account {
+                lamports: 1,
+                owner: NATIVE_LOADER,
+                executable: true,
+                ..Account::default()
+            },
+        );
+        svm.add_program(chain_signatures::ID, chain_signatures::entry);
+        svm.add_program(proxy_test_cpi::ID, proxy_test_cpi::entry);
+        svm
+    }
+
+    /// Loads a program under `program_id`.
+    pub fn add_program(&mut self, program_id: Pubkey, entrypoint: Entrypoint) {
+        self.programs.insert(program_id, entrypoint);
+        self.set_account(
+            program_id,
+            Account {
+                lamports: 1,
+                owner: BPF_LOADER_UPGRADEABLE,
+                executable: true,
+                ..Account::default()
+            },
+        );
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&Account> {
+        self.accounts.get(key)
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.accounts.insert(key, account);
+    }
+
+    /// Sum of the lamports of all accounts.
+    pub fn total_lamports(&self) -> u128 {
+        self.accounts
+            .values()
+            .map(|account| u128::from(account.lamports))
+            .sum()
+    }
+
+    /// Lamports of `key`, zero for missing accounts.
+    pub fn balance(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    /// Credits `lamports` to `key`, creating a system account if needed.
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        self.accounts.entry(*key).or_default().lamports += lamports;
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    pub fn warp_to_slot(&mut self, slot: u64) {
+        self.slot = slot;
+    }
+
+    /// Executes `instructions` atomically as one transaction signed by
+    /// `signers`, committing the resulting state only on success.
+    pub fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<TransactionMeta, SvmError> {
+        let message = message_privileges(instructions);
+        if message
+            .iter()
+            .any(|(key, (is_signer, _))| *is_signer && !signers.contains(key))
+        {
+            return Err(SvmError::Program(ProgramError::MissingRequiredSignature));
+        }
+
+        let mut accounts: HashMap<Pubkey, Account> = message
+            .keys()
+            .chain(
+                instructions
+                    .iter()
+                    .map(|instruction| &instruction.program_id),
+            )
+            .map(|key| (*key, self.accounts.get(key).cloned().unwrap_or_default()))
+            .collect();
+
+        let _runtime = Runtime::enter(self.programs.clone(), self.slot);
+        for (index, instruction) in instructions.iter().enumerate() {
+            accounts.insert(
+                solana_instructions_sysvar::ID,
+                instructions_sysvar(instructions, &message, index),
+            );
+            let metas: Vec<Meta> = instruction
+                .accounts
+                .iter()
+                .map(|meta| {
+                    let (is_signer, is_writable) = message[&meta.pubkey];
+                    Meta {
+                        pubkey: meta.pubkey,
+                        is_signer,
+                        is_writable,
+                    }
+                })
+                .collect();
+            runtime::clear_return_data();
+            runtime::execute(
+                &instruction.program_id,
+                &metas,
+                &mut accounts,
+                &instruction.data,
+            )?;
+        }
+        let rent = Rent::default();
+        for (key, (_, is_writable)) in &message {
+            let post = &accounts[key];
+            if !is_writable || post.lamports == 0 || post.executable {
+                continue;
+            }
+            if post.lamports >= rent.minimum_balance(post.data.len()) {
+                continue;
+            }
+            // Accounts that were already below the minimum may stay there as
+            // long as they do not grow or gain lamports.
+            let was_rent_paying = self.accounts.get(key).is_some_and(|pre| {
+                pre.lamports > 0
+                    && pre.lamports < rent.minimum_balance(pre.data.len())
+                    && pre.data.len() == post.data.len()
+                    && post.lamports <= pre.lamports
+            });
+            if !was_rent_paying {
+                return Err(SvmError::InsufficientFundsForRent(*key));
+            }
+        }
+
+        let meta = runtime::take_meta();
+        accounts.remove(&solana_instructions_sysvar::ID);
+        for (key, account) in accounts {
+            if account.lamports == 0 {
+                self.accounts.remove(&key);
+            } else {
+                self.accounts.insert(key, account);
+            }
+        }
+        Ok(meta)
+    }
+
+    /// Executes `instructions` like [`Svm::process`] without committing any
+    /// state.
+    pub fn simulate(
+        &self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<TransactionMeta, SvmError> {
+        self.clone().process(instructions, signers)
+    }
+}
+
+/// Maximum size of a serialized transaction.
+pub const PACKET_DATA_SIZE: usize = 1232;
+
+/// Size of the legacy transaction carrying `instructions`, including one
+/// signature per signer. [`Svm::process`] does not enforce
+/// [`PACKET_DATA_SIZE`], as the same instructions can exceed it through CPI.
+pub fn transaction_size(instructions: &[Instruction]) -> usize {
+    fn compact_len(len: usize) -> usize {
+        match len {
+            0..0x80 => 1,
+            0x80..0x4000 => 2,
+            _ => 3,
+        }
+    }
+
+    let message = message_privileges(instructions);
+    let signers = message.values().filter(|(is_signer, _)| *is_signer).count();
+    let keys = message
+        .keys()
+        .chain(
+            instructions
+                .iter()
+                .map(|instruction| &instruction.program_id),
+        )
+        .collect::<HashSet<_>>()
+        .len();
+    let instructions_size: usize = instructions
+        .iter()
+        .map(|instruction| {
+            1 + compact_len(instruction.accounts.len())
+                + instruction.accounts.len()
+                + compact_len(instruction.data.len())
+                + instruction.data.len()
+        })
+        .sum();
+
+    compact_len(signers)
+        + 64 * signers
+        + 3
+        + compact_len(keys)
+        + 32 * keys
+        + 32
+        + compact_len(instructions.len())
+        + instructions_size
+}
+
+/// Signer and writable privileges of every account in the transaction.
+fn message_privileges(instructions: &[Instruction]) -> HashMap<Pubkey, (bool, bool)> {
+    let mut privileges: HashMap<Pubkey, (bool, bool)> = HashMap::new();
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        let entry = privileges.entry(meta.pubkey).or_default();
+        entry.0 |= meta.is_signer;
+        entry.1 |= meta.is_writable;
+    }
+    privileges
+}
+
+/// Instructions sysvar account while executing instruction `index`.
+fn instructions_sysvar(
+    instructions: &[Instruction],
+    message: &HashMap<Pubkey, (bool, bool)>,
+    index: usize,
+) -> Account {
+    use solana_instruction::{BorrowedAccountMeta, BorrowedInstruction};
+
+    let borrowed: Vec<BorrowedInstruction> = instructions
+        .iter()
+        .map(|instruction| BorrowedInstruction {
+            program_id: &instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| {
+                    let (is_signer, is_writable) = message[&meta.pubkey];
+                    BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer,
+                        is_writable,
+                    }
+                })
+                .collect(),
+            data: &instruction.data,
+        })
+        .collect();
+
+    let mut data = solana_instructions_sysvar::construct_instructions_data(&borrowed);
+    solana_instructions_sysvar::store_current_index_checked(&mut data, index as u16).unwrap();
+
+    Account {
+        lamports: 0,
+        data,
+        owner: SYSVAR,
+        executable: false,
+    }
+}
+
+/// Distinct keys of `metas`, in order.
+fn unique_keys(metas: &[Meta]) -> Vec<Pubkey> {
+    let mut seen = HashSet::new();
+    metas
+        .iter()
+        .filter(|meta| seen.insert(meta.pubkey))
+        .map(|meta| meta.pubkey)
+        .collect()
+}
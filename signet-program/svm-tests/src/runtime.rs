@@ -0,0 +1,580 @@
+//! Program execution, syscall stubs and the system program.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::entrypoint::{
+    deserialize, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER,
+};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::syscalls::{
+    MAX_CPI_ACCOUNT_INFOS, MAX_CPI_INSTRUCTION_ACCOUNTS, MAX_CPI_INSTRUCTION_DATA_LEN,
+};
+use solana_system_interface::error::SystemError;
+use solana_system_interface::instruction::SystemInstruction;
+use solana_system_interface::MAX_PERMITTED_DATA_LENGTH;
+
+use crate::{unique_keys, Account, CpiEvent, Entrypoint, SvmError, TransactionMeta};
+
+/// Number of programs that may be on the invocation stack at once: the
+/// top-level instruction and four nested CPIs.
+const MAX_INVOKE_STACK_HEIGHT: usize = 5;
+
+/// Account of an invocation with the privileges granted to the callee.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Meta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Program invocation in progress.
+struct Frame {
+    program_id: Pubkey,
+    /// State of the accounts as last seen by the runtime; updated around CPIs
+    /// so changes made by callees are not attributed to this program.
+    accounts: HashMap<Pubkey, Account>,
+}
+
+/// Per-thread state of the transaction being executed.
+#[derive(Default)]
+struct State {
+    programs: HashMap<Pubkey, Entrypoint>,
+    slot: u64,
+    stack: Vec<Frame>,
+    /// Error of a failed CPI. It aborts the transaction even if the caller
+    /// ignores the error it was handed.
+    failure: Option<SvmError>,
+    meta: TransactionMeta,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Guard of the per-thread transaction state.
+pub(crate) struct Runtime;
+
+impl Runtime {
+    pub fn enter(programs: HashMap<Pubkey, Entrypoint>, slot: u64) -> Self {
+        STATE.with(|state| {
+            *state.borrow_mut() = State {
+                programs,
+                slot,
+                ..State::default()
+            }
+        });
+        Self
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        STATE.with(|state| *state.borrow_mut() = State::default());
+    }
+}
+
+pub(crate) fn take_meta() -> TransactionMeta {
+    STATE.with(|state| std::mem::take(&mut state.borrow_mut().meta))
+}
+
+pub(crate) fn clear_return_data() {
+    STATE.with(|state| state.borrow_mut().meta.return_data = None);
+}
+
+fn current_program() -> Pubkey {
+    STATE.with(|state| {
+        state
+            .borrow()
+            .stack
+            .last()
+            .expect("syscall outside of a program")
+            .program_id
+    })
+}
+
+/// Runs `program_id` on `metas`, updating `accounts` if it succeeds.
+pub(crate) fn execute(
+    program_id: &Pubkey,
+    metas: &[Meta],
+    accounts: &mut HashMap<Pubkey, Account>,
+    data: &[u8],
+) -> std::result::Result<(), SvmError> {
+    let entrypoint = STATE
+        .with(|state| state.borrow().programs.get(program_id).copied())
+        .ok_or(SvmError::UnsupportedProgramId(*program_id))?;
+
+    let keys = unique_keys(metas);
+    let pre: HashMap<Pubkey, Account> = keys
+        .iter()
+        .map(|key| (*key, accounts.get(key).cloned().unwrap_or_default()))
+        .collect();
+    let mut input = Input::serialize(program_id, metas, &pre, data);
+
+    STATE.with(|state| {
+        state.borrow_mut().stack.push(Frame {
+            program_id: *program_id,
+            accounts: pre.clone(),
+        })
+    });
+    let result = {
+        // SAFETY: the input is laid out as `deserialize` expects and outlives
+        // the account infos.
+        let (program_id, infos, data) = unsafe { deserialize(input.as_mut_ptr()) };
+        entrypoint(program_id, &infos, data)
+    };
+    let (frame, failure) = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let frame = state.stack.pop().expect("unbalanced invocation");
+        (frame, state.failure.take())
+    });
+    if let Some(failure) = failure {
+        return Err(failure);
+    }
+    result.map_err(SvmError::Program)?;
+
+    let post = input.accounts();
+    for key in &keys {
+        let is_writable = metas
+            .iter()
+            .any(|meta| meta.pubkey == *key && meta.is_writable);
+        verify_change(
+            program_id,
+            &frame.accounts[key],
+            &post[key],
+            is_writable,
+            key,
+        )?;
+    }
+    let total = |accounts: &HashMap<Pubkey, Account>| -> u128 {
+        accounts
+            .values()
+            .map(|account| account.lamports as u128)
+            .sum()
+    };
+    if total(&pre) != total(&post) {
+        return Err(SvmError::UnbalancedInstruction);
+    }
+
+    accounts.extend(post);
+    Ok(())
+}
+
+/// Checks that `program_id` was allowed to change `pre` into `post`.
+fn verify_change(
+    program_id: &Pubkey,
+    pre: &Account,
+    post: &Account,
+    is_writable: bool,
+    key: &Pubkey,
+) -> std::result::Result<(), SvmError> {
+    let owned = pre.owner == *program_id;
+
+    if pre.owner != post.owner && !(is_writable && owned && post.data.iter().all(|byte| *byte == 0))
+    {
+        return Err(SvmError::ModifiedProgramId(*key));
+    }
+    if pre.lamports != post.lamports {
+        if !is_writable {
+            return Err(SvmError::ReadonlyLamportChange(*key));
+        }
+        if post.lamports < pre.lamports && !owned {
+            return Err(SvmError::ExternalAccountLamportSpend(*key));
+        }
+    }
+    if pre.data != post.data {
+        if !is_writable {
+            return Err(SvmError::ReadonlyDataModified(*key));
+        }
+        if !owned {
+            return Err(SvmError::ExternalAccountDataModified(*key));
+        }
+    }
+    if pre.executable != post.executable {
+        return Err(SvmError::ExecutableModified(*key));
+    }
+
+    Ok(())
+}
+
+/// Program input in the layout of the runtime's BPF loader.
+struct Input {
+    /// `u64` words keep the buffer aligned for `deserialize`.
+    buffer: Vec<u64>,
+    /// Key and offset of every distinct account.
+    offsets: Vec<(Pubkey, usize)>,
+}
+
+impl Input {
+    fn serialize(
+        program_id: &Pubkey,
+        metas: &[Meta],
+        accounts: &HashMap<Pubkey, Account>,
+        data: &[u8],
+    ) -> Self {
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::new();
+        let mut positions: HashMap<Pubkey, usize> = HashMap::new();
+
+        bytes.extend_from_slice(&(metas.len() as u64).to_le_bytes());
+        for (index, meta) in metas.iter().enumerate() {
+            if let Some(position) = positions.get(&meta.pubkey) {
+                bytes.push(*position as u8);
+                bytes.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            positions.insert(meta.pubkey, index);
+
+            let account = &accounts[&meta.pubkey];
+            offsets.push((meta.pubkey, bytes.len()));
+            bytes.push(NON_DUP_MARKER);
+            bytes.push(meta.is_signer as u8);
+            bytes.push(meta.is_writable as u8);
+            bytes.push(account.executable as u8);
+            bytes.extend_from_slice(&[0; 4]);
+            bytes.extend_from_slice(meta.pubkey.as_ref());
+            bytes.extend_from_slice(account.owner.as_ref());
+            bytes.extend_from_slice(&account.lamports.to_le_bytes());
+            bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&account.data);
+            bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            bytes.resize(bytes.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+            bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(program_id.as_ref());
+
+        let mut buffer = vec![0u64; bytes.len().div_ceil(8)];
+        // SAFETY: the buffer holds at least `bytes.len()` bytes.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                buffer.as_mut_ptr() as *mut u8,
+                bytes.len(),
+            );
+        }
+
+        Self { buffer, offsets }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.buffer.as_mut_ptr() as *mut u8
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: reinterprets the initialized words as bytes.
+        unsafe {
+            std::slice::from_raw_parts(self.buffer.as_ptr() as *const u8, self.buffer.len() * 8)
+        }
+    }
+
+    /// State of the accounts after the program ran.
+    fn accounts(&self) -> HashMap<Pubkey, Account> {
+        let bytes = self.bytes();
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+
+        self.offsets
+            .iter()
+            .map(|(key, offset)| {
+                let data_len = u64_at(offset + 80) as usize;
+                let account = Account {
+                    lamports: u64_at(offset + 72),
+                    data: bytes[offset + 88..offset + 88 + data_len].to_vec(),
+                    owner: Pubkey::try_from(&bytes[offset + 40..offset + 72]).unwrap(),
+                    executable: bytes[offset + 3] != 0,
+                };
+                (*key, account)
+            })
+            .collect()
+    }
+}
+
+fn account_of(info: &AccountInfo) -> Account {
+    Account {
+        lamports: info.lamports(),
+        data: info.data.borrow().to_vec(),
+        owner: *info.owner,
+        executable: info.executable,
+    }
+}
+
+/// Applies the state a callee left in `account` to the caller's `info`.
+fn write_back(info: &AccountInfo, account: &Account) -> std::result::Result<(), SvmError> {
+    if *info.owner != account.owner {
+        info.assign(&account.owner);
+    }
+    if info.lamports() != account.lamports {
+        **info.try_borrow_mut_lamports().map_err(SvmError::Program)? = account.lamports;
+    }
+    if *info.data.borrow() != account.data {
+        info.resize(account.data.len()).map_err(SvmError::Program)?;
+        info.try_borrow_mut_data()
+            .map_err(SvmError::Program)?
+            .copy_from_slice(&account.data);
+    }
+    Ok(())
+}
+
+/// Checks the caller's changes to `infos` since its last sync and records
+/// their current state.
+fn sync_caller(infos: &[AccountInfo], verify: bool) -> std::result::Result<(), SvmError> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let frame = state
+            .stack
+            .last_mut()
+            .expect("syscall outside of a program");
+        for info in infos {
+            let current = account_of(info);
+            if verify {
+                if let Some(pre) = frame.accounts.get(info.key) {
+                    verify_change(&frame.program_id, pre, &current, info.is_writable, info.key)?;
+                }
+            }
+            frame.accounts.insert(*info.key, current);
+        }
+        Ok(())
+    })
+}
+
+fn invoke(
+    instruction: &Instruction,
+    infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> std::result::Result<(), SvmError> {
+    let caller = current_program();
+    let height = STATE.with(|state| state.borrow().stack.len());
+    if height >= MAX_INVOKE_STACK_HEIGHT {
+        return Err(SvmError::CallDepth);
+    }
+    if instruction.data.len() as u64 > MAX_CPI_INSTRUCTION_DATA_LEN
+        || instruction.accounts.len() > MAX_CPI_INSTRUCTION_ACCOUNTS as usize
+        || infos.len() > MAX_CPI_ACCOUNT_INFOS
+    {
+        return Err(SvmError::CpiLimitExceeded);
+    }
+    let pda_signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| SvmError::Program(ProgramError::InvalidSeeds))?;
+
+    let mut metas = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let info = infos
+            .iter()
+            .find(|info| *info.key == meta.pubkey)
+            .ok_or(SvmError::MissingAccount(meta.pubkey))?;
+        let is_signer = info.is_signer || pda_signers.contains(&meta.pubkey);
+        if (meta.is_signer && !is_signer) || (meta.is_writable && !info.is_writable) {
+            return Err(SvmError::PrivilegeEscalation(meta.pubkey));
+        }
+        metas.push(Meta {
+            pubkey: meta.pubkey,
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        });
+    }
+
+    sync_caller(infos, true)?;
+    if instruction.program_id == anchor_lang::system_program::ID {
+        process_system_instruction(&metas, infos, &instruction.data)?;
+    } else {
+        let mut accounts: HashMap<Pubkey, Account> = infos
+            .iter()
+            .map(|info| (*info.key, account_of(info)))
+            .collect();
+        execute(
+            &instruction.program_id,
+            &metas,
+            &mut accounts,
+            &instruction.data,
+        )?;
+        for info in infos {
+            write_back(info, &accounts[info.key])?;
+        }
+
+        if instruction.program_id == caller {
+            if let Some(data) = instruction.data.strip_prefix(EVENT_IX_TAG_LE) {
+                STATE.with(|state| {
+                    state.borrow_mut().meta.events.push(CpiEvent {
+                        program_id: caller,
+                        data: data.to_vec(),
+                    })
+                });
+            }
+        }
+    }
+    sync_caller(infos, false)
+}
+
+/// The subset of the system program used by Anchor.
+fn process_system_instruction(
+    metas: &[Meta],
+    infos: &[AccountInfo],
+    data: &[u8],
+) -> std::result::Result<(), SvmError> {
+    let account = |index: usize| -> std::result::Result<(&AccountInfo, &Meta), SvmError> {
+        let meta = metas
+            .get(index)
+            .ok_or(SvmError::Program(ProgramError::NotEnoughAccountKeys))?;
+        let info = infos.iter().find(|info| *info.key == meta.pubkey).unwrap();
+        Ok((info, meta))
+    };
+    let require_signer = |meta: &Meta| {
+        if meta.is_signer {
+            Ok(())
+        } else {
+            Err(SvmError::Program(ProgramError::MissingRequiredSignature))
+        }
+    };
+    let require_unused = |info: &AccountInfo| {
+        if info.data_is_empty() && *info.owner == anchor_lang::system_program::ID {
+            Ok(())
+        } else {
+            Err(system_error(SystemError::AccountAlreadyInUse))
+        }
+    };
+    let instruction: SystemInstruction = bincode::deserialize(data)
+        .map_err(|_| SvmError::Program(ProgramError::InvalidInstructionData))?;
+
+    match instruction {
+        SystemInstruction::Transfer { lamports } => {
+            let (from, from_meta) = account(0)?;
+            let (to, to_meta) = account(1)?;
+            require_signer(from_meta)?;
+            transfer(from, from_meta, to, to_meta, lamports)
+        }
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let (from, from_meta) = account(0)?;
+            let (to, to_meta) = account(1)?;
+            require_signer(from_meta)?;
+            require_signer(to_meta)?;
+            if to.lamports() > 0 {
+                return Err(system_error(SystemError::AccountAlreadyInUse));
+            }
+            require_unused(to)?;
+            transfer(from, from_meta, to, to_meta, lamports)?;
+            allocate(to, space)?;
+            to.assign(&owner);
+            Ok(())
+        }
+        SystemInstruction::Allocate { space } => {
+            let (info, meta) = account(0)?;
+            require_signer(meta)?;
+            require_unused(info)?;
+            allocate(info, space)
+        }
+        SystemInstruction::Assign { owner } => {
+            let (info, meta) = account(0)?;
+            require_signer(meta)?;
+            if *info.owner != anchor_lang::system_program::ID {
+                return Err(SvmError::ModifiedProgramId(*info.key));
+            }
+            info.assign(&owner);
+            Ok(())
+        }
+        _ => Err(SvmError::Program(ProgramError::InvalidInstructionData)),
+    }
+}
+
+fn system_error(error: SystemError) -> SvmError {
+    SvmError::Program(ProgramError::Custom(error as u32))
+}
+
+fn transfer(
+    from: &AccountInfo,
+    from_meta: &Meta,
+    to: &AccountInfo,
+    to_meta: &Meta,
+    lamports: u64,
+) -> std::result::Result<(), SvmError> {
+    if !from_meta.is_writable {
+        return Err(SvmError::ReadonlyLamportChange(from_meta.pubkey));
+    }
+    if !to_meta.is_writable {
+        return Err(SvmError::ReadonlyLamportChange(to_meta.pubkey));
+    }
+    if !from.data_is_empty() || *from.owner != anchor_lang::system_program::ID {
+        return Err(SvmError::Program(ProgramError::InvalidArgument));
+    }
+    if from.lamports() < lamports {
+        return Err(system_error(SystemError::ResultWithNegativeLamports));
+    }
+    **from.try_borrow_mut_lamports().map_err(SvmError::Program)? -= lamports;
+    **to.try_borrow_mut_lamports().map_err(SvmError::Program)? += lamports;
+    Ok(())
+}
+
+fn allocate(info: &AccountInfo, space: u64) -> std::result::Result<(), SvmError> {
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        return Err(system_error(SystemError::InvalidAccountDataLength));
+    }
+    info.resize(space as usize).map_err(SvmError::Program)
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        invoke(instruction, account_infos, signers_seeds).map_err(|error| {
+            let program_error = match &error {
+                SvmError::Program(program_error) => program_error.clone(),
+                _ => ProgramError::InvalidArgument,
+            };
+            STATE.with(|state| state.borrow_mut().failure = Some(error));
+            program_error
+        })
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            slot: STATE.with(|state| state.borrow().slot),
+            ..Clock::default()
+        };
+        // SAFETY: `Clock::get` passes a pointer to a `Clock`.
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: `Rent::get` passes a pointer to a `Rent`.
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        STATE.with(|state| state.borrow().meta.return_data.clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program_id = current_program();
+        STATE.with(|state| state.borrow_mut().meta.return_data = Some((program_id, data.to_vec())));
+    }
+}
+
+static INSTALL_STUBS: Once = Once::new();
+
+/// Routes the `solana_program` syscalls of every thread to this runtime.
+pub(crate) fn install_stubs() {
+    INSTALL_STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+}
@@ -0,0 +1,653 @@
+//! Fixtures for the `chain_signatures` program: PDAs, instruction builders and
+//! an MPC network signing with a fixed root key.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+use chain_signatures::derivation::derive_epsilon;
+use chain_signatures::request_id::sign_request_id;
+use chain_signatures::schema::{AbiParam, AbiType, BorshType};
+use chain_signatures::{
+    accounts, instruction, AffinePoint, ErrorResponse, ExecutionStatus, Signature, RATE_LIMIT_SEED,
+    REQUEST_BUFFER_SEED, RESPONSE_RECORD_SEED, SIGNATURE_REQUEST_SEED,
+};
+
+use crate::Svm;
+
+/// Chain id the program is initialized with by [`setup`].
+pub const CHAIN_ID: &str = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+
+/// Signature deposit set by [`setup`].
+pub const DEPOSIT: u64 = 1_000_000;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &chain_signatures::ID).0
+}
+
+pub fn program_state() -> Pubkey {
+    pda(&[b"program-state"])
+}
+
+pub fn root_public_key() -> Pubkey {
+    pda(&[b"root-public-key"])
+}
+
+pub fn key_version_registry() -> Pubkey {
+    pda(&[b"key-version-registry"])
+}
+
+pub fn rate_limit_config() -> Pubkey {
+    pda(&[b"rate-limit-config"])
+}
+
+/// `RequesterRateLimit` PDA of a requester.
+pub fn rate_limit(requester: &Pubkey) -> Pubkey {
+    pda(&[RATE_LIMIT_SEED, requester.as_ref()])
+}
+
+pub fn event_authority() -> Pubkey {
+    pda(&[b"__event_authority"])
+}
+
+pub fn signature_request(request_id: &[u8; 32]) -> Pubkey {
+    pda(&[SIGNATURE_REQUEST_SEED, request_id])
+}
+
+pub fn response_record(request_id: &[u8; 32]) -> Pubkey {
+    pda(&[RESPONSE_RECORD_SEED, request_id])
+}
+
+pub fn request_buffer(owner: &Pubkey, buffer_id: u64) -> Pubkey {
+    pda(&[
+        REQUEST_BUFFER_SEED,
+        owner.as_ref(),
+        &buffer_id.to_le_bytes(),
+    ])
+}
+
+fn signet_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: chain_signatures::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize(admin: Pubkey, signature_deposit: u64, chain_id: &str) -> Instruction {
+    signet_instruction(
+        accounts::Initialize {
+            program_state: program_state(),
+            admin,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::Initialize {
+            signature_deposit,
+            chain_id: chain_id.to_string(),
+        },
+    )
+}
+
+pub fn set_root_public_key(admin: Pubkey, public_key: [u8; 64]) -> Instruction {
+    signet_instruction(
+        accounts::SetRootPublicKey {
+            program_state: program_state(),
+            root_public_key: root_public_key(),
+            admin,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::SetRootPublicKey { public_key },
+    )
+}
+
+pub fn set_key_version(
+    admin: Pubkey,
+    key_version: u32,
+    public_key: [u8; 64],
+    active: bool,
+    deprecation_slot: Option<u64>,
+) -> Instruction {
+    signet_instruction(
+        accounts::SetKeyVersion {
+            program_state: program_state(),
+            key_version_registry: key_version_registry(),
+            admin,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::SetKeyVersion {
+            key_version,
+            public_key,
+            active,
+            deprecation_slot,
+        },
+    )
+}
+
+pub fn update_deposit(admin: Pubkey, new_deposit: u64) -> Instruction {
+    signet_instruction(
+        accounts::AdminOnly {
+            program_state: program_state(),
+            admin,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::UpdateDeposit { new_deposit },
+    )
+}
+
+pub fn update_chain_id(admin: Pubkey, chain_id: &str) -> Instruction {
+    signet_instruction(
+        accounts::AdminOnly {
+            program_state: program_state(),
+            admin,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::UpdateChainId {
+            chain_id: chain_id.to_string(),
+        },
+    )
+}
+
+pub fn set_rate_limit(
+    admin: Pubkey,
+    max_requests: u32,
+    window_slots: u64,
+    global_max_requests: u32,
+) -> Instruction {
+    signet_instruction(
+        accounts::SetRateLimit {
+            program_state: program_state(),
+            rate_limit_config: rate_limit_config(),
+            admin,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::SetRateLimit {
+            max_requests,
+            window_slots,
+            global_max_requests,
+        },
+    )
+}
+
+pub fn withdraw_funds(admin: Pubkey, recipient: Pubkey, amount: u64) -> Instruction {
+    signet_instruction(
+        accounts::WithdrawFunds {
+            program_state: program_state(),
+            admin,
+            recipient,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::WithdrawFunds { amount },
+    )
+}
+
+pub fn get_signature_deposit() -> Instruction {
+    signet_instruction(
+        accounts::GetSignatureDeposit {
+            program_state: program_state(),
+        },
+        instruction::GetSignatureDeposit {},
+    )
+}
+
+/// Arguments of a `sign` request.
+#[derive(Clone, Debug, Default)]
+pub struct SignRequest {
+    pub payload: [u8; 32],
+    pub key_version: u32,
+    pub path: String,
+    pub algo: String,
+    pub dest: String,
+    pub params: String,
+}
+
+impl SignRequest {
+    pub fn new(payload: [u8; 32], path: &str) -> Self {
+        Self {
+            payload,
+            path: path.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Request id of this request made by `requester`.
+    pub fn request_id(&self, requester: &Pubkey) -> [u8; 32] {
+        sign_request_id(
+            requester,
+            &self.payload,
+            &self.path,
+            self.key_version,
+            CHAIN_ID,
+            &self.algo,
+            &self.dest,
+            &self.params,
+        )
+    }
+
+    pub fn data(&self) -> instruction::Sign {
+        instruction::Sign {
+            payload: self.payload,
+            key_version: self.key_version,
+            path: self.path.clone(),
+            algo: self.algo.clone(),
+            dest: self.dest.clone(),
+            params: self.params.clone(),
+        }
+    }
+}
+
+pub fn sign(requester: Pubkey, fee_payer: Option<Pubkey>, request: &SignRequest) -> Instruction {
+    signet_instruction(
+        accounts::Sign {
+            program_state: program_state(),
+            key_version_registry: key_version_registry(),
+            requester,
+            fee_payer,
+            signature_request: Some(signature_request(&request.request_id(&requester))),
+            allowlist_entry: None,
+            rate_limit_config: rate_limit_config(),
+            rate_limit: None,
+            system_program: anchor_lang::system_program::ID,
+            instructions: Some(solana_instructions_sysvar::ID),
+            response_record: Some(response_record(&request.request_id(&requester))),
+            event_authority: event_authority(),
+            program: chain_signatures::ID,
+        },
+        request.data(),
+    )
+}
+
+/// `instruction`, a request built by this module, with the `rate_limit`
+/// account of `requester` instead of `None`.
+pub fn with_rate_limit(mut instruction: Instruction, requester: &Pubkey) -> Instruction {
+    let config = instruction
+        .accounts
+        .iter()
+        .position(|meta| meta.pubkey == rate_limit_config())
+        .expect("not a rate limited instruction");
+    instruction.accounts[config + 1] = AccountMeta::new(rate_limit(requester), false);
+    instruction
+}
+
+/// Arguments of a `sign_bidirectional` request.
+#[derive(Clone, Debug, Default)]
+pub struct SignBidirectionalRequest {
+    pub serialized_transaction: Vec<u8>,
+    pub caip2_id: String,
+    pub key_version: u32,
+    pub path: String,
+    pub algo: String,
+    pub dest: String,
+    pub params: String,
+    pub program_id: Pubkey,
+    pub output_deserialization_schema: Vec<u8>,
+    pub respond_serialization_schema: Vec<u8>,
+}
+
+impl SignBidirectionalRequest {
+    /// Request for `serialized_transaction` on `caip2_id` expecting a `bool`
+    /// output and an empty response.
+    pub fn new(serialized_transaction: Vec<u8>, caip2_id: &str, path: &str) -> Self {
+        let output_deserialization_schema = if caip2_id.starts_with("eip155:") {
+            AbiParam::schema_bytes(&[AbiParam::new("", AbiType::Bool)])
+        } else {
+            BorshType::Bool.schema_bytes()
+        };
+        Self {
+            serialized_transaction,
+            caip2_id: caip2_id.to_string(),
+            path: path.to_string(),
+            output_deserialization_schema,
+            respond_serialization_schema: BorshType::Struct(vec![]).schema_bytes(),
+            ..Self::default()
+        }
+    }
+
+    /// Serialized transaction, output schema and respond schema as stored in
+    /// a request buffer.
+    pub fn buffer_payload(&self) -> Vec<u8> {
+        [
+            &self.serialized_transaction[..],
+            &self.output_deserialization_schema,
+            &self.respond_serialization_schema,
+        ]
+        .concat()
+    }
+
+    pub fn data(&self) -> instruction::SignBidirectional {
+        instruction::SignBidirectional {
+            serialized_transaction: self.serialized_transaction.clone(),
+            caip2_id: self.caip2_id.clone(),
+            key_version: self.key_version,
+            path: self.path.clone(),
+            algo: self.algo.clone(),
+            dest: self.dest.clone(),
+            params: self.params.clone(),
+            program_id: self.program_id,
+            output_deserialization_schema: self.output_deserialization_schema.clone(),
+            respond_serialization_schema: self.respond_serialization_schema.clone(),
+        }
+    }
+}
+
+pub fn sign_bidirectional(
+    requester: Pubkey,
+    fee_payer: Option<Pubkey>,
+    request: &SignBidirectionalRequest,
+) -> Instruction {
+    signet_instruction(
+        accounts::SignBidirectional {
+            program_state: program_state(),
+            key_version_registry: key_version_registry(),
+            requester,
+            fee_payer,
+            allowlist_entry: None,
+            rate_limit_config: rate_limit_config(),
+            rate_limit: None,
+            system_program: anchor_lang::system_program::ID,
+            instructions: Some(solana_instructions_sysvar::ID),
+            event_authority: event_authority(),
+            program: chain_signatures::ID,
+        },
+        request.data(),
+    )
+}
+
+/// `quote_sign_bidirectional` for `request`; neither account needs to sign.
+pub fn quote_sign_bidirectional(
+    requester: Pubkey,
+    fee_payer: Option<Pubkey>,
+    request: &SignBidirectionalRequest,
+) -> Instruction {
+    signet_instruction(
+        accounts::QuoteSignBidirectional {
+            program_state: program_state(),
+            key_version_registry: key_version_registry(),
+            requester,
+            fee_payer,
+            allowlist_entry: None,
+            rate_limit_config: rate_limit_config(),
+            rate_limit: None,
+        },
+        instruction::QuoteSignBidirectional {
+            serialized_transaction: request.serialized_transaction.clone(),
+            caip2_id: request.caip2_id.clone(),
+            key_version: request.key_version,
+            path: request.path.clone(),
+            algo: request.algo.clone(),
+            dest: request.dest.clone(),
+            params: request.params.clone(),
+            _program_id: request.program_id,
+            output_deserialization_schema: request.output_deserialization_schema.clone(),
+            respond_serialization_schema: request.respond_serialization_schema.clone(),
+        },
+    )
+}
+
+pub fn init_request_buffer(
+    owner: Pubkey,
+    payer: Pubkey,
+    buffer_id: u64,
+    request: &SignBidirectionalRequest,
+) -> Instruction {
+    signet_instruction(
+        accounts::InitRequestBuffer {
+            owner,
+            payer,
+            request_buffer: request_buffer(&owner, buffer_id),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::InitRequestBuffer {
+            buffer_id,
+            transaction_len: request.serialized_transaction.len() as u32,
+            output_deserialization_schema_len: request.output_deserialization_schema.len() as u32,
+            respond_serialization_schema_len: request.respond_serialization_schema.len() as u32,
+        },
+    )
+}
+
+pub fn write_request_buffer(
+    owner: Pubkey,
+    buffer_id: u64,
+    offset: u32,
+    bytes: Vec<u8>,
+) -> Instruction {
+    signet_instruction(
+        accounts::WriteRequestBuffer {
+            owner,
+            request_buffer: request_buffer(&owner, buffer_id),
+        },
+        instruction::WriteRequestBuffer {
+            buffer_id,
+            offset,
+            bytes,
+        },
+    )
+}
+
+pub fn close_request_buffer(owner: Pubkey, rent_payer: Pubkey, buffer_id: u64) -> Instruction {
+    signet_instruction(
+        accounts::CloseRequestBuffer {
+            owner,
+            request_buffer: request_buffer(&owner, buffer_id),
+            rent_payer,
+        },
+        instruction::CloseRequestBuffer { buffer_id },
+    )
+}
+
+/// `write_request_buffer` instructions uploading the payload of `request` in
+/// chunks of `chunk_len` bytes.
+pub fn upload_request_buffer(
+    owner: Pubkey,
+    buffer_id: u64,
+    request: &SignBidirectionalRequest,
+    chunk_len: usize,
+) -> Vec<Instruction> {
+    request
+        .buffer_payload()
+        .chunks(chunk_len)
+        .enumerate()
+        .map(|(index, chunk)| {
+            write_request_buffer(owner, buffer_id, (index * chunk_len) as u32, chunk.to_vec())
+        })
+        .collect()
+}
+
+pub fn sign_bidirectional_from_buffer(
+    requester: Pubkey,
+    fee_payer: Option<Pubkey>,
+    rent_payer: Pubkey,
+    buffer_id: u64,
+    request: &SignBidirectionalRequest,
+) -> Instruction {
+    signet_instruction(
+        accounts::SignBidirectionalFromBuffer {
+            program_state: program_state(),
+            key_version_registry: key_version_registry(),
+            requester,
+            fee_payer,
+            allowlist_entry: None,
+            rate_limit_config: rate_limit_config(),
+            rate_limit: None,
+            request_buffer: request_buffer(&requester, buffer_id),
+            rent_payer,
+            system_program: anchor_lang::system_program::ID,
+            instructions: Some(solana_instructions_sysvar::ID),
+            event_authority: event_authority(),
+            program: chain_signatures::ID,
+        },
+        instruction::SignBidirectionalFromBuffer {
+            buffer_id,
+            caip2_id: request.caip2_id.clone(),
+            key_version: request.key_version,
+            path: request.path.clone(),
+            algo: request.algo.clone(),
+            dest: request.dest.clone(),
+            params: request.params.clone(),
+            program_id: request.program_id,
+        },
+    )
+}
+
+/// `respond` with the request and response record PDAs of `request_ids` and
+/// `rent_payer`, which receives the rent of tracked requests, as remaining
+/// accounts.
+pub fn respond(
+    responder: Pubkey,
+    rent_payer: Pubkey,
+    request_ids: &[[u8; 32]],
+    signatures: Vec<Signature>,
+) -> Instruction {
+    let mut instruction = signet_instruction(
+        accounts::Respond {
+            responder,
+            root_public_key: Some(root_public_key()),
+            key_version_registry: key_version_registry(),
+            system_program: anchor_lang::system_program::ID,
+            event_authority: event_authority(),
+            program: chain_signatures::ID,
+        },
+        instruction::Respond {
+            request_ids: request_ids.to_vec(),
+            signatures,
+        },
+    );
+    for request_id in request_ids {
+        instruction.accounts.extend([
+            AccountMeta::new(signature_request(request_id), false),
+            AccountMeta::new(response_record(request_id), false),
+            AccountMeta::new(rent_payer, false),
+        ]);
+    }
+    instruction
+}
+
+/// `respond_error` with the response record PDAs of `errors` as remaining
+/// accounts.
+pub fn respond_error(responder: Pubkey, errors: Vec<ErrorResponse>) -> Instruction {
+    let records: Vec<AccountMeta> = errors
+        .iter()
+        .map(|error| AccountMeta::new_readonly(response_record(&error.request_id), false))
+        .collect();
+    let mut instruction = signet_instruction(
+        accounts::RespondError {
+            responder,
+            event_authority: event_authority(),
+            program: chain_signatures::ID,
+        },
+        instruction::RespondError { errors },
+    );
+    instruction.accounts.extend(records);
+    instruction
+}
+
+pub fn respond_bidirectional(
+    responder: Pubkey,
+    request_id: [u8; 32],
+    status: ExecutionStatus,
+    serialized_output: Vec<u8>,
+    revert_data: Option<Vec<u8>>,
+    signature: Signature,
+) -> Instruction {
+    signet_instruction(
+        accounts::ReadRespond {
+            responder,
+            event_authority: event_authority(),
+            program: chain_signatures::ID,
+        },
+        instruction::RespondBidirectional {
+            request_id,
+            status,
+            serialized_output,
+            revert_data,
+            signature,
+        },
+    )
+}
+
+/// MPC network signing with a fixed root key.
+pub struct Mpc {
+    root_secret: libsecp256k1::SecretKey,
+}
+
+impl Default for Mpc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mpc {
+    pub fn new() -> Self {
+        Self::from_secret([0x42; 32])
+    }
+
+    /// MPC whose root secret key is `root_secret`, e.g. a rotated key.
+    pub fn from_secret(root_secret: [u8; 32]) -> Self {
+        Self {
+            root_secret: libsecp256k1::SecretKey::parse(&root_secret).unwrap(),
+        }
+    }
+
+    /// Uncompressed root public key without the `0x04` prefix.
+    pub fn root_public_key(&self) -> [u8; 64] {
+        libsecp256k1::PublicKey::from_secret_key(&self.root_secret).serialize()[1..]
+            .try_into()
+            .unwrap()
+    }
+
+    /// Signs `message_hash` with the key derived for `sender` at `path`.
+    pub fn sign(&self, sender: &Pubkey, path: &str, message_hash: &[u8; 32]) -> Signature {
+        let mut secret = self.root_secret;
+        secret
+            .tweak_add_assign(
+                &libsecp256k1::SecretKey::parse(&derive_epsilon(CHAIN_ID, sender, path)).unwrap(),
+            )
+            .unwrap();
+
+        let mut nonce = libsecp256k1::curve::Scalar::default();
+        let _ = nonce.set_b32(&[0x24; 32]);
+        let (r, s, recovery_id) = libsecp256k1::ECMULT_GEN_CONTEXT
+            .sign_raw(
+                &secret.into(),
+                &libsecp256k1::Message::parse(message_hash).0,
+                &nonce,
+            )
+            .unwrap();
+
+        Signature {
+            big_r: AffinePoint {
+                x: r.b32(),
+                y: [0; 32],
+            },
+            s: s.b32(),
+            recovery_id,
+        }
+    }
+}
+
+/// Creates a system account holding `lamports`.
+pub fn funded_account(svm: &mut Svm, lamports: u64) -> Pubkey {
+    let key = Pubkey::new_unique();
+    svm.airdrop(&key, lamports);
+    key
+}
+
+/// Runtime with `chain_signatures` initialized by a funded admin, charging
+/// [`DEPOSIT`] and verifying responses against the [`Mpc`] root key.
+pub fn setup() -> (Svm, Pubkey, Mpc) {
+    let mut svm = Svm::new();
+    let mpc = Mpc::new();
+    let admin = funded_account(&mut svm, 10 * LAMPORTS_PER_SOL);
+
+    svm.process(
+        &[
+            initialize(admin, DEPOSIT, CHAIN_ID),
+            set_root_public_key(admin, mpc.root_public_key()),
+        ],
+        &[admin],
+    )
+    .expect("setup failed");
+
+    (svm, admin, mpc)
+}
@@ -0,0 +1,302 @@
+//! Property tests feeding adversarial instruction data to `chain_signatures`.
+//!
+//! Every transaction, successful or not, must leave the program without
+//! panicking, fail only with program errors, conserve lamports and keep the
+//! admin of an initialized program unchanged. Failed transactions must leave
+//! no state behind. Transactions too large to submit may also exceed the CPI
+//! size limits, through the events they emit.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use chain_signatures::schema::{AbiParam, AbiType, BorshType};
+use chain_signatures::{
+    ChainSignaturesError, ProgramState, SignBidirectionalEvent, MAX_CHAIN_ID_LEN,
+    MAX_REQUEST_BUFFER_LEN,
+};
+use proptest::prelude::*;
+use svm_tests::signet::{
+    self, funded_account, setup, SignBidirectionalRequest, SignRequest, CHAIN_ID, DEPOSIT,
+    LAMPORTS_PER_SOL,
+};
+use svm_tests::{anchor_error, transaction_size, Svm, SvmError, TransactionMeta, PACKET_DATA_SIZE};
+
+const CAIP2_IDS: [&str; 5] = [
+    "eip155:1",
+    "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp",
+    "bip122:000000000019d6689c085ae165831e93",
+    "cosmos:cosmoshub-4",
+    "",
+];
+
+/// Processes `instructions` and checks the invariants every transaction must
+/// keep against the state before it.
+fn process_checked(
+    svm: &mut Svm,
+    instructions: &[Instruction],
+    signers: &[Pubkey],
+) -> std::result::Result<TransactionMeta, SvmError> {
+    let before = svm.clone();
+    let oversized = transaction_size(instructions) > PACKET_DATA_SIZE;
+    let result = svm.process(instructions, signers);
+
+    assert_eq!(svm.total_lamports(), before.total_lamports());
+    let admin = |svm: &Svm| {
+        svm.account(&signet::program_state())
+            .map(|account| account.deserialize::<ProgramState>().admin)
+    };
+    if let Some(admin_before) = admin(&before) {
+        assert_eq!(admin(svm), Some(admin_before));
+    }
+    if let Err(error) = &result {
+        assert!(
+            matches!(error, SvmError::Program(_))
+                || (oversized && *error == SvmError::CpiLimitExceeded),
+            "runtime rule violated: {error:?}"
+        );
+        for key in [signet::program_state(), signet::root_public_key()] {
+            assert_eq!(svm.account(&key), before.account(&key));
+        }
+    }
+    result
+}
+
+fn any_string(max_len: usize) -> impl Strategy<Value = String> {
+    prop::collection::vec(any::<char>(), 0..max_len).prop_map(String::from_iter)
+}
+
+/// Schema bytes: valid ABI or Borsh schemas, deeply nested JSON or noise.
+fn any_schema() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        Just(AbiParam::schema_bytes(&[AbiParam::new("", AbiType::Bool)])),
+        Just(BorshType::Bool.schema_bytes()),
+        (1..2048usize).prop_map(|depth| format!(
+            "{}\"u8\"{}",
+            "{\"vec\":".repeat(depth),
+            "}".repeat(depth)
+        )
+        .into_bytes()),
+        (1..4096usize).prop_map(|depth| "[".repeat(depth).into_bytes()),
+        prop::collection::vec(any::<u8>(), 0..4096),
+    ]
+}
+
+/// Borsh struct schema with as many `u8` fields as fit a `sign_bidirectional`
+/// transaction into `PACKET_DATA_SIZE - slack` bytes.
+fn fill_respond_schema(requester: Pubkey, request: &mut SignBidirectionalRequest, slack: usize) {
+    let mut fields = vec![];
+    loop {
+        fields.push((format!("field_{}", fields.len()), BorshType::U8));
+        request.respond_serialization_schema = BorshType::Struct(fields.clone()).schema_bytes();
+        if transaction_size(&[signet::sign_bidirectional(requester, None, request)])
+            > PACKET_DATA_SIZE - slack
+        {
+            fields.pop();
+            request.respond_serialization_schema = BorshType::Struct(fields).schema_bytes();
+            return;
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn sign_bidirectional_handles_adversarial_sizes(
+        serialized_transaction in prop::collection::vec(any::<u8>(), 0..12_288),
+        caip2_id in prop::sample::select(&CAIP2_IDS[..]),
+        path in any_string(2048),
+        output_deserialization_schema in any_schema(),
+        respond_serialization_schema in any_schema(),
+    ) {
+        let (mut svm, _, _) = setup();
+        let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+        let request = SignBidirectionalRequest {
+            output_deserialization_schema,
+            respond_serialization_schema,
+            ..SignBidirectionalRequest::new(serialized_transaction, caip2_id, &path)
+        };
+        let state_balance = svm.balance(&signet::program_state());
+
+        let result = process_checked(
+            &mut svm,
+            &[signet::sign_bidirectional(requester, None, &request)],
+            &[requester],
+        );
+
+        match result {
+            Ok(meta) => {
+                prop_assert!(!request.caip2_id.is_empty());
+                prop_assert!(!request.serialized_transaction.is_empty());
+                prop_assert!(request.buffer_payload().len() <= MAX_REQUEST_BUFFER_LEN);
+                prop_assert_eq!(svm.balance(&signet::program_state()), state_balance + DEPOSIT);
+                prop_assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL - DEPOSIT);
+                let events = meta.events::<SignBidirectionalEvent>();
+                prop_assert_eq!(events.len(), 1);
+                prop_assert_eq!(&events[0].serialized_transaction, &request.serialized_transaction);
+                prop_assert_eq!(&events[0].path, &request.path);
+                prop_assert_eq!(
+                    &events[0].respond_serialization_schema,
+                    &request.respond_serialization_schema
+                );
+            }
+            Err(SvmError::CpiLimitExceeded) => {}
+            Err(error) if request.caip2_id.is_empty() => {
+                prop_assert_eq!(error, anchor_error(ChainSignaturesError::InvalidChainId));
+            }
+            Err(error) if request.serialized_transaction.is_empty() => {
+                prop_assert_eq!(error, anchor_error(ChainSignaturesError::InvalidTransaction));
+            }
+            Err(error) if request.buffer_payload().len() > MAX_REQUEST_BUFFER_LEN => {
+                prop_assert_eq!(error, anchor_error(ChainSignaturesError::RequestTooLarge));
+            }
+            Err(error) => {
+                prop_assert_eq!(error, anchor_error(ChainSignaturesError::InvalidSchema));
+            }
+        }
+    }
+
+    #[test]
+    fn sign_bidirectional_accepts_schemas_near_the_size_limit(
+        caip2_id in prop::sample::select(&CAIP2_IDS[..3]),
+        path_len in 0..256usize,
+        slack in 0..64usize,
+    ) {
+        let (mut svm, _, _) = setup();
+        let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+        let mut request =
+            SignBidirectionalRequest::new(vec![0xc0], caip2_id, &"/".repeat(path_len));
+        fill_respond_schema(requester, &mut request, slack);
+        let instruction = signet::sign_bidirectional(requester, None, &request);
+        prop_assert!(transaction_size(std::slice::from_ref(&instruction)) <= PACKET_DATA_SIZE - slack);
+
+        let meta = process_checked(&mut svm, &[instruction], &[requester]).unwrap();
+
+        let events = meta.events::<SignBidirectionalEvent>();
+        prop_assert_eq!(
+            &events[0].respond_serialization_schema,
+            &request.respond_serialization_schema
+        );
+    }
+
+    #[test]
+    fn respond_rejects_mismatched_lengths(
+        request_count in 0..16u8,
+        signature_count in 0..16usize,
+        extra_accounts in -4..4isize,
+    ) {
+        let (mut svm, _, mpc) = setup();
+        let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+        let request_ids: Vec<[u8; 32]> = (0..request_count).map(|i| [i; 32]).collect();
+        let signatures = vec![mpc.sign(&responder, "", &[0; 32]); signature_count];
+        let mut respond = signet::respond(responder, responder, &request_ids, signatures);
+        if extra_accounts < 0 {
+            let len = respond.accounts.len().saturating_sub(extra_accounts.unsigned_abs());
+            respond.accounts.truncate(len.max(6));
+        } else {
+            for _ in 0..extra_accounts {
+                respond.accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+            }
+        }
+        let remaining_accounts = respond.accounts.len() - 6;
+
+        let result = process_checked(&mut svm, &[respond], &[responder]);
+
+        if signature_count == request_ids.len() && remaining_accounts == 3 * request_ids.len() {
+            prop_assert!(result.is_ok());
+            for request_id in &request_ids {
+                prop_assert!(svm.account(&signet::response_record(request_id)).is_none());
+            }
+        } else {
+            prop_assert_eq!(
+                result.unwrap_err(),
+                anchor_error(ChainSignaturesError::InvalidInputLength)
+            );
+        }
+    }
+
+    #[test]
+    fn initialize_accepts_only_caip2_chain_ids(
+        chain_id in prop_oneof![
+            "[-a-z0-9]{3,8}:[-_a-zA-Z0-9]{1,32}",
+            "[a-z]{0,10}:?[a-zA-Z0-9 ]{0,40}",
+            any_string(200),
+        ],
+    ) {
+        let mut svm = Svm::new();
+        let admin = funded_account(&mut svm, LAMPORTS_PER_SOL);
+        let is_caip2 = chain_id.split_once(':').is_some_and(|(namespace, reference)| {
+            (3..=8).contains(&namespace.len())
+                && namespace
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+                && (1..=32).contains(&reference.len())
+                && reference
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        });
+
+        let result = process_checked(
+            &mut svm,
+            &[signet::initialize(admin, DEPOSIT, &chain_id)],
+            &[admin],
+        );
+
+        if is_caip2 {
+            prop_assert!(result.is_ok());
+            let state: ProgramState =
+                svm.account(&signet::program_state()).unwrap().deserialize();
+            prop_assert_eq!(state.chain_id, chain_id);
+        } else if chain_id.len() > MAX_CHAIN_ID_LEN {
+            prop_assert_eq!(
+                result.unwrap_err(),
+                anchor_error(ChainSignaturesError::ChainIdTooLong)
+            );
+            prop_assert!(svm.account(&signet::program_state()).is_none());
+        } else {
+            prop_assert_eq!(
+                result.unwrap_err(),
+                anchor_error(ChainSignaturesError::InvalidChainId)
+            );
+            prop_assert!(svm.account(&signet::program_state()).is_none());
+        }
+    }
+
+    #[test]
+    fn arbitrary_instruction_data_never_breaks_invariants(
+        index in 0..14usize,
+        tail in prop::collection::vec(any::<u8>(), 0..512),
+    ) {
+        let (mut svm, admin, _) = setup();
+        let user = funded_account(&mut svm, LAMPORTS_PER_SOL);
+        let request = SignBidirectionalRequest::new(vec![0xc0], CHAIN_ID, "");
+        svm.process(&[signet::init_request_buffer(user, user, 0, &request)], &[user])
+            .unwrap();
+        let mut instruction = [
+            signet::initialize(admin, DEPOSIT, CHAIN_ID),
+            signet::update_deposit(admin, DEPOSIT),
+            signet::update_chain_id(admin, CHAIN_ID),
+            signet::withdraw_funds(admin, user, 0),
+            signet::set_root_public_key(admin, [0; 64]),
+            signet::sign(user, None, &SignRequest::new([0; 32], "")),
+            signet::sign_bidirectional(user, None, &request),
+            signet::quote_sign_bidirectional(user, None, &request),
+            signet::respond(user, user, &[[0; 32]], vec![]),
+            signet::respond_error(user, vec![]),
+            signet::get_signature_deposit(),
+            signet::init_request_buffer(user, user, 1, &request),
+            signet::write_request_buffer(user, 0, 0, vec![]),
+            signet::sign_bidirectional_from_buffer(user, None, user, 0, &request),
+        ][index]
+            .clone();
+        instruction.data.truncate(8);
+        instruction.data.extend(tail);
+        let signers: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect();
+
+        let _ = process_checked(&mut svm, &[instruction], &signers);
+    }
+}
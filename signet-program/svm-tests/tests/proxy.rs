@@ -0,0 +1,536 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+use chain_signatures::address::derived_public_key;
+use chain_signatures::evm::{self, Eip1559Transaction};
+use chain_signatures::response::{bidirectional_response_hash, RESPONSE_KEY_PATH};
+use chain_signatures::{
+    ChainSignaturesError, ExecutionStatus, SignBidirectionalEvent, SignatureRequest,
+    SignatureRequestedEvent,
+};
+use proxy_test_cpi::{
+    accounts, instruction, Erc20Balance, EvmTxParams, ProxyError, DEPOSIT_KEY_VERSION,
+    ERC20_BALANCE_SEED, PENDING_DEPOSIT_SEED, TREASURY_PATH, VAULT_SEED,
+};
+use svm_tests::anchor_error;
+use svm_tests::signet::{
+    self, funded_account, setup, Mpc, SignRequest, CHAIN_ID, DEPOSIT, LAMPORTS_PER_SOL,
+};
+
+const ERC20_ADDRESS: [u8; 20] = [0x1c; 20];
+
+fn proxy_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &proxy_test_cpi::ID).0
+}
+
+fn vault() -> Pubkey {
+    proxy_pda(&[VAULT_SEED])
+}
+
+fn proxy_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: proxy_test_cpi::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn call_sign(requester: Pubkey, fee_payer: Option<Pubkey>, request: &SignRequest) -> Instruction {
+    let data = request.data();
+    proxy_instruction(
+        accounts::CallSign {
+            signet_program: chain_signatures::ID,
+            signet_program_state: signet::program_state(),
+            key_version_registry: signet::key_version_registry(),
+            requester,
+            fee_payer,
+            signature_request: signet::signature_request(&request.request_id(&requester)),
+            response_record: signet::response_record(&request.request_id(&requester)),
+            allowlist_entry: None,
+            rate_limit_config: signet::rate_limit_config(),
+            rate_limit: None,
+            system_program: anchor_lang::system_program::ID,
+            instructions: solana_instructions_sysvar::ID,
+            event_authority: signet::event_authority(),
+        },
+        instruction::CallSign {
+            payload: data.payload,
+            key_version: data.key_version,
+            path: data.path,
+            algo: data.algo,
+            dest: data.dest,
+            params: data.params,
+        },
+    )
+}
+
+fn call_sign_as_vault(fee_payer: Pubkey, request: &SignRequest) -> Instruction {
+    let data = request.data();
+    proxy_instruction(
+        accounts::CallSignAsVault {
+            signet_program: chain_signatures::ID,
+            signet_program_state: signet::program_state(),
+            key_version_registry: signet::key_version_registry(),
+            vault: vault(),
+            fee_payer,
+            signature_request: signet::signature_request(&request.request_id(&vault())),
+            response_record: signet::response_record(&request.request_id(&vault())),
+            allowlist_entry: None,
+            rate_limit_config: signet::rate_limit_config(),
+            rate_limit: None,
+            system_program: anchor_lang::system_program::ID,
+            instructions: solana_instructions_sysvar::ID,
+            event_authority: signet::event_authority(),
+        },
+        instruction::CallSignAsVault {
+            payload: data.payload,
+            key_version: data.key_version,
+            path: data.path,
+            algo: data.algo,
+            dest: data.dest,
+            params: data.params,
+        },
+    )
+}
+
+fn tx_params(nonce: u64) -> EvmTxParams {
+    EvmTxParams {
+        chain_id: 11155111,
+        nonce,
+        gas_limit: 100_000,
+        max_fee_per_gas: 30_000_000_000,
+        max_priority_fee_per_gas: 1_000_000_000,
+    }
+}
+
+/// Request id of the transaction `deposit_erc20` builds.
+fn deposit_request_id(
+    mpc: &Mpc,
+    requester: &Pubkey,
+    amount: u128,
+    params: &EvmTxParams,
+) -> [u8; 32] {
+    let treasury = evm::public_key_to_address(
+        &derived_public_key(&mpc.root_public_key(), CHAIN_ID, &vault(), TREASURY_PATH).unwrap(),
+    );
+    let mut data = vec![0xa9, 0x05, 0x9c, 0xbb];
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(&treasury);
+    data.extend_from_slice(&[0; 16]);
+    data.extend_from_slice(&amount.to_be_bytes());
+
+    let transaction = Eip1559Transaction {
+        chain_id: params.chain_id,
+        nonce: params.nonce,
+        max_priority_fee_per_gas: params.max_priority_fee_per_gas,
+        max_fee_per_gas: params.max_fee_per_gas,
+        gas_limit: params.gas_limit,
+        to: Some(ERC20_ADDRESS),
+        value: 0,
+        data,
+        access_list: vec![],
+    };
+    evm::generate_request_id(
+        &vault(),
+        &transaction.encode_for_signing(),
+        &evm::caip2_id(params.chain_id),
+        0,
+        &requester.to_string(),
+        "",
+        "",
+        "",
+    )
+}
+
+fn pending_deposit(request_id: &[u8; 32]) -> Pubkey {
+    proxy_pda(&[PENDING_DEPOSIT_SEED, request_id])
+}
+
+fn erc20_balance(requester: &Pubkey) -> Pubkey {
+    proxy_pda(&[ERC20_BALANCE_SEED, requester.as_ref(), &ERC20_ADDRESS])
+}
+
+fn deposit_erc20(
+    requester: Pubkey,
+    request_id: [u8; 32],
+    amount: u128,
+    params: EvmTxParams,
+) -> Instruction {
+    proxy_instruction(
+        accounts::DepositErc20 {
+            signet_program: chain_signatures::ID,
+            signet_program_state: signet::program_state(),
+            root_public_key: signet::root_public_key(),
+            key_version_registry: signet::key_version_registry(),
+            vault: vault(),
+            requester,
+            pending_deposit: pending_deposit(&request_id),
+            allowlist_entry: None,
+            rate_limit_config: signet::rate_limit_config(),
+            rate_limit: None,
+            system_program: anchor_lang::system_program::ID,
+            instructions: solana_instructions_sysvar::ID,
+            event_authority: signet::event_authority(),
+        },
+        instruction::DepositErc20 {
+            request_id,
+            erc20_address: ERC20_ADDRESS,
+            amount,
+            tx_params: params,
+        },
+    )
+}
+
+fn claim_erc20(
+    mpc: &Mpc,
+    requester: Pubkey,
+    request_id: [u8; 32],
+    status: ExecutionStatus,
+    serialized_output: Vec<u8>,
+) -> Instruction {
+    let signature = mpc.sign(
+        &vault(),
+        RESPONSE_KEY_PATH,
+        &bidirectional_response_hash(&request_id, status, &serialized_output, None),
+    );
+    proxy_instruction(
+        accounts::ClaimErc20 {
+            signet_program: chain_signatures::ID,
+            signet_program_state: signet::program_state(),
+            root_public_key: signet::root_public_key(),
+            key_version_registry: signet::key_version_registry(),
+            vault: vault(),
+            pending_deposit: pending_deposit(&request_id),
+            balance: erc20_balance(&requester),
+            requester,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::ClaimErc20 {
+            request_id,
+            status,
+            serialized_output,
+            revert_data: None,
+            signature,
+        },
+    )
+}
+
+fn rent(space: usize) -> u64 {
+    Rent::default().minimum_balance(space)
+}
+
+#[test]
+fn cpi_requests_record_the_top_level_program() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([1; 32], "cpi");
+
+    let meta = svm
+        .process(&[call_sign(requester, None, &request)], &[requester])
+        .unwrap();
+
+    assert_eq!(
+        svm.balance(&requester),
+        LAMPORTS_PER_SOL - DEPOSIT - rent(SignatureRequest::SPACE)
+    );
+    let events = meta.events::<SignatureRequestedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].sender, requester);
+    assert_eq!(events[0].top_level_program, Some(proxy_test_cpi::ID));
+    assert_eq!(meta.events[0].program_id, chain_signatures::ID);
+}
+
+#[test]
+fn cpi_requests_charge_the_fee_payer() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let fee_payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([2; 32], "");
+
+    let meta = svm
+        .process(
+            &[call_sign(requester, Some(fee_payer), &request)],
+            &[requester, fee_payer],
+        )
+        .unwrap();
+
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL);
+    assert_eq!(
+        svm.balance(&fee_payer),
+        LAMPORTS_PER_SOL - DEPOSIT - rent(SignatureRequest::SPACE)
+    );
+    assert_eq!(
+        meta.events::<SignatureRequestedEvent>()[0].fee_payer,
+        Some(fee_payer)
+    );
+}
+
+#[test]
+fn cpi_requests_fail_with_the_signet_error() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, DEPOSIT - 1);
+    let request = SignRequest::new([3; 32], "");
+
+    let result = svm.process(&[call_sign(requester, None, &request)], &[requester]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InsufficientDeposit)
+    );
+    assert_eq!(svm.balance(&requester), DEPOSIT - 1);
+}
+
+#[test]
+fn program_pdas_request_through_invoke_signed() {
+    let (mut svm, _, _) = setup();
+    let fee_payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([4; 32], "vault");
+
+    let meta = svm
+        .process(&[call_sign_as_vault(fee_payer, &request)], &[fee_payer])
+        .unwrap();
+
+    let event = &meta.events::<SignatureRequestedEvent>()[0];
+    assert_eq!(event.sender, vault());
+    assert_eq!(event.fee_payer, Some(fee_payer));
+    assert!(svm
+        .account(&signet::signature_request(&request.request_id(&vault())))
+        .is_some());
+}
+
+#[test]
+fn erc20_deposits_are_credited_after_a_successful_response() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let amount = 1_000_000;
+    let request_id = deposit_request_id(&mpc, &requester, amount, &tx_params(0));
+
+    let meta = svm
+        .process(
+            &[deposit_erc20(requester, request_id, amount, tx_params(0))],
+            &[requester],
+        )
+        .unwrap();
+    let event = &meta.events::<SignBidirectionalEvent>()[0];
+    assert_eq!(event.sender, vault());
+    assert_eq!(event.top_level_program, Some(proxy_test_cpi::ID));
+    assert_eq!(event.path, requester.to_string());
+    assert_eq!(event.chain_id, CHAIN_ID);
+    assert_eq!(event.request_id(), request_id);
+
+    svm.process(
+        &[claim_erc20(
+            &mpc,
+            requester,
+            request_id,
+            ExecutionStatus::Success,
+            vec![1],
+        )],
+        &[requester],
+    )
+    .unwrap();
+
+    let balance: Erc20Balance = svm
+        .account(&erc20_balance(&requester))
+        .unwrap()
+        .deserialize();
+    assert_eq!(balance.amount, amount);
+    assert!(svm.account(&pending_deposit(&request_id)).is_none());
+}
+
+#[test]
+fn erc20_deposits_close_without_credit_when_reverted() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request_id = deposit_request_id(&mpc, &requester, 500, &tx_params(1));
+    svm.process(
+        &[deposit_erc20(requester, request_id, 500, tx_params(1))],
+        &[requester],
+    )
+    .unwrap();
+
+    svm.process(
+        &[claim_erc20(
+            &mpc,
+            requester,
+            request_id,
+            ExecutionStatus::Reverted,
+            vec![],
+        )],
+        &[requester],
+    )
+    .unwrap();
+
+    let balance: Erc20Balance = svm
+        .account(&erc20_balance(&requester))
+        .unwrap()
+        .deserialize();
+    assert_eq!(balance.amount, 0);
+    assert!(svm.account(&pending_deposit(&request_id)).is_none());
+}
+
+#[test]
+fn erc20_deposits_require_the_matching_request_id() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request_id = deposit_request_id(&mpc, &requester, 7, &tx_params(2));
+
+    let result = svm.process(
+        &[deposit_erc20(requester, request_id, 8, tx_params(2))],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ProxyError::InvalidRequestId)
+    );
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL);
+}
+
+#[test]
+fn erc20_claims_require_the_vault_response_key() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request_id = deposit_request_id(&mpc, &requester, 42, &tx_params(3));
+    svm.process(
+        &[deposit_erc20(requester, request_id, 42, tx_params(3))],
+        &[requester],
+    )
+    .unwrap();
+
+    let mut claim = claim_erc20(
+        &mpc,
+        requester,
+        request_id,
+        ExecutionStatus::Success,
+        vec![1],
+    );
+    let forged = mpc.sign(
+        &requester,
+        RESPONSE_KEY_PATH,
+        &bidirectional_response_hash(&request_id, ExecutionStatus::Success, &[1], None),
+    );
+    claim.data = instruction::ClaimErc20 {
+        request_id,
+        status: ExecutionStatus::Success,
+        serialized_output: vec![1],
+        revert_data: None,
+        signature: forged,
+    }
+    .data();
+
+    let result = svm.process(&[claim], &[requester]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidSignature)
+    );
+    assert!(svm.account(&pending_deposit(&request_id)).is_some());
+}
+
+#[test]
+fn erc20_claims_follow_the_registered_key_version() {
+    let (mut svm, admin, mpc) = setup();
+    let rotated = Mpc::from_secret([0x43; 32]);
+    svm.process(
+        &[signet::set_key_version(
+            admin,
+            DEPOSIT_KEY_VERSION,
+            rotated.root_public_key(),
+            true,
+            None,
+        )],
+        &[admin],
+    )
+    .unwrap();
+
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request_id = deposit_request_id(&rotated, &requester, 42, &tx_params(4));
+    svm.process(
+        &[deposit_erc20(requester, request_id, 42, tx_params(4))],
+        &[requester],
+    )
+    .unwrap();
+
+    // A response signed under the replaced root key no longer verifies.
+    let result = svm.process(
+        &[claim_erc20(
+            &mpc,
+            requester,
+            request_id,
+            ExecutionStatus::Success,
+            vec![1],
+        )],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidSignature)
+    );
+
+    svm.process(
+        &[claim_erc20(
+            &rotated,
+            requester,
+            request_id,
+            ExecutionStatus::Success,
+            vec![1],
+        )],
+        &[requester],
+    )
+    .unwrap();
+
+    let balance: Erc20Balance = svm
+        .account(&erc20_balance(&requester))
+        .unwrap()
+        .deserialize();
+    assert_eq!(balance.amount, 42);
+}
+
+#[test]
+fn erc20_claims_reject_undecodable_output() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request_id = deposit_request_id(&mpc, &requester, 9, &tx_params(4));
+    svm.process(
+        &[deposit_erc20(requester, request_id, 9, tx_params(4))],
+        &[requester],
+    )
+    .unwrap();
+
+    // The fakenet signer's Substrate error output, signed as a success.
+    let result = svm.process(
+        &[claim_erc20(
+            &mpc,
+            requester,
+            request_id,
+            ExecutionStatus::Success,
+            vec![0xde, 0xad, 0xbe, 0xef, 1],
+        )],
+        &[requester],
+    );
+    assert_eq!(result.unwrap_err(), anchor_error(ProxyError::InvalidOutput));
+    assert!(svm.account(&pending_deposit(&request_id)).is_some());
+}
+
+#[test]
+fn failed_transactions_leave_no_state() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([5; 32], "");
+    let balance = svm.balance(&requester);
+
+    // The requester is not the admin, so the whole transaction fails.
+    let result = svm.process(
+        &[
+            call_sign(requester, None, &request),
+            signet::withdraw_funds(requester, requester, 1),
+        ],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::Unauthorized)
+    );
+    assert_eq!(svm.balance(&requester), balance);
+    assert!(svm
+        .account(&signet::signature_request(&request.request_id(&requester)))
+        .is_none());
+}
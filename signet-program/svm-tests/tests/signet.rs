@@ -0,0 +1,1101 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_error::ProgramError;
+use chain_signatures::{
+    ChainSignaturesError, ErrorResponse, ExecutionStatus, ProgramState, RateLimitConfig,
+    RequestBuffer, RespondBidirectionalEvent, ResponseRecord, SignBidirectionalEvent,
+    SignBidirectionalQuote, SignatureErrorCode, SignatureErrorEvent, SignatureRequest,
+    SignatureRequestedEvent, SignatureRespondedEvent, MAX_CHAIN_ID_LEN, MAX_REQUEST_BUFFER_LEN,
+};
+use solana_system_interface::error::SystemError;
+use svm_tests::signet::{
+    self, funded_account, setup, SignBidirectionalRequest, SignRequest, CHAIN_ID, DEPOSIT,
+};
+use svm_tests::{anchor_error, transaction_size, Svm, SvmError, PACKET_DATA_SIZE};
+
+const LAMPORTS_PER_SOL: u64 = signet::LAMPORTS_PER_SOL;
+
+fn rent(space: usize) -> u64 {
+    Rent::default().minimum_balance(space)
+}
+
+#[test]
+fn initialize_stores_configuration_once() {
+    let mut svm = Svm::new();
+    let admin = funded_account(&mut svm, LAMPORTS_PER_SOL);
+
+    svm.process(&[signet::initialize(admin, DEPOSIT, CHAIN_ID)], &[admin])
+        .unwrap();
+
+    let state: ProgramState = svm.account(&signet::program_state()).unwrap().deserialize();
+    assert_eq!(state.admin, admin);
+    assert_eq!(state.signature_deposit, DEPOSIT);
+    assert_eq!(state.chain_id, CHAIN_ID);
+    assert!(!state.permissioned);
+
+    let attacker = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let result = svm.process(&[signet::initialize(attacker, 0, CHAIN_ID)], &[attacker]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(SystemError::AccountAlreadyInUse as u32)
+    );
+}
+
+#[test]
+fn initialize_rejects_invalid_chain_ids() {
+    let mut svm = Svm::new();
+    let admin = funded_account(&mut svm, LAMPORTS_PER_SOL);
+
+    for chain_id in [
+        "",
+        "solana",
+        "so:mainnet",
+        "Solana:mainnet",
+        "solana:",
+        "solana:main net",
+        "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpX",
+    ] {
+        let result = svm.process(&[signet::initialize(admin, DEPOSIT, chain_id)], &[admin]);
+        assert_eq!(
+            result.unwrap_err(),
+            anchor_error(ChainSignaturesError::InvalidChainId),
+            "{chain_id}"
+        );
+    }
+    let too_long = format!("solana:{}", "a".repeat(MAX_CHAIN_ID_LEN));
+    let result = svm.process(&[signet::initialize(admin, DEPOSIT, &too_long)], &[admin]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::ChainIdTooLong)
+    );
+    assert!(svm.account(&signet::program_state()).is_none());
+}
+
+#[test]
+fn update_chain_id_replaces_the_chain_id() {
+    let (mut svm, admin, _) = setup();
+    let chain_id = "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1";
+
+    svm.process(&[signet::update_chain_id(admin, chain_id)], &[admin])
+        .unwrap();
+
+    let state: ProgramState = svm.account(&signet::program_state()).unwrap().deserialize();
+    assert_eq!(state.chain_id, chain_id);
+    assert_eq!(state.admin, admin);
+
+    let result = svm.process(&[signet::update_chain_id(admin, "solana")], &[admin]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidChainId)
+    );
+
+    let attacker = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let result = svm.process(&[signet::update_chain_id(attacker, CHAIN_ID)], &[attacker]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::Unauthorized)
+    );
+}
+
+#[test]
+fn get_signature_deposit_returns_the_deposit() {
+    let (svm, admin, _) = setup();
+
+    let meta = svm
+        .simulate(&[signet::get_signature_deposit()], &[admin])
+        .unwrap();
+    assert_eq!(meta.return_value::<u64>(), Some(DEPOSIT));
+}
+
+#[test]
+fn sign_charges_the_deposit_to_the_requester() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([1; 32], "path");
+    let state_before = svm.balance(&signet::program_state());
+
+    let meta = svm
+        .process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+
+    assert_eq!(
+        svm.balance(&requester),
+        LAMPORTS_PER_SOL - DEPOSIT - rent(SignatureRequest::SPACE)
+    );
+    assert_eq!(
+        svm.balance(&signet::program_state()),
+        state_before + DEPOSIT
+    );
+
+    let events = meta.events::<SignatureRequestedEvent>();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.sender, requester);
+    assert_eq!(event.payload, request.payload);
+    assert_eq!(event.deposit, DEPOSIT);
+    assert_eq!(event.chain_id, CHAIN_ID);
+    assert_eq!(event.path, "path");
+    assert_eq!(event.fee_payer, None);
+    assert_eq!(event.top_level_program, None);
+    assert_eq!(meta.events[0].program_id, chain_signatures::ID);
+
+    let tracked: SignatureRequest = svm
+        .account(&signet::signature_request(&request.request_id(&requester)))
+        .unwrap()
+        .deserialize();
+    assert_eq!(tracked.sender, requester);
+    assert_eq!(tracked.rent_payer, requester);
+    assert_eq!(tracked.payload, request.payload);
+}
+
+#[test]
+fn sign_accepts_requests_without_the_instructions_sysvar() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([1; 32], "no-sysvar");
+    let mut ix = signet::sign(requester, None, &request);
+    // Anchor passes the program id for an absent optional account.
+    for meta in &mut ix.accounts {
+        if meta.pubkey == solana_instructions_sysvar::ID {
+            *meta = AccountMeta::new_readonly(chain_signatures::ID, false);
+        }
+    }
+
+    let meta = svm.process(&[ix], &[requester]).unwrap();
+
+    let events = meta.events::<SignatureRequestedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].top_level_program, None);
+}
+
+#[test]
+fn sign_charges_the_fee_payer_instead_of_the_requester() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let fee_payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([2; 32], "");
+
+    let meta = svm
+        .process(
+            &[signet::sign(requester, Some(fee_payer), &request)],
+            &[requester, fee_payer],
+        )
+        .unwrap();
+
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL);
+    assert_eq!(
+        svm.balance(&fee_payer),
+        LAMPORTS_PER_SOL - DEPOSIT - rent(SignatureRequest::SPACE)
+    );
+    let event = &meta.events::<SignatureRequestedEvent>()[0];
+    assert_eq!(event.sender, requester);
+    assert_eq!(event.fee_payer, Some(fee_payer));
+}
+
+#[test]
+fn sign_requires_the_deposit_and_signatures() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, DEPOSIT + rent(SignatureRequest::SPACE) - 1);
+    let request = SignRequest::new([3; 32], "");
+
+    let result = svm.process(&[signet::sign(requester, None, &request)], &[requester]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InsufficientDeposit)
+    );
+
+    let fee_payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let result = svm.process(
+        &[signet::sign(requester, Some(fee_payer), &request)],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        SvmError::Program(ProgramError::MissingRequiredSignature)
+    );
+    assert_eq!(svm.balance(&fee_payer), LAMPORTS_PER_SOL);
+}
+
+#[test]
+fn sign_retries_pending_requests() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let fee_payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([4; 32], "");
+    let request_id = request.request_id(&requester);
+
+    svm.process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+    let meta = svm
+        .process(
+            &[signet::sign(requester, Some(fee_payer), &request)],
+            &[requester, fee_payer],
+        )
+        .unwrap();
+
+    assert_eq!(meta.events::<SignatureRequestedEvent>().len(), 1);
+    assert_eq!(svm.balance(&fee_payer), LAMPORTS_PER_SOL - DEPOSIT);
+    let tracked: SignatureRequest = svm
+        .account(&signet::signature_request(&request_id))
+        .unwrap()
+        .deserialize();
+    assert_eq!(tracked.rent_payer, requester);
+}
+
+#[test]
+fn sign_without_a_signature_request_is_not_tracked() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([5; 32], "");
+    let request_pda = signet::signature_request(&request.request_id(&requester));
+    let mut sign = signet::sign(requester, None, &request);
+    for account in &mut sign.accounts {
+        if account.pubkey == request_pda {
+            *account = AccountMeta::new_readonly(chain_signatures::ID, false);
+        }
+    }
+
+    svm.process(&[sign], &[requester]).unwrap();
+
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL - DEPOSIT);
+    assert!(svm.account(&request_pda).is_none());
+}
+
+#[test]
+fn sign_is_rate_limited_per_requester_and_globally() {
+    let (mut svm, admin, _) = setup();
+    svm.process(&[signet::set_rate_limit(admin, 2, 100, 3)], &[admin])
+        .unwrap();
+    let requesters: Vec<Pubkey> = (0..3)
+        .map(|_| funded_account(&mut svm, LAMPORTS_PER_SOL))
+        .collect();
+    let sign = |requester: Pubkey, payload: u8| {
+        signet::with_rate_limit(
+            signet::sign(requester, None, &SignRequest::new([payload; 32], "path")),
+            &requester,
+        )
+    };
+
+    svm.process(&[sign(requesters[0], 1)], &[requesters[0]])
+        .unwrap();
+    svm.process(&[sign(requesters[0], 2)], &[requesters[0]])
+        .unwrap();
+    let result = svm.process(&[sign(requesters[0], 3)], &[requesters[0]]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::RateLimited)
+    );
+
+    // The third request overall exhausts the global limit, so a requester
+    // with no requests of its own is limited too.
+    svm.process(&[sign(requesters[1], 4)], &[requesters[1]])
+        .unwrap();
+    let result = svm.process(&[sign(requesters[2], 5)], &[requesters[2]]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::RateLimited)
+    );
+    let config: RateLimitConfig = svm
+        .account(&signet::rate_limit_config())
+        .unwrap()
+        .deserialize();
+    assert_eq!(config.global_count, 3);
+
+    svm.warp_to_slot(svm.slot() + 100);
+    svm.process(&[sign(requesters[2], 5)], &[requesters[2]])
+        .unwrap();
+    svm.process(&[sign(requesters[0], 3)], &[requesters[0]])
+        .unwrap();
+}
+
+#[test]
+fn set_rate_limit_requires_a_window_for_any_limit() {
+    let (mut svm, admin, _) = setup();
+
+    for (max_requests, global_max_requests) in [(1, 0), (0, 1)] {
+        let result = svm.process(
+            &[signet::set_rate_limit(
+                admin,
+                max_requests,
+                0,
+                global_max_requests,
+            )],
+            &[admin],
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            anchor_error(ChainSignaturesError::InvalidRateLimit)
+        );
+    }
+    svm.process(&[signet::set_rate_limit(admin, 0, 0, 0)], &[admin])
+        .unwrap();
+}
+
+#[test]
+fn withdraw_funds_is_bounded_by_the_rent_exempt_balance() {
+    let (mut svm, admin, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    svm.process(
+        &[signet::sign(
+            requester,
+            None,
+            &SignRequest::new([5; 32], ""),
+        )],
+        &[requester],
+    )
+    .unwrap();
+
+    let recipient = Pubkey::new_unique();
+    let state = signet::program_state();
+    let balance = svm.balance(&state);
+    let data_len = svm.account(&state).unwrap().data.len();
+    let available = balance - rent(data_len);
+    assert_eq!(available, DEPOSIT);
+
+    let result = svm.process(
+        &[signet::withdraw_funds(admin, recipient, balance + 1)],
+        &[admin],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InsufficientFunds)
+    );
+
+    let result = svm.process(
+        &[signet::withdraw_funds(admin, recipient, available + 1)],
+        &[admin],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        SvmError::InsufficientFundsForRent(state)
+    );
+
+    svm.process(
+        &[signet::withdraw_funds(admin, recipient, available)],
+        &[admin],
+    )
+    .unwrap();
+    assert_eq!(svm.balance(&recipient), available);
+    assert_eq!(svm.balance(&state), rent(data_len));
+}
+
+#[test]
+fn withdraw_funds_requires_the_admin_and_a_recipient() {
+    let (mut svm, admin, _) = setup();
+    let other = funded_account(&mut svm, LAMPORTS_PER_SOL);
+
+    let result = svm.process(&[signet::withdraw_funds(other, other, 1)], &[other]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::Unauthorized)
+    );
+
+    let result = svm.process(
+        &[signet::withdraw_funds(admin, Pubkey::default(), 1)],
+        &[admin],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidRecipient)
+    );
+}
+
+#[test]
+fn respond_verifies_and_records_signatures() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([6; 32], "eth");
+    let request_id = request.request_id(&requester);
+    svm.process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+
+    let signature = mpc.sign(&requester, "eth", &request.payload);
+    let meta = svm
+        .process(
+            &[signet::respond(
+                responder,
+                requester,
+                &[request_id],
+                vec![signature.clone()],
+            )],
+            &[responder],
+        )
+        .unwrap();
+
+    let events = meta.events::<SignatureRespondedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].request_id, request_id);
+    assert_eq!(events[0].responder, responder);
+    let record = svm.account(&signet::response_record(&request_id)).unwrap();
+    assert_eq!(record.owner, chain_signatures::ID);
+    assert_eq!(record.deserialize::<ResponseRecord>().responder, responder);
+    // The record is funded from the rent of the closed request.
+    assert_eq!(svm.balance(&responder), LAMPORTS_PER_SOL);
+    assert!(svm
+        .account(&signet::signature_request(&request_id))
+        .is_none());
+    assert_eq!(
+        svm.balance(&requester),
+        LAMPORTS_PER_SOL - DEPOSIT - rent(ResponseRecord::SPACE)
+    );
+
+    let result = svm.process(
+        &[signet::respond(
+            responder,
+            requester,
+            &[request_id],
+            vec![signature],
+        )],
+        &[responder],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::AlreadyResponded)
+    );
+}
+
+#[test]
+fn sign_rejects_requests_that_were_answered() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([6; 32], "eth");
+    let request_id = request.request_id(&requester);
+    svm.process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+    let signature = mpc.sign(&requester, "eth", &request.payload);
+    svm.process(
+        &[signet::respond(
+            responder,
+            requester,
+            &[request_id],
+            vec![signature],
+        )],
+        &[responder],
+    )
+    .unwrap();
+
+    let result = svm.process(&[signet::sign(requester, None, &request)], &[requester]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::AlreadyResponded)
+    );
+    assert_eq!(
+        svm.balance(&requester),
+        LAMPORTS_PER_SOL - DEPOSIT - rent(ResponseRecord::SPACE)
+    );
+    assert!(svm
+        .account(&signet::signature_request(&request_id))
+        .is_none());
+
+    // Tracking a request without its record would skip the check.
+    let mut sign = signet::sign(requester, None, &request);
+    let record = signet::response_record(&request_id);
+    for account in &mut sign.accounts {
+        if account.pubkey == record {
+            *account = AccountMeta::new_readonly(chain_signatures::ID, false);
+        }
+    }
+    let result = svm.process(&[sign], &[requester]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidResponseRecord)
+    );
+}
+
+#[test]
+fn respond_rejects_signatures_from_other_keys() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([7; 32], "eth");
+    let request_id = request.request_id(&requester);
+    svm.process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+
+    let signature = mpc.sign(&requester, "btc", &request.payload);
+    let result = svm.process(
+        &[signet::respond(
+            responder,
+            requester,
+            &[request_id],
+            vec![signature],
+        )],
+        &[responder],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidSignature)
+    );
+    assert!(svm.account(&signet::response_record(&request_id)).is_none());
+}
+
+#[test]
+fn respond_refunds_the_rent_only_to_the_rent_payer() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([8; 32], "eth");
+    let request_id = request.request_id(&requester);
+    svm.process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+
+    let signature = mpc.sign(&requester, "eth", &request.payload);
+    let result = svm.process(
+        &[signet::respond(
+            responder,
+            responder,
+            &[request_id],
+            vec![signature],
+        )],
+        &[responder],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidRentPayer)
+    );
+    assert!(svm
+        .account(&signet::signature_request(&request_id))
+        .is_some());
+}
+
+#[test]
+fn respond_does_not_record_unverified_responses() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let attacker = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([9; 32], "eth");
+    let request_id = request.request_id(&requester);
+    let junk = mpc.sign(&attacker, "eth", &request.payload);
+
+    svm.process(
+        &[signet::respond(
+            attacker,
+            requester,
+            &[request_id],
+            vec![junk.clone()],
+        )],
+        &[attacker],
+    )
+    .unwrap();
+    assert!(svm.account(&signet::response_record(&request_id)).is_none());
+
+    svm.process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+    let result = svm.process(
+        &[signet::respond(
+            attacker,
+            requester,
+            &[request_id],
+            vec![junk],
+        )],
+        &[attacker],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidSignature)
+    );
+
+    let signature = mpc.sign(&requester, "eth", &request.payload);
+    svm.process(
+        &[signet::respond(
+            responder,
+            requester,
+            &[request_id],
+            vec![signature],
+        )],
+        &[responder],
+    )
+    .unwrap();
+    let record = svm.account(&signet::response_record(&request_id)).unwrap();
+    assert_eq!(record.deserialize::<ResponseRecord>().responder, responder);
+}
+
+#[test]
+fn respond_rejects_mismatched_inputs() {
+    let (mut svm, _, mpc) = setup();
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let signature = mpc.sign(&responder, "", &[0; 32]);
+
+    let result = svm.process(
+        &[signet::respond(
+            responder,
+            responder,
+            &[[1; 32], [2; 32]],
+            vec![signature.clone()],
+        )],
+        &[responder],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidInputLength)
+    );
+
+    let mut respond = signet::respond(responder, responder, &[[1; 32]], vec![signature]);
+    respond.accounts.pop();
+    let result = svm.process(&[respond], &[responder]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidInputLength)
+    );
+}
+
+#[test]
+fn respond_error_reports_unanswered_requests() {
+    let (mut svm, _, mpc) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignRequest::new([8; 32], "");
+    let request_id = request.request_id(&requester);
+    svm.process(&[signet::sign(requester, None, &request)], &[requester])
+        .unwrap();
+
+    let error = ErrorResponse {
+        request_id,
+        code: SignatureErrorCode::UnsupportedChain,
+        detail: Some("unsupported".to_string()),
+    };
+    let meta = svm
+        .process(
+            &[signet::respond_error(responder, vec![error.clone()])],
+            &[responder],
+        )
+        .unwrap();
+    let events = meta.events::<SignatureErrorEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].request_id, request_id);
+    assert_eq!(events[0].code, SignatureErrorCode::UnsupportedChain);
+    assert_eq!(events[0].detail.as_deref(), Some("unsupported"));
+
+    let signature = mpc.sign(&requester, "", &request.payload);
+    svm.process(
+        &[signet::respond(
+            responder,
+            requester,
+            &[request_id],
+            vec![signature],
+        )],
+        &[responder],
+    )
+    .unwrap();
+    let result = svm.process(
+        &[signet::respond_error(responder, vec![error])],
+        &[responder],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::AlreadyResponded)
+    );
+}
+
+#[test]
+fn respond_bidirectional_emits_the_execution_result() {
+    let (mut svm, _, mpc) = setup();
+    let responder = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request_id = [9; 32];
+    let signature = mpc.sign(&responder, "", &request_id);
+
+    let meta = svm
+        .process(
+            &[signet::respond_bidirectional(
+                responder,
+                request_id,
+                ExecutionStatus::Reverted,
+                vec![],
+                Some(vec![0xde, 0xad]),
+                signature.clone(),
+            )],
+            &[responder],
+        )
+        .unwrap();
+    let events = meta.events::<RespondBidirectionalEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].request_id, request_id);
+    assert_eq!(events[0].status, ExecutionStatus::Reverted);
+    assert_eq!(events[0].revert_data, Some(vec![0xde, 0xad]));
+
+    let result = svm.process(
+        &[signet::respond_bidirectional(
+            responder,
+            request_id,
+            ExecutionStatus::Success,
+            vec![1],
+            Some(vec![0xde, 0xad]),
+            signature,
+        )],
+        &[responder],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::UnexpectedRevertData)
+    );
+
+    let result = svm.process(
+        &[signet::respond_bidirectional(
+            responder,
+            request_id,
+            ExecutionStatus::Dropped,
+            vec![1],
+            None,
+            mpc.sign(&responder, "", &request_id),
+        )],
+        &[responder],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::UnexpectedOutput)
+    );
+}
+
+#[test]
+fn quote_sign_bidirectional_matches_the_request_without_charging() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignBidirectionalRequest::new(vec![0xc0], "eip155:1", "vault");
+    let state_balance = svm.balance(&signet::program_state());
+
+    let meta = svm
+        .process(
+            &[signet::quote_sign_bidirectional(requester, None, &request)],
+            &[],
+        )
+        .unwrap();
+
+    let quote = meta.return_value::<SignBidirectionalQuote>().unwrap();
+    assert_eq!(quote.deposit, DEPOSIT);
+    assert!(quote.failures.is_empty());
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL);
+    assert_eq!(svm.balance(&signet::program_state()), state_balance);
+
+    let meta = svm
+        .process(
+            &[signet::sign_bidirectional(requester, None, &request)],
+            &[requester],
+        )
+        .unwrap();
+    let event = &meta.events::<SignBidirectionalEvent>()[0];
+    assert_eq!(quote.request_id, event.request_id());
+}
+
+#[test]
+fn quote_sign_bidirectional_reports_every_failure() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, DEPOSIT - 1);
+    let request = SignBidirectionalRequest {
+        respond_serialization_schema: b"{".to_vec(),
+        ..SignBidirectionalRequest::new(vec![], "eip155:1", "")
+    };
+
+    let quote = svm
+        .simulate(
+            &[signet::quote_sign_bidirectional(requester, None, &request)],
+            &[],
+        )
+        .unwrap()
+        .return_value::<SignBidirectionalQuote>()
+        .unwrap();
+
+    assert_eq!(
+        quote.failures,
+        [
+            ChainSignaturesError::InsufficientDeposit,
+            ChainSignaturesError::InvalidTransaction,
+            ChainSignaturesError::InvalidSchema,
+        ]
+    );
+
+    let payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let quote = svm
+        .simulate(
+            &[signet::quote_sign_bidirectional(
+                requester,
+                Some(payer),
+                &request,
+            )],
+            &[],
+        )
+        .unwrap()
+        .return_value::<SignBidirectionalQuote>()
+        .unwrap();
+    assert_eq!(
+        quote.failures,
+        [
+            ChainSignaturesError::InvalidTransaction,
+            ChainSignaturesError::InvalidSchema,
+        ]
+    );
+}
+
+#[test]
+fn quote_sign_bidirectional_reports_chain_id_and_size_failures() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let cases = [
+        (
+            SignBidirectionalRequest::new(vec![1], &"a".repeat(MAX_CHAIN_ID_LEN + 1), ""),
+            ChainSignaturesError::ChainIdTooLong,
+        ),
+        (
+            SignBidirectionalRequest::new(vec![1], "eip155", ""),
+            ChainSignaturesError::InvalidChainId,
+        ),
+        (
+            SignBidirectionalRequest::new(vec![1; MAX_REQUEST_BUFFER_LEN], "eip155:1", ""),
+            ChainSignaturesError::RequestTooLarge,
+        ),
+    ];
+
+    for (request, error) in cases {
+        let quote = svm
+            .simulate(
+                &[signet::quote_sign_bidirectional(requester, None, &request)],
+                &[],
+            )
+            .unwrap()
+            .return_value::<SignBidirectionalQuote>()
+            .unwrap();
+        assert_eq!(quote.failures, [error]);
+
+        let result = svm.process(
+            &[signet::sign_bidirectional(requester, None, &request)],
+            &[requester],
+        );
+        assert_eq!(result.unwrap_err(), anchor_error(error));
+    }
+}
+
+#[test]
+fn sign_bidirectional_from_buffer_signs_uploaded_requests() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignBidirectionalRequest::new(vec![0xab; 4000], "eip155:1", "vault");
+    assert!(
+        transaction_size(&[signet::sign_bidirectional(requester, None, &request)])
+            > PACKET_DATA_SIZE
+    );
+
+    svm.process(
+        &[signet::init_request_buffer(requester, payer, 7, &request)],
+        &[requester, payer],
+    )
+    .unwrap();
+    let buffer = signet::request_buffer(&requester, 7);
+    let buffer_rent = svm.balance(&buffer);
+    assert_eq!(
+        buffer_rent,
+        rent(RequestBuffer::space(request.buffer_payload().len()))
+    );
+    assert_eq!(svm.balance(&payer), LAMPORTS_PER_SOL - buffer_rent);
+
+    for write in signet::upload_request_buffer(requester, 7, &request, 900) {
+        assert!(transaction_size(std::slice::from_ref(&write)) <= PACKET_DATA_SIZE);
+        svm.process(&[write], &[requester]).unwrap();
+    }
+    let state_balance = svm.balance(&signet::program_state());
+
+    let meta = svm
+        .process(
+            &[signet::sign_bidirectional_from_buffer(
+                requester, None, payer, 7, &request,
+            )],
+            &[requester],
+        )
+        .unwrap();
+
+    let event = &meta.events::<SignBidirectionalEvent>()[0];
+    assert_eq!(event.sender, requester);
+    assert_eq!(event.serialized_transaction, request.serialized_transaction);
+    assert_eq!(
+        event.output_deserialization_schema,
+        request.output_deserialization_schema
+    );
+    assert_eq!(
+        event.respond_serialization_schema,
+        request.respond_serialization_schema
+    );
+    assert_eq!(event.path, "vault");
+    assert!(svm.account(&buffer).is_none());
+    assert_eq!(svm.balance(&payer), LAMPORTS_PER_SOL);
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL - DEPOSIT);
+    assert_eq!(
+        svm.balance(&signet::program_state()),
+        state_balance + DEPOSIT
+    );
+}
+
+#[test]
+fn sign_bidirectional_from_buffer_accepts_the_maximum_payload() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let mut request = SignBidirectionalRequest::new(vec![], "eip155:1", "vault");
+    request.serialized_transaction = vec![
+        0xcd;
+        MAX_REQUEST_BUFFER_LEN
+            - request.output_deserialization_schema.len()
+            - request.respond_serialization_schema.len()
+    ];
+    assert_eq!(request.buffer_payload().len(), MAX_REQUEST_BUFFER_LEN);
+
+    let mut instructions = vec![signet::init_request_buffer(
+        requester, requester, 3, &request,
+    )];
+    instructions.extend(signet::upload_request_buffer(requester, 3, &request, 900));
+    for instruction in instructions {
+        svm.process(&[instruction], &[requester]).unwrap();
+    }
+
+    let sign = signet::sign_bidirectional_from_buffer(requester, None, requester, 3, &request);
+    assert!(transaction_size(std::slice::from_ref(&sign)) <= PACKET_DATA_SIZE);
+    let meta = svm.process(&[sign], &[requester]).unwrap();
+
+    let event = &meta.events::<SignBidirectionalEvent>()[0];
+    assert_eq!(event.serialized_transaction, request.serialized_transaction);
+    assert_eq!(
+        event.respond_serialization_schema,
+        request.respond_serialization_schema
+    );
+    assert!(svm
+        .account(&signet::request_buffer(&requester, 3))
+        .is_none());
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL - DEPOSIT);
+}
+
+#[test]
+fn request_buffers_reject_oversized_payloads_and_writes() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+
+    let oversized = SignBidirectionalRequest::new(vec![0; MAX_REQUEST_BUFFER_LEN], "eip155:1", "");
+    let result = svm.process(
+        &[signet::init_request_buffer(
+            requester, requester, 0, &oversized,
+        )],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::RequestBufferTooLarge)
+    );
+
+    let request = SignBidirectionalRequest::new(vec![1; 64], "eip155:1", "");
+    svm.process(
+        &[signet::init_request_buffer(
+            requester, requester, 0, &request,
+        )],
+        &[requester],
+    )
+    .unwrap();
+    let len = request.buffer_payload().len() as u32;
+
+    let result = svm.process(
+        &[signet::write_request_buffer(
+            requester,
+            0,
+            len - 1,
+            vec![0; 2],
+        )],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::RequestBufferOverflow)
+    );
+
+    let attacker = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let mut write = signet::write_request_buffer(attacker, 0, 0, vec![0; 8]);
+    write.accounts[1].pubkey = signet::request_buffer(&requester, 0);
+    let result = svm.process(&[write], &[attacker]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidRequestBuffer)
+    );
+}
+
+#[test]
+fn sign_bidirectional_from_buffer_keeps_the_buffer_on_failure() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignBidirectionalRequest {
+        respond_serialization_schema: b"{\"struct\":".to_vec(),
+        ..SignBidirectionalRequest::new(vec![1; 64], "eip155:1", "")
+    };
+    let mut instructions = vec![signet::init_request_buffer(
+        requester, requester, 0, &request,
+    )];
+    instructions.extend(signet::upload_request_buffer(requester, 0, &request, 512));
+    svm.process(&instructions, &[requester]).unwrap();
+    let buffer = signet::request_buffer(&requester, 0);
+    let before = svm.account(&buffer).cloned();
+
+    let result = svm.process(
+        &[signet::sign_bidirectional_from_buffer(
+            requester, None, requester, 0, &request,
+        )],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidSchema)
+    );
+
+    let other = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let result = svm.process(
+        &[signet::sign_bidirectional_from_buffer(
+            requester, None, other, 0, &request,
+        )],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidRentPayer)
+    );
+    assert_eq!(svm.account(&buffer).cloned(), before);
+}
+
+#[test]
+fn close_request_buffer_refunds_abandoned_uploads() {
+    let (mut svm, _, _) = setup();
+    let requester = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let payer = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let request = SignBidirectionalRequest::new(vec![1; 64], "eip155:1", "");
+    let mut instructions = vec![signet::init_request_buffer(requester, payer, 5, &request)];
+    instructions.extend(signet::upload_request_buffer(requester, 5, &request, 512));
+    svm.process(&instructions, &[requester, payer]).unwrap();
+    let buffer = signet::request_buffer(&requester, 5);
+
+    let attacker = funded_account(&mut svm, LAMPORTS_PER_SOL);
+    let mut close = signet::close_request_buffer(attacker, attacker, 5);
+    close.accounts[1].pubkey = buffer;
+    let result = svm.process(&[close], &[attacker]);
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidRequestBuffer)
+    );
+
+    let result = svm.process(
+        &[signet::close_request_buffer(requester, requester, 5)],
+        &[requester],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        anchor_error(ChainSignaturesError::InvalidRentPayer)
+    );
+
+    svm.process(
+        &[signet::close_request_buffer(requester, payer, 5)],
+        &[requester],
+    )
+    .unwrap();
+    assert!(svm.account(&buffer).is_none());
+    assert_eq!(svm.balance(&payer), LAMPORTS_PER_SOL);
+    assert_eq!(svm.balance(&requester), LAMPORTS_PER_SOL);
+
+    // The id can be reused once the buffer is closed.
+    svm.process(
+        &[signet::init_request_buffer(requester, payer, 5, &request)],
+        &[requester, payer],
+    )
+    .unwrap();
+}
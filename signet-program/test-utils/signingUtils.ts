@@ -7,11 +7,13 @@
  * - Waiting for signature responses from the subscriber
  * - Logging payload descriptions for easy debugging
  */
-import type * as anchor from '@coral-xyz/anchor';
+import * as anchor from '@coral-xyz/anchor';
 import type { Program } from '@coral-xyz/anchor';
 import type { ProxyTestCpi } from '../target/types/proxy_test_cpi';
 import type { ChainSignatures } from '../target/types/chain_signatures';
-import type { contracts } from 'signet.js';
+import { contracts } from 'signet.js';
+import { SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
+import { responseRecordPda, signatureRequestPda } from './utils.js';
 
 export interface SignArgs {
   payload: number[];
@@ -56,15 +58,59 @@ export function createSignArgs(
   };
 }
 
+/**
+ * `SignatureRequest` PDA that `sign` creates for `requester`'s request, and
+ * the `ResponseRecord` PDA that `sign` checks so that answered requests are
+ * not signed again
+ */
+export async function signRequestAccounts(
+  program: Program<ChainSignatures>,
+  requester: anchor.web3.PublicKey,
+  signArgs: SignArgs
+): Promise<{
+  signatureRequest: anchor.web3.PublicKey;
+  responseRecord: anchor.web3.PublicKey;
+}> {
+  const [programStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('program-state')],
+    program.programId
+  );
+  const { chainId } = await program.account.programState.fetch(programStatePda);
+
+  const requestId = contracts.solana.getRequestIdRespond({
+    address: requester.toString(),
+    payload: signArgs.payload,
+    path: signArgs.path,
+    keyVersion: signArgs.keyVersion,
+    chainId,
+    algo: signArgs.algo,
+    dest: signArgs.dest,
+    params: signArgs.params,
+  });
+
+  const requestIdBytes = Array.from(Buffer.from(requestId.slice(2), 'hex'));
+  return {
+    signatureRequest: signatureRequestPda(program.programId, requestIdBytes),
+    responseRecord: responseRecordPda(program.programId, requestIdBytes),
+  };
+}
+
 /**
  * Call sign function via CPI proxy
  */
 export async function callProxySign(
   proxyProgram: Program<ProxyTestCpi>,
+  signetProgram: Program<ChainSignatures>,
   signArgs: SignArgs,
   feePayer: anchor.web3.PublicKey,
   eventAuthorityPda: anchor.web3.PublicKey
 ): Promise<string> {
+  const requestAccounts = await signRequestAccounts(
+    signetProgram,
+    proxyProgram.provider.publicKey!,
+    signArgs
+  );
+
   return proxyProgram.methods
     .callSign(
       signArgs.payload,
@@ -76,6 +122,7 @@ export async function callProxySign(
     )
     .accounts({
       feePayer,
+      ...requestAccounts,
       eventAuthority: eventAuthorityPda,
     })
     .rpc();
@@ -88,6 +135,12 @@ export async function callDirectSign(
   program: Program<ChainSignatures>,
   signArgs: SignArgs
 ): Promise<string> {
+  const requestAccounts = await signRequestAccounts(
+    program,
+    program.provider.publicKey!,
+    signArgs
+  );
+
   return program.methods
     .sign(
       signArgs.payload,
@@ -97,6 +150,10 @@ export async function callDirectSign(
       signArgs.dest,
       signArgs.params
     )
+    .accountsPartial({
+      ...requestAccounts,
+      instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    })
     .rpc();
 }
 
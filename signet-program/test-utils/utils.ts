@@ -15,3 +15,103 @@ export const confirmTransaction = async (
     'confirmed'
   );
 };
+
+/**
+ * `ResponseRecord` PDA (seeds `["response", requestId]`) that `respond`
+ * creates once a request is answered.
+ */
+export const responseRecordPda = (
+  programId: anchor.web3.PublicKey,
+  requestId: number[]
+): anchor.web3.PublicKey =>
+  anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('response'), Buffer.from(requestId)],
+    programId
+  )[0];
+
+/**
+ * Remaining accounts for `respond_error`: the `ResponseRecord` PDA of every
+ * request id, in order.
+ */
+export const responseRecordAccounts = (
+  programId: anchor.web3.PublicKey,
+  requestIds: number[][]
+): anchor.web3.AccountMeta[] =>
+  requestIds.map((requestId) => ({
+    pubkey: responseRecordPda(programId, requestId),
+    isSigner: false,
+    isWritable: true,
+  }));
+
+/**
+ * `SignatureRequest` PDA (seeds `["request", requestId]`) that `sign` creates
+ * to track a request for on-chain response verification.
+ */
+export const signatureRequestPda = (
+  programId: anchor.web3.PublicKey,
+  requestId: number[]
+): anchor.web3.PublicKey =>
+  anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('request'), Buffer.from(requestId)],
+    programId
+  )[0];
+
+/**
+ * Remaining accounts for `respond`: the `SignatureRequest` PDA, the
+ * `ResponseRecord` PDA and `rentPayer`, which receives the rent of the closed
+ * `SignatureRequest`, for every request id, in order.
+ */
+export const respondAccounts = (
+  programId: anchor.web3.PublicKey,
+  requestIds: number[][],
+  rentPayer: anchor.web3.PublicKey
+): anchor.web3.AccountMeta[] =>
+  requestIds.flatMap((requestId) => [
+    {
+      pubkey: signatureRequestPda(programId, requestId),
+      isSigner: false,
+      isWritable: true,
+    },
+    ...responseRecordAccounts(programId, [requestId]),
+    { pubkey: rentPayer, isSigner: false, isWritable: true },
+  ]);
+
+/**
+ * `RequesterRateLimit` PDA (seeds `["rate-limit", key]`) counting the requests
+ * of a requester.
+ */
+export const rateLimitPda = (
+  programId: anchor.web3.PublicKey,
+  key: anchor.web3.PublicKey
+): anchor.web3.PublicKey =>
+  anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('rate-limit'), key.toBuffer()],
+    programId
+  )[0];
+
+/**
+ * `RequestBuffer` PDA (seeds `["request-buffer", owner, bufferId]`), which
+ * `write_request_buffer` and `sign_bidirectional_from_buffer` take explicitly.
+ */
+export const requestBufferPda = (
+  programId: anchor.web3.PublicKey,
+  owner: anchor.web3.PublicKey,
+  bufferId: anchor.BN
+): anchor.web3.PublicKey =>
+  anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from('request-buffer'),
+      owner.toBuffer(),
+      bufferId.toArrayLike(Buffer, 'le', 8),
+    ],
+    programId
+  )[0];
+
+export const allowlistPda = (
+  programId: anchor.web3.PublicKey,
+  key: anchor.web3.PublicKey
+): anchor.web3.PublicKey =>
+  anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('allowlist'), key.toBuffer()],
+    programId
+  )[0];
@@ -109,6 +109,69 @@ describe('Configuration Functions', () => {
     });
   });
 
+  describe('update_chain_id', () => {
+    it('Should update the chain id and emit an event', async () => {
+      const { chainId } =
+        await program.account.programState.fetch(programStatePda);
+      const newChainId = 'solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1';
+
+      try {
+        const txSignature = await program.methods
+          .updateChainId(newChainId)
+          .rpc();
+        await confirmTransaction(connection, txSignature);
+        const events = await getEventsFromTransaction(txSignature);
+
+        const programStateAfter =
+          await program.account.programState.fetch(programStatePda);
+        assert.equal(programStateAfter.chainId, newChainId);
+
+        const chainIdUpdatedEvents = events.filter(
+          (e) => e.name === 'chainIdUpdatedEvent'
+        );
+        assert.ok(
+          chainIdUpdatedEvents.length > 0,
+          'ChainIdUpdatedEvent should have been emitted'
+        );
+        assert.equal(chainIdUpdatedEvents[0].data.oldChainId, chainId);
+        assert.equal(chainIdUpdatedEvents[0].data.newChainId, newChainId);
+      } finally {
+        const restoreTx = await program.methods.updateChainId(chainId).rpc();
+        await confirmTransaction(connection, restoreTx);
+      }
+    });
+
+    it('Should reject chain ids that are not CAIP-2', async () => {
+      try {
+        await program.methods.updateChainId('solana').rpc();
+
+        assert.fail('Should have thrown an error for an invalid chain id');
+      } catch (error) {
+        assert.ok(
+          error.message.includes('Invalid CAIP-2 chain id'),
+          `Expected invalid chain id error, got: ${error.message}`
+        );
+      }
+    });
+
+    it('Should fail when called by non-admin', async () => {
+      try {
+        await program.methods
+          .updateChainId('solana:mainnet')
+          .accounts({ admin: nonAdminKeypair.publicKey })
+          .signers([nonAdminKeypair])
+          .rpc();
+
+        assert.fail('Should have thrown an error for unauthorized access');
+      } catch (error) {
+        assert.ok(
+          error.message.includes('Unauthorized access'),
+          `Expected unauthorized error, got: ${error.message}`
+        );
+      }
+    });
+  });
+
   describe('withdraw_funds', () => {
     const newDeposit = new BN('50000');
 
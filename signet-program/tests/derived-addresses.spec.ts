@@ -0,0 +1,64 @@
+import { assert } from 'chai';
+import * as fs from 'fs';
+import * as path from 'path';
+import { fileURLToPath } from 'url';
+import { AddressUtils, CryptoUtils } from 'fakenet-signer';
+
+const __dirname = path.dirname(fileURLToPath(import.meta.url));
+
+// Shared with the `chain_signatures::address` unit tests.
+const VECTORS_PATH = path.resolve(
+  __dirname,
+  '../programs/signet/test-vectors/derived_addresses.json'
+);
+
+interface DerivedAddressVector {
+  root_secret_key: string;
+  chain_id: string;
+  sender: string;
+  path: string;
+  epsilon: string;
+  derived_public_key: string;
+  evm_address: string;
+  bitcoin: { hrp: string; p2wpkh: string; p2tr: string };
+  cosmos: { hrp: string; address: string };
+}
+
+describe('Derived address vectors', () => {
+  const vectors: DerivedAddressVector[] = JSON.parse(
+    fs.readFileSync(VECTORS_PATH, 'utf8')
+  );
+
+  vectors.forEach((vector) => {
+    it(`derives addresses of '${vector.path}' on ${vector.chain_id}`, () => {
+      const epsilon = CryptoUtils.deriveEpsilon(
+        vector.sender,
+        vector.path,
+        vector.chain_id
+      );
+      assert.equal(epsilon, BigInt(`0x${vector.epsilon}`));
+
+      const derivedKey = CryptoUtils.deriveSigningKeyFromEpsilon(
+        epsilon,
+        vector.root_secret_key
+      );
+      assert.equal(
+        AddressUtils.publicKey(derivedKey),
+        vector.derived_public_key
+      );
+      assert.equal(AddressUtils.evmAddress(derivedKey), vector.evm_address);
+      assert.equal(
+        AddressUtils.p2wpkhAddress(derivedKey, vector.bitcoin.hrp),
+        vector.bitcoin.p2wpkh
+      );
+      assert.equal(
+        AddressUtils.p2trAddress(derivedKey, vector.bitcoin.hrp),
+        vector.bitcoin.p2tr
+      );
+      assert.equal(
+        AddressUtils.cosmosAddress(derivedKey, vector.cosmos.hrp),
+        vector.cosmos.address
+      );
+    });
+  });
+});
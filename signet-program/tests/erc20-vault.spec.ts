@@ -0,0 +1,210 @@
+import * as anchor from '@coral-xyz/anchor';
+import type { Program } from '@coral-xyz/anchor';
+import { assert } from 'chai';
+import BN from 'bn.js';
+import { ethers } from 'ethers';
+import { CryptoUtils } from 'fakenet-signer';
+import { contracts } from 'signet.js';
+import type { ProxyTestCpi } from '../target/types/proxy_test_cpi';
+import { testSetup } from '../test-utils/testSetup.js';
+import { testEnvConfig } from '../test-utils/testEnvConfig.js';
+
+describe('ERC20 vault integration', () => {
+  const { provider, program: signetProgram } = testSetup();
+
+  const proxyProgram = anchor.workspace.proxyTestCpi as Program<ProxyTestCpi>;
+
+  const [eventAuthorityPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('__event_authority')],
+    signetProgram.programId
+  );
+  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('vault')],
+    proxyProgram.programId
+  );
+
+  const requester = provider.wallet.publicKey;
+  const erc20Address = ethers.getBytes(
+    '0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238'
+  );
+  const erc20 = new ethers.Interface([
+    'function transfer(address to, uint256 amount) returns (bool)',
+  ]);
+  const txParams = {
+    chainId: new BN(11155111),
+    gasLimit: new BN(100_000),
+    maxFeePerGas: new BN(30_000_000_000),
+    maxPriorityFeePerGas: new BN(1_000_000_000),
+  };
+
+  const pendingDepositPda = (requestId: number[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('pending-erc20-deposit'), Buffer.from(requestId)],
+      proxyProgram.programId
+    )[0];
+
+  const [balancePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from('erc20-balance'),
+      requester.toBuffer(),
+      Buffer.from(erc20Address),
+    ],
+    proxyProgram.programId
+  );
+
+  // Mirrors the transaction `deposit_erc20` builds on-chain.
+  const depositRequestId = async (amount: bigint, nonce: number) => {
+    const treasury = ethers.computeAddress(
+      await CryptoUtils.deriveSigningKey(
+        'treasury',
+        vaultPda.toString(),
+        testEnvConfig.MPC_ROOT_KEY
+      )
+    );
+    const transaction = ethers.Transaction.from({
+      type: 2,
+      chainId: txParams.chainId.toString(),
+      nonce,
+      gasLimit: txParams.gasLimit.toString(),
+      maxFeePerGas: txParams.maxFeePerGas.toString(),
+      maxPriorityFeePerGas: txParams.maxPriorityFeePerGas.toString(),
+      to: ethers.hexlify(erc20Address),
+      value: 0,
+      data: erc20.encodeFunctionData('transfer', [treasury, amount]),
+      accessList: [],
+    });
+
+    const requestId = contracts.solana.getRequestIdBidirectional({
+      sender: vaultPda.toString(),
+      payload: Array.from(ethers.getBytes(transaction.unsignedSerialized)),
+      caip2Id: `eip155:${txParams.chainId.toString()}`,
+      keyVersion: 0,
+      path: requester.toString(),
+      algo: '',
+      dest: '',
+      params: '',
+    });
+    return Array.from(ethers.getBytes(requestId));
+  };
+
+  const deposit = (requestId: number[], amount: bigint, nonce: number) =>
+    proxyProgram.methods
+      .depositErc20(
+        requestId,
+        Array.from(erc20Address),
+        new BN(amount.toString()),
+        { ...txParams, nonce: new BN(nonce) }
+      )
+      .accountsPartial({
+        requester,
+        pendingDeposit: pendingDepositPda(requestId),
+        eventAuthority: eventAuthorityPda,
+      })
+      .rpc();
+
+  const claim = async (
+    requestId: number[],
+    status: 'success' | 'reverted',
+    output: Buffer,
+    responseSender = vaultPda
+  ) => {
+    const signature = await CryptoUtils.signExecutionResponse(
+      Uint8Array.from(requestId),
+      status,
+      output,
+      null,
+      testEnvConfig.MPC_ROOT_KEY,
+      responseSender.toString()
+    );
+    return proxyProgram.methods
+      .claimErc20(requestId, { [status]: {} }, output, null, signature)
+      .accountsPartial({
+        pendingDeposit: pendingDepositPda(requestId),
+        balance: balancePda,
+        requester,
+      })
+      .rpc();
+  };
+
+  const balance = async () => {
+    const account = await proxyProgram.account.erc20Balance.fetchNullable(
+      balancePda
+    );
+    return account ? BigInt(account.amount.toString()) : 0n;
+  };
+
+  const expectError = async (call: () => Promise<string>, expected: string) => {
+    try {
+      await call();
+      assert.fail(`Should have thrown ${expected}`);
+    } catch (error) {
+      assert.ok(
+        error.message.includes(expected),
+        `Expected ${expected} error, got: ${error.message}`
+      );
+    }
+  };
+
+  // Unique nonces keep request ids distinct across runs.
+  let nonce = Date.now();
+
+  it('Records a pending deposit and credits it on success', async () => {
+    const amount = 1_000_000n;
+    const requestId = await depositRequestId(amount, nonce);
+    await deposit(requestId, amount, nonce);
+    nonce += 1;
+
+    const pending = await proxyProgram.account.pendingErc20Deposit.fetch(
+      pendingDepositPda(requestId)
+    );
+    assert.ok(pending.requester.equals(requester));
+    assert.equal(pending.amount.toString(), amount.toString());
+
+    const before = await balance();
+    // Borsh `true`, the serialized return value of `transfer`.
+    await claim(requestId, 'success', Buffer.from([1]));
+
+    assert.equal(await balance(), before + amount);
+    assert.isNull(
+      await proxyProgram.account.pendingErc20Deposit.fetchNullable(
+        pendingDepositPda(requestId)
+      ),
+      'The pending deposit should be closed'
+    );
+  });
+
+  it('Closes failed deposits without crediting', async () => {
+    const amount = 500n;
+    const requestId = await depositRequestId(amount, nonce);
+    await deposit(requestId, amount, nonce);
+    nonce += 1;
+
+    const before = await balance();
+    await claim(requestId, 'reverted', Buffer.alloc(0));
+
+    assert.equal(await balance(), before);
+    assert.isNull(
+      await proxyProgram.account.pendingErc20Deposit.fetchNullable(
+        pendingDepositPda(requestId)
+      ),
+      'The pending deposit should be closed'
+    );
+  });
+
+  it('Rejects responses not signed by the vault response key', async () => {
+    const amount = 42n;
+    const requestId = await depositRequestId(amount, nonce);
+    await deposit(requestId, amount, nonce);
+    nonce += 1;
+
+    await expectError(
+      () => claim(requestId, 'success', Buffer.from([1]), requester),
+      'InvalidSignature'
+    );
+  });
+
+  it('Rejects deposits with a mismatched request id', async () => {
+    const requestId = await depositRequestId(7n, nonce);
+    await expectError(() => deposit(requestId, 8n, nonce), 'InvalidRequestId');
+  });
+});
@@ -0,0 +1,103 @@
+import * as anchor from '@coral-xyz/anchor';
+import { assert } from 'chai';
+import { Keypair } from '@solana/web3.js';
+import BN from 'bn.js';
+import { ethers } from 'ethers';
+import { testSetup } from '../test-utils/testSetup.js';
+import { testEnvConfig } from '../test-utils/testEnvConfig.js';
+import { createSignArgs, callDirectSign } from '../test-utils/signingUtils.js';
+
+describe('Key version registry', () => {
+  const { program } = testSetup();
+
+  // Later specs sign with key version 0, so it stays registered and active.
+  const rootPublicKey = Array.from(
+    Buffer.from(
+      new ethers.SigningKey(testEnvConfig.MPC_ROOT_KEY).publicKey.slice(4),
+      'hex'
+    )
+  );
+
+  const [keyVersionRegistryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('key-version-registry')],
+    program.programId
+  );
+
+  const setKeyVersion = (
+    keyVersion: number,
+    active: boolean,
+    deprecationSlot: BN | null
+  ) =>
+    program.methods
+      .setKeyVersion(keyVersion, rootPublicKey, active, deprecationSlot)
+      .rpc();
+
+  const expectSignError = async (keyVersion: number, expected: string) => {
+    const signArgs = {
+      ...createSignArgs('CONFIG_TEST', `key-version-${keyVersion}`),
+      keyVersion,
+    };
+    try {
+      await callDirectSign(program, signArgs);
+      assert.fail(`Should have thrown ${expected}`);
+    } catch (error) {
+      assert.ok(
+        error.message.includes(expected),
+        `Expected ${expected} error, got: ${error.message}`
+      );
+    }
+  };
+
+  it('Registers the active key version', async () => {
+    await setKeyVersion(0, true, null);
+
+    const registry = await program.account.keyVersionRegistry.fetch(
+      keyVersionRegistryPda
+    );
+    const entry = registry.versions.find((version) => version.keyVersion === 0);
+    assert.ok(entry, 'Key version 0 should be registered');
+    assert.deepEqual(entry.publicKey, rootPublicKey);
+    assert.isTrue(entry.active);
+    assert.isNull(entry.deprecationSlot);
+  });
+
+  it('Accepts requests for the active key version', async () => {
+    const signArgs = createSignArgs(
+      'CONFIG_TEST',
+      `key-version-0-${Date.now()}`
+    );
+    const tx = await callDirectSign(program, signArgs);
+    assert.ok(tx, 'Transaction should succeed');
+  });
+
+  it('Rejects requests for an unregistered key version', async () => {
+    await expectSignError(99, 'UnknownKeyVersion');
+  });
+
+  it('Rejects requests for an inactive key version', async () => {
+    await setKeyVersion(1, false, null);
+    await expectSignError(1, 'RetiredKeyVersion');
+  });
+
+  it('Rejects requests past the deprecation slot', async () => {
+    await setKeyVersion(2, true, new BN(0));
+    await expectSignError(2, 'RetiredKeyVersion');
+  });
+
+  it('Rejects updates from non-admin', async () => {
+    const nonAdminKeypair = Keypair.generate();
+    try {
+      await program.methods
+        .setKeyVersion(3, rootPublicKey, true, null)
+        .accounts({ admin: nonAdminKeypair.publicKey })
+        .signers([nonAdminKeypair])
+        .rpc();
+      assert.fail('Should have thrown an error for unauthorized access');
+    } catch (error) {
+      assert.ok(
+        error.message.includes('Unauthorized access'),
+        `Expected unauthorized error, got: ${error.message}`
+      );
+    }
+  });
+});
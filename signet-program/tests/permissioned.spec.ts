@@ -0,0 +1,178 @@
+import * as anchor from '@coral-xyz/anchor';
+import type { Program } from '@coral-xyz/anchor';
+import { assert } from 'chai';
+import { Keypair, SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
+import type { ProxyTestCpi } from '../target/types/proxy_test_cpi';
+import { testSetup } from '../test-utils/testSetup.js';
+import { allowlistPda } from '../test-utils/utils.js';
+import {
+  createSignArgs,
+  signRequestAccounts,
+  type SignArgs,
+} from '../test-utils/signingUtils.js';
+
+describe('Permissioned mode', () => {
+  const { provider, program } = testSetup();
+
+  const proxyProgram = anchor.workspace.proxyTestCpi as Program<ProxyTestCpi>;
+
+  const [eventAuthorityPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('__event_authority')],
+    program.programId
+  );
+
+  const [programStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('program-state')],
+    program.programId
+  );
+
+  const requester = provider.wallet.publicKey;
+
+  const setPermissioned = (permissioned: boolean) =>
+    program.methods.setPermissioned(permissioned).rpc();
+
+  const addToAllowlist = (key: anchor.web3.PublicKey) =>
+    program.methods.addToAllowlist(key).rpc();
+
+  const removeFromAllowlist = (key: anchor.web3.PublicKey) =>
+    program.methods.removeFromAllowlist(key).rpc();
+
+  const directSign = async (signArgs: SignArgs) =>
+    program.methods
+      .sign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        ...(await signRequestAccounts(
+          program,
+          requester,
+          signArgs
+        )),
+        allowlistEntry: allowlistPda(program.programId, requester),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .rpc();
+
+  const proxySign = async (signArgs: SignArgs) =>
+    proxyProgram.methods
+      .callSign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        feePayer: requester,
+        ...(await signRequestAccounts(
+          program,
+          requester,
+          signArgs
+        )),
+        allowlistEntry: allowlistPda(program.programId, requester),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        eventAuthority: eventAuthorityPda,
+      })
+      .rpc();
+
+  const expectError = async (call: () => Promise<string>, expected: string) => {
+    try {
+      await call();
+      assert.fail(`Should have thrown ${expected}`);
+    } catch (error) {
+      assert.ok(
+        error.message.includes(expected),
+        `Expected ${expected} error, got: ${error.message}`
+      );
+    }
+  };
+
+  const uniqueSignArgs = (label: string) =>
+    createSignArgs('CONFIG_TEST', `permissioned-${label}-${Date.now()}`);
+
+  before(async () => {
+    await setPermissioned(true);
+  });
+
+  after(async () => {
+    await setPermissioned(false);
+  });
+
+  it('Enables permissioned mode', async () => {
+    const programState = await program.account.programState.fetch(
+      programStatePda
+    );
+    assert.isTrue(programState.permissioned);
+  });
+
+  it('Rejects requests from requesters not on the allowlist', async () => {
+    await expectError(
+      () => directSign(uniqueSignArgs('direct-denied')),
+      'NotAllowlisted'
+    );
+  });
+
+  it('Accepts requests from allowlisted requesters', async () => {
+    await addToAllowlist(requester);
+    try {
+      const tx = await directSign(uniqueSignArgs('direct-allowed'));
+      assert.ok(tx, 'Transaction should succeed');
+    } finally {
+      await removeFromAllowlist(requester);
+    }
+
+    await expectError(
+      () => directSign(uniqueSignArgs('direct-removed')),
+      'NotAllowlisted'
+    );
+  });
+
+  it('Checks CPI requests against the requester', async () => {
+    // Allowlisting the proxy does not admit its callers.
+    await addToAllowlist(proxyProgram.programId);
+    try {
+      await expectError(
+        () => proxySign(uniqueSignArgs('cpi-denied')),
+        'NotAllowlisted'
+      );
+    } finally {
+      await removeFromAllowlist(proxyProgram.programId);
+    }
+
+    await addToAllowlist(requester);
+    try {
+      const tx = await proxySign(uniqueSignArgs('cpi-allowed'));
+      assert.ok(tx, 'Transaction should succeed');
+    } finally {
+      await removeFromAllowlist(requester);
+    }
+  });
+
+  it('Rejects updates from non-admin', async () => {
+    const nonAdminKeypair = Keypair.generate();
+    await expectError(
+      () =>
+        program.methods
+          .setPermissioned(false)
+          .accounts({ admin: nonAdminKeypair.publicKey })
+          .signers([nonAdminKeypair])
+          .rpc(),
+      'Unauthorized access'
+    );
+    await expectError(
+      () =>
+        program.methods
+          .addToAllowlist(nonAdminKeypair.publicKey)
+          .accounts({ admin: nonAdminKeypair.publicKey })
+          .signers([nonAdminKeypair])
+          .rpc(),
+      'Unauthorized access'
+    );
+  });
+});
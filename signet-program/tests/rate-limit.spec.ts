@@ -0,0 +1,207 @@
+import * as anchor from '@coral-xyz/anchor';
+import type { Program } from '@coral-xyz/anchor';
+import { assert } from 'chai';
+import { Keypair, SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
+import BN from 'bn.js';
+import type { ProxyTestCpi } from '../target/types/proxy_test_cpi';
+import { testSetup } from '../test-utils/testSetup.js';
+import { rateLimitPda } from '../test-utils/utils.js';
+import {
+  createSignArgs,
+  signRequestAccounts,
+  type SignArgs,
+} from '../test-utils/signingUtils.js';
+
+describe('Rate limiting', () => {
+  const { provider, program } = testSetup();
+
+  const proxyProgram = anchor.workspace.proxyTestCpi as Program<ProxyTestCpi>;
+
+  const [eventAuthorityPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('__event_authority')],
+    program.programId
+  );
+
+  const MAX_REQUESTS = 2;
+
+  const setRateLimit = (
+    maxRequests: number,
+    windowSlots: number,
+    globalMaxRequests = 0
+  ) =>
+    program.methods
+      .setRateLimit(maxRequests, new BN(windowSlots), globalMaxRequests)
+      .rpc();
+
+  const directSign = async (signArgs: SignArgs, withRateLimit = true) =>
+    program.methods
+      .sign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        ...(await signRequestAccounts(
+          program,
+          provider.wallet.publicKey,
+          signArgs
+        )),
+        rateLimit: withRateLimit
+          ? rateLimitPda(program.programId, provider.wallet.publicKey)
+          : null,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .rpc();
+
+  // Signed by `requester`, with the wallet paying the deposit.
+  const sponsoredSign = async (requester: Keypair, signArgs: SignArgs) =>
+    program.methods
+      .sign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        requester: requester.publicKey,
+        feePayer: provider.wallet.publicKey,
+        ...(await signRequestAccounts(
+          program,
+          requester.publicKey,
+          signArgs
+        )),
+        rateLimit: rateLimitPda(program.programId, requester.publicKey),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .signers([requester])
+      .rpc();
+
+  // Signed by `requester` through the proxy, with the wallet paying the
+  // deposit.
+  const proxySign = async (requester: Keypair, signArgs: SignArgs) =>
+    proxyProgram.methods
+      .callSign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        requester: requester.publicKey,
+        feePayer: provider.wallet.publicKey,
+        ...(await signRequestAccounts(
+          program,
+          requester.publicKey,
+          signArgs
+        )),
+        rateLimit: rateLimitPda(program.programId, requester.publicKey),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        eventAuthority: eventAuthorityPda,
+      })
+      .signers([requester])
+      .rpc();
+
+  const expectError = async (call: () => Promise<string>, expected: string) => {
+    try {
+      await call();
+      assert.fail(`Should have thrown ${expected}`);
+    } catch (error) {
+      assert.ok(
+        error.message.includes(expected),
+        `Expected ${expected} error, got: ${error.message}`
+      );
+    }
+  };
+
+  const uniqueSignArgs = (label: string, index: number) =>
+    createSignArgs('CONFIG_TEST', `rate-limit-${label}-${Date.now()}`, index);
+
+  before(async () => {
+    // A window long enough that the whole spec runs within it.
+    await setRateLimit(MAX_REQUESTS, 10_000);
+  });
+
+  after(async () => {
+    await setRateLimit(0, 0);
+  });
+
+  it('Limits direct requests per requester', async () => {
+    for (let i = 0; i < MAX_REQUESTS; i += 1) {
+      await directSign(uniqueSignArgs('direct', i));
+    }
+
+    await expectError(
+      () => directSign(uniqueSignArgs('direct', MAX_REQUESTS)),
+      'RateLimited'
+    );
+  });
+
+  it('Limits CPI requests per requester', async () => {
+    const requester = Keypair.generate();
+    for (let i = 0; i < MAX_REQUESTS; i += 1) {
+      await proxySign(requester, uniqueSignArgs('cpi', i));
+    }
+
+    await expectError(
+      () => proxySign(requester, uniqueSignArgs('cpi', MAX_REQUESTS)),
+      'RateLimited'
+    );
+    // The proxy does not share one bucket between its users.
+    await proxySign(Keypair.generate(), uniqueSignArgs('cpi-other', 0));
+  });
+
+  it('Limits requests across requesters', async () => {
+    await setRateLimit(0, 10_000, MAX_REQUESTS);
+    try {
+      const requesters = Array.from({ length: MAX_REQUESTS + 1 }, () =>
+        Keypair.generate()
+      );
+      for (let i = 0; i < MAX_REQUESTS; i += 1) {
+        await sponsoredSign(requesters[i], uniqueSignArgs('global', i));
+      }
+
+      await expectError(
+        () =>
+          sponsoredSign(
+            requesters[MAX_REQUESTS],
+            uniqueSignArgs('global', MAX_REQUESTS)
+          ),
+        'RateLimited'
+      );
+    } finally {
+      await setRateLimit(MAX_REQUESTS, 10_000);
+    }
+  });
+
+  it('Requires the rate limit account while limiting is enabled', async () => {
+    await expectError(
+      () => directSign(uniqueSignArgs('missing', 0), false),
+      'InvalidRateLimitAccount'
+    );
+  });
+
+  it('Rejects an empty window', async () => {
+    await expectError(() => setRateLimit(1, 0), 'InvalidRateLimit');
+    await expectError(() => setRateLimit(0, 0, 1), 'InvalidRateLimit');
+  });
+
+  it('Rejects updates from non-admin', async () => {
+    const nonAdminKeypair = Keypair.generate();
+    await expectError(
+      () =>
+        program.methods
+          .setRateLimit(1, new BN(1), 0)
+          .accounts({ admin: nonAdminKeypair.publicKey })
+          .signers([nonAdminKeypair])
+          .rpc(),
+      'Unauthorized access'
+    );
+  });
+});
@@ -0,0 +1,178 @@
+import { assert } from 'chai';
+import { ethers } from 'ethers';
+import { CpiEventParser, CryptoUtils } from 'fakenet-signer';
+import { testSetup } from '../test-utils/testSetup.js';
+import { testEnvConfig } from '../test-utils/testEnvConfig.js';
+import { confirmTransaction } from '../test-utils/utils.js';
+import type { PublicKey } from '@solana/web3.js';
+
+interface RespondBidirectionalEvent {
+  requestId: number[];
+  responder: PublicKey;
+  status: Record<string, object>;
+  serializedOutput: Buffer;
+  revertData: Buffer | null;
+  signature: Awaited<ReturnType<typeof CryptoUtils.signMessage>>;
+}
+
+describe('Respond bidirectional tests', () => {
+  const { provider, connection, program } = testSetup();
+
+  const sender = provider.wallet.publicKey.toString();
+
+  const getRespondEvents = async (
+    txSignature: string
+  ): Promise<RespondBidirectionalEvent[]> => {
+    await confirmTransaction(connection, txSignature);
+    const events = await CpiEventParser.parseCpiEvents(
+      connection,
+      txSignature,
+      program.programId.toString(),
+      program
+    );
+    return events
+      .filter((e) => e.name === 'respondBidirectionalEvent')
+      .map((e) => e.data as unknown as RespondBidirectionalEvent);
+  };
+
+  it('Emits a signed reverted status with revert data', async () => {
+    const requestId = Array.from({ length: 32 }, (_, i) => (i + 30) % 256);
+    const revertData = Buffer.from('08c379a0', 'hex');
+    const signature = await CryptoUtils.signExecutionResponse(
+      Uint8Array.from(requestId),
+      'reverted',
+      new Uint8Array(),
+      revertData,
+      testEnvConfig.MPC_ROOT_KEY,
+      sender
+    );
+
+    const tx = await program.methods
+      .respondBidirectional(
+        requestId,
+        { reverted: {} },
+        Buffer.alloc(0),
+        revertData,
+        signature
+      )
+      .accounts({
+        responder: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const events = await getRespondEvents(tx);
+    assert.equal(
+      events.length,
+      1,
+      'respondBidirectionalEvent should be emitted'
+    );
+    const event = events[0];
+
+    assert.deepEqual(event.status, { reverted: {} }, 'Status should match');
+    assert.equal(event.serializedOutput.length, 0, 'Output should be empty');
+    assert.ok(
+      Buffer.from(event.revertData).equals(revertData),
+      'Revert data should match'
+    );
+
+    const messageHash = CryptoUtils.bidirectionalResponseHash(
+      Uint8Array.from(event.requestId),
+      'reverted',
+      event.serializedOutput,
+      event.revertData
+    );
+    const recovered = ethers.recoverAddress(messageHash, {
+      r: '0x' + Buffer.from(event.signature.bigR.x).toString('hex'),
+      s: '0x' + Buffer.from(event.signature.s).toString('hex'),
+      v: event.signature.recoveryId + 27,
+    });
+    const expected = ethers.computeAddress(
+      await CryptoUtils.deriveSigningKey(
+        'solana response key',
+        sender,
+        testEnvConfig.MPC_ROOT_KEY
+      )
+    );
+    assert.equal(recovered, expected, 'Signature should cover the status');
+
+    const successHash = CryptoUtils.bidirectionalResponseHash(
+      Uint8Array.from(event.requestId),
+      'success',
+      event.serializedOutput,
+      event.revertData
+    );
+    assert.notEqual(
+      successHash,
+      messageHash,
+      'A different status should change the signed message'
+    );
+  });
+
+  it('Rejects revert data for a non-reverted status', async () => {
+    const requestId = Array.from({ length: 32 }, (_, i) => (i + 40) % 256);
+    const revertData = Buffer.from('deadbeef', 'hex');
+    const signature = await CryptoUtils.signExecutionResponse(
+      Uint8Array.from(requestId),
+      'success',
+      new Uint8Array(),
+      revertData,
+      testEnvConfig.MPC_ROOT_KEY,
+      sender
+    );
+
+    try {
+      await program.methods
+        .respondBidirectional(
+          requestId,
+          { success: {} },
+          Buffer.alloc(0),
+          revertData,
+          signature
+        )
+        .accounts({
+          responder: provider.wallet.publicKey,
+        })
+        .rpc();
+      assert.fail('Should have thrown UnexpectedRevertData');
+    } catch (error) {
+      assert.ok(
+        error.message.includes('UnexpectedRevertData'),
+        `Expected UnexpectedRevertData error, got: ${error.message}`
+      );
+    }
+  });
+
+  it('Rejects output for a non-successful status', async () => {
+    const requestId = Array.from({ length: 32 }, (_, i) => (i + 50) % 256);
+    const output = Buffer.from([1]);
+    const signature = await CryptoUtils.signExecutionResponse(
+      Uint8Array.from(requestId),
+      'dropped',
+      output,
+      null,
+      testEnvConfig.MPC_ROOT_KEY,
+      sender
+    );
+
+    try {
+      await program.methods
+        .respondBidirectional(
+          requestId,
+          { dropped: {} },
+          output,
+          null,
+          signature
+        )
+        .accounts({
+          responder: provider.wallet.publicKey,
+        })
+        .rpc();
+      assert.fail('Should have thrown UnexpectedOutput');
+    } catch (error) {
+      assert.ok(
+        error.message.includes('UnexpectedOutput'),
+        `Expected UnexpectedOutput error, got: ${error.message}`
+      );
+    }
+  });
+});
@@ -0,0 +1,248 @@
+import { assert } from 'chai';
+import * as anchor from '@coral-xyz/anchor';
+import { SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
+import { CryptoUtils } from 'fakenet-signer';
+import { contracts } from 'signet.js';
+import { testSetup } from '../test-utils/testSetup.js';
+import { testEnvConfig } from '../test-utils/testEnvConfig.js';
+import { createSignArgs, type SignArgs } from '../test-utils/signingUtils.js';
+import {
+  respondAccounts,
+  responseRecordAccounts,
+  responseRecordPda,
+  signatureRequestPda,
+} from '../test-utils/utils.js';
+
+describe('Respond deduplication tests', () => {
+  const { provider, program } = testSetup();
+
+  const [programStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('program-state')],
+    program.programId
+  );
+
+  const uniqueRequestId = (seed: number) =>
+    Array.from({ length: 32 }, (_, i) => (i * 7 + seed + Date.now()) % 256);
+
+  const dummySignature = {
+    bigR: {
+      x: Array.from({ length: 32 }, () => 1),
+      y: Array.from({ length: 32 }, () => 2),
+    },
+    s: Array.from({ length: 32 }, () => 3),
+    recoveryId: 0,
+  };
+
+  type Signature = typeof dummySignature;
+
+  const requestIdOf = async (signArgs: SignArgs) => {
+    const { chainId } =
+      await program.account.programState.fetch(programStatePda);
+    const requestId = contracts.solana.getRequestIdRespond({
+      address: provider.wallet.publicKey.toString(),
+      payload: signArgs.payload,
+      path: signArgs.path,
+      keyVersion: signArgs.keyVersion,
+      chainId,
+      algo: signArgs.algo,
+      dest: signArgs.dest,
+      params: signArgs.params,
+    });
+    return Array.from(Buffer.from(requestId.slice(2), 'hex'));
+  };
+
+  const signIx = async (signArgs: SignArgs) =>
+    program.methods
+      .sign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        signatureRequest: signatureRequestPda(
+          program.programId,
+          await requestIdOf(signArgs)
+        ),
+        responseRecord: responseRecordPda(
+          program.programId,
+          await requestIdOf(signArgs)
+        ),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+  const genuineSignature = async (signArgs: SignArgs) =>
+    CryptoUtils.signMessage(
+      signArgs.payload,
+      await CryptoUtils.deriveSigningKey(
+        signArgs.path,
+        provider.wallet.publicKey.toString(),
+        testEnvConfig.MPC_ROOT_KEY
+      )
+    );
+
+  const respond = (
+    requestIds: number[][],
+    signatures: Signature[],
+    preInstructions: anchor.web3.TransactionInstruction[] = []
+  ) =>
+    program.methods
+      .respond(requestIds, signatures)
+      .accounts({
+        responder: provider.wallet.publicKey,
+      })
+      .remainingAccounts(
+        respondAccounts(
+          program.programId,
+          requestIds,
+          provider.wallet.publicKey
+        )
+      )
+      .preInstructions(preInstructions)
+      .rpc();
+
+  // `sign` and `respond` go in one transaction so the fakenet signer cannot
+  // answer the request first.
+  const signAndRespond = async (signArgs: SignArgs, copies = 1) => {
+    const requestId = await requestIdOf(signArgs);
+    const signature = await genuineSignature(signArgs);
+
+    await respond(
+      Array.from({ length: copies }, () => requestId),
+      Array.from({ length: copies }, () => signature),
+      [await signIx(signArgs)]
+    );
+    return { requestId, signature };
+  };
+
+  const expectError = async (name: string, call: () => Promise<unknown>) => {
+    try {
+      await call();
+      assert.fail(`Should have thrown ${name}`);
+    } catch (error) {
+      assert.ok(
+        error.message.includes(name),
+        `Expected ${name} error, got: ${error.message}`
+      );
+    }
+  };
+
+  const fetchRecord = (requestId: number[]) =>
+    program.account.responseRecord.fetchNullable(
+      responseRecordAccounts(program.programId, [requestId])[0].pubkey
+    );
+
+  it('Records the response for a request id', async () => {
+    const { requestId } = await signAndRespond(
+      createSignArgs('WALLET_TEST', `dedup-record-${Date.now()}`)
+    );
+
+    const responseRecord = await fetchRecord(requestId);
+    assert.ok(
+      responseRecord?.responder.equals(provider.wallet.publicKey),
+      'Record should store the responder'
+    );
+    assert.isNull(
+      await provider.connection.getAccountInfo(
+        signatureRequestPda(program.programId, requestId)
+      ),
+      'Signature request should be closed'
+    );
+  });
+
+  it('Rejects a second respond for the same request id', async () => {
+    const { requestId, signature } = await signAndRespond(
+      createSignArgs('WALLET_TEST', `dedup-second-${Date.now()}`)
+    );
+
+    await expectError('AlreadyResponded', () =>
+      respond([requestId], [signature])
+    );
+  });
+
+  it('Rejects a duplicated request id within one batch', async () => {
+    await expectError('AlreadyResponded', () =>
+      signAndRespond(
+        createSignArgs('WALLET_TEST', `dedup-batch-${Date.now()}`),
+        2
+      )
+    );
+  });
+
+  it('Rejects respond_error after a successful respond', async () => {
+    const { requestId } = await signAndRespond(
+      createSignArgs('WALLET_TEST', `dedup-error-${Date.now()}`)
+    );
+
+    await expectError('AlreadyResponded', () =>
+      program.methods
+        .respondError([
+          { requestId, code: { timeout: {} }, detail: 'late error' },
+        ])
+        .accounts({
+          responder: provider.wallet.publicKey,
+        })
+        .remainingAccounts(
+          responseRecordAccounts(program.programId, [requestId])
+        )
+        .rpc()
+    );
+  });
+
+  it('Lets the genuine response land after a junk front-run', async () => {
+    const signArgs = createSignArgs(
+      'WALLET_TEST',
+      `dedup-front-run-${Date.now()}`
+    );
+    const requestId = await requestIdOf(signArgs);
+
+    // Before the request lands its id is untracked: the junk response is
+    // emitted unverified but not recorded.
+    await respond([requestId], [dummySignature]);
+    assert.isNull(await fetchRecord(requestId));
+
+    // Once tracked, the junk response fails verification.
+    await expectError('InvalidSignature', async () =>
+      respond([requestId], [dummySignature], [await signIx(signArgs)])
+    );
+
+    await signAndRespond(signArgs);
+    const responseRecord = await fetchRecord(requestId);
+    assert.ok(
+      responseRecord?.responder.equals(provider.wallet.publicKey),
+      'Genuine response should be recorded'
+    );
+  });
+
+  it('Rejects a record account that is not the request PDA', async () => {
+    const requestId = uniqueRequestId(5);
+    const [wrongRecord] = responseRecordAccounts(program.programId, [
+      uniqueRequestId(6),
+    ]);
+
+    await expectError('InvalidResponseRecord', () =>
+      program.methods
+        .respond([requestId], [dummySignature])
+        .accounts({
+          responder: provider.wallet.publicKey,
+        })
+        .remainingAccounts([
+          {
+            pubkey: signatureRequestPda(program.programId, requestId),
+            isSigner: false,
+            isWritable: true,
+          },
+          wrongRecord,
+          {
+            pubkey: provider.wallet.publicKey,
+            isSigner: false,
+            isWritable: true,
+          },
+        ])
+        .rpc()
+    );
+  });
+});
@@ -1,68 +1,82 @@
 import { assert } from 'chai';
+import { CpiEventParser } from 'fakenet-signer';
 import { testSetup } from '../test-utils/testSetup.js';
+import {
+  confirmTransaction,
+  responseRecordAccounts,
+} from '../test-utils/utils.js';
 import type { PublicKey } from '@solana/web3.js';
 
 interface SignatureErrorEvent {
   requestId: number[];
   responder: PublicKey;
-  error: string;
+  code: Record<string, object>;
+  detail: string | null;
 }
 
 describe('Respond Error tests', () => {
-  const { provider, program } = testSetup();
+  const { provider, connection, program } = testSetup();
+
+  const getErrorEvents = async (
+    txSignature: string
+  ): Promise<SignatureErrorEvent[]> => {
+    await confirmTransaction(connection, txSignature);
+    const events = await CpiEventParser.parseCpiEvents(
+      connection,
+      txSignature,
+      program.programId.toString(),
+      program
+    );
+    return events
+      .filter((e) => e.name === 'signatureErrorEvent')
+      .map((e) => e.data as unknown as SignatureErrorEvent);
+  };
 
   it('Can respond with single error', async () => {
     const requestId = Array.from({ length: 32 }, (_, i) => i % 256);
 
     const errorResponse = {
       requestId,
-      errorMessage: 'Test error message',
+      code: { invalidTransaction: {} },
+      detail: 'Test error message',
     };
 
-    let errorEventReceived = false;
-    let capturedEvent: SignatureErrorEvent | null = null;
-
-    const listener = program.addEventListener(
-      'signatureErrorEvent',
-      (event: SignatureErrorEvent) => {
-        errorEventReceived = true;
-        capturedEvent = event;
-      }
-    );
-
-    try {
-      const tx = await program.methods
-        .respondError([errorResponse])
-        .accounts({
-          responder: provider.wallet.publicKey,
-        })
-        .rpc();
+    const tx = await program.methods
+      .respondError([errorResponse])
+      .accounts({
+        responder: provider.wallet.publicKey,
+      })
+      .remainingAccounts(
+        responseRecordAccounts(program.programId, [requestId])
+      )
+      .rpc();
 
-      await new Promise((resolve) => setTimeout(resolve, 1000));
+    assert.ok(tx, 'Transaction should succeed');
 
-      assert.ok(tx, 'Transaction should succeed');
+    const events = await getErrorEvents(tx);
+    assert.equal(events.length, 1, 'signatureErrorEvent should be emitted');
+    const capturedEvent = events[0];
 
-      assert.ok(errorEventReceived, 'signatureErrorEvent should be emitted');
-      assert.ok(capturedEvent, 'Event data should be captured');
-
-      assert.deepEqual(
-        Array.from(capturedEvent.requestId),
-        requestId,
-        'Request ID should match'
-      );
-      assert.equal(
-        capturedEvent.responder.toString(),
-        provider.wallet.publicKey.toString(),
-        'Responder should match'
-      );
-      assert.equal(
-        capturedEvent.error,
-        'Test error message',
-        'Error message should match'
-      );
-    } finally {
-      await program.removeEventListener(listener);
-    }
+    assert.deepEqual(
+      Array.from(capturedEvent.requestId),
+      requestId,
+      'Request ID should match'
+    );
+    assert.equal(
+      capturedEvent.responder.toString(),
+      provider.wallet.publicKey.toString(),
+      'Responder should match'
+    );
+    assert.deepEqual(
+      capturedEvent.code,
+      { invalidTransaction: {} },
+      'Error code should match'
+    );
+    assert.equal(
+      capturedEvent.detail,
+      'Test error message',
+      'Error detail should match'
+    );
   });
 
   it('Can respond with multiple errors', async () => {
@@ -72,160 +86,165 @@ describe('Respond Error tests', () => {
     const errorResponses = [
       {
         requestId: requestId1,
-        errorMessage: 'First error message',
+        code: { unsupportedChain: {} },
+        detail: 'First error message',
       },
       {
         requestId: requestId2,
-        errorMessage: 'Second error message',
+        code: { timeout: {} },
+        detail: 'Second error message',
       },
     ];
 
-    const capturedEvents: SignatureErrorEvent[] = [];
-
-    const listener = program.addEventListener(
-      'signatureErrorEvent',
-      (event: SignatureErrorEvent) => {
-        capturedEvents.push(event);
-      }
+    const tx = await program.methods
+      .respondError(errorResponses)
+      .accounts({
+        responder: provider.wallet.publicKey,
+      })
+      .remainingAccounts(
+        responseRecordAccounts(program.programId, [requestId1, requestId2])
+      )
+      .rpc();
+
+    assert.ok(tx, 'Transaction should succeed');
+
+    const capturedEvents = await getErrorEvents(tx);
+    assert.equal(
+      capturedEvents.length,
+      2,
+      'Two signatureErrorEvents should be emitted'
     );
 
-    try {
-      const tx = await program.methods
-        .respondError(errorResponses)
-        .accounts({
-          responder: provider.wallet.publicKey,
-        })
-        .rpc();
-
-      await new Promise((resolve) => setTimeout(resolve, 1500));
-
-      assert.ok(tx, 'Transaction should succeed');
-
-      assert.equal(
-        capturedEvents.length,
-        2,
-        'Two signatureErrorEvents should be emitted'
-      );
-
-      const event1 = capturedEvents.find(
-        (e) => Array.from(e.requestId).join(',') === requestId1.join(',')
-      );
-      assert.ok(event1, 'First error event should be found');
-      assert.equal(
-        event1.error,
-        'First error message',
-        'First error message should match'
-      );
-      assert.equal(
-        event1.responder.toString(),
-        provider.wallet.publicKey.toString(),
-        'First responder should match'
-      );
+    const event1 = capturedEvents.find(
+      (e) => Array.from(e.requestId).join(',') === requestId1.join(',')
+    );
+    assert.ok(event1, 'First error event should be found');
+    assert.deepEqual(
+      event1.code,
+      { unsupportedChain: {} },
+      'First error code should match'
+    );
+    assert.equal(
+      event1.detail,
+      'First error message',
+      'First error detail should match'
+    );
+    assert.equal(
+      event1.responder.toString(),
+      provider.wallet.publicKey.toString(),
+      'First responder should match'
+    );
 
-      const event2 = capturedEvents.find(
-        (e) => Array.from(e.requestId).join(',') === requestId2.join(',')
-      );
-      assert.ok(event2, 'Second error event should be found');
-      assert.equal(
-        event2.error,
-        'Second error message',
-        'Second error message should match'
-      );
-      assert.equal(
-        event2.responder.toString(),
-        provider.wallet.publicKey.toString(),
-        'Second responder should match'
-      );
-    } finally {
-      await program.removeEventListener(listener);
-    }
+    const event2 = capturedEvents.find(
+      (e) => Array.from(e.requestId).join(',') === requestId2.join(',')
+    );
+    assert.ok(event2, 'Second error event should be found');
+    assert.deepEqual(
+      event2.code,
+      { timeout: {} },
+      'Second error code should match'
+    );
+    assert.equal(
+      event2.detail,
+      'Second error message',
+      'Second error detail should match'
+    );
+    assert.equal(
+      event2.responder.toString(),
+      provider.wallet.publicKey.toString(),
+      'Second responder should match'
+    );
   });
 
-  it('Can respond with error containing special characters', async () => {
+  it('Can respond with error detail containing special characters', async () => {
     const requestId = Array.from({ length: 32 }, (_, i) => (i + 100) % 256);
 
     const errorResponse = {
       requestId,
-      errorMessage:
+      code: { other: {} },
+      detail:
         'Error with special chars: àáâãäå æç èéêë ìíîï ñ òóôõö ùúûü ý 🚨⚠️💥',
     };
 
-    let errorEventReceived = false;
-    let capturedEvent: SignatureErrorEvent | null = null;
-
-    const listener = program.addEventListener(
-      'signatureErrorEvent',
-      (event: SignatureErrorEvent) => {
-        errorEventReceived = true;
-        capturedEvent = event;
-      }
+    const tx = await program.methods
+      .respondError([errorResponse])
+      .accounts({
+        responder: provider.wallet.publicKey,
+      })
+      .remainingAccounts(
+        responseRecordAccounts(program.programId, [requestId])
+      )
+      .rpc();
+
+    assert.ok(tx, 'Transaction should succeed');
+
+    const events = await getErrorEvents(tx);
+    assert.equal(events.length, 1, 'signatureErrorEvent should be emitted');
+
+    assert.equal(
+      events[0].detail,
+      errorResponse.detail,
+      'Error detail with special characters should match'
     );
-
-    try {
-      const tx = await program.methods
-        .respondError([errorResponse])
-        .accounts({
-          responder: provider.wallet.publicKey,
-        })
-        .rpc();
-
-      await new Promise((resolve) => setTimeout(resolve, 1000));
-
-      assert.ok(tx, 'Transaction should succeed');
-
-      assert.ok(errorEventReceived, 'signatureErrorEvent should be emitted');
-      assert.ok(capturedEvent, 'Event data should be captured');
-
-      assert.equal(
-        capturedEvent.error,
-        errorResponse.errorMessage,
-        'Error message with special characters should match'
-      );
-    } finally {
-      await program.removeEventListener(listener);
-    }
   });
 
-  it('Can handle empty error message', async () => {
+  it('Can respond with an error code and no detail', async () => {
     const requestId = Array.from({ length: 32 }, (_, i) => (i + 200) % 256);
 
     const errorResponse = {
       requestId,
-      errorMessage: '',
+      code: { destinationReverted: {} },
+      detail: null,
     };
 
-    let errorEventReceived = false;
-    let capturedEvent: SignatureErrorEvent | null = null;
-
-    const listener = program.addEventListener(
-      'signatureErrorEvent',
-      (event: SignatureErrorEvent) => {
-        errorEventReceived = true;
-        capturedEvent = event;
-      }
+    const tx = await program.methods
+      .respondError([errorResponse])
+      .accounts({
+        responder: provider.wallet.publicKey,
+      })
+      .remainingAccounts(
+        responseRecordAccounts(program.programId, [requestId])
+      )
+      .rpc();
+
+    assert.ok(tx, 'Transaction should succeed');
+
+    const events = await getErrorEvents(tx);
+    assert.equal(events.length, 1, 'signatureErrorEvent should be emitted');
+
+    assert.deepEqual(
+      events[0].code,
+      { destinationReverted: {} },
+      'Error code should match'
     );
+    assert.isNull(events[0].detail, 'Error detail should be absent');
+  });
+
+  it('Rejects an error detail longer than the cap', async () => {
+    const requestId = Array.from({ length: 32 }, (_, i) => (i + 250) % 256);
 
     try {
-      const tx = await program.methods
-        .respondError([errorResponse])
+      await program.methods
+        .respondError([
+          {
+            requestId,
+            code: { deserializationFailed: {} },
+            detail: 'x'.repeat(257),
+          },
+        ])
         .accounts({
           responder: provider.wallet.publicKey,
         })
+        .remainingAccounts(
+          responseRecordAccounts(program.programId, [requestId])
+        )
         .rpc();
-
-      await new Promise((resolve) => setTimeout(resolve, 1000));
-
-      assert.ok(tx, 'Transaction should succeed');
-
-      assert.ok(errorEventReceived, 'signatureErrorEvent should be emitted');
-
-      assert.equal(
-        capturedEvent.error,
-        '',
-        'Empty error message should be handled correctly'
+      assert.fail('Should have thrown ErrorDetailTooLong');
+    } catch (error) {
+      assert.ok(
+        error.message.includes('ErrorDetailTooLong'),
+        `Expected ErrorDetailTooLong error, got: ${error.message}`
       );
-    } finally {
-      await program.removeEventListener(listener);
     }
   });
 });
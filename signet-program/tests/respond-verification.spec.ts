@@ -0,0 +1,118 @@
+import { assert } from 'chai';
+import * as anchor from '@coral-xyz/anchor';
+import { SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
+import { CryptoUtils } from 'fakenet-signer';
+import { contracts } from 'signet.js';
+import { testSetup } from '../test-utils/testSetup.js';
+import { testEnvConfig } from '../test-utils/testEnvConfig.js';
+import { createSignArgs, type SignArgs } from '../test-utils/signingUtils.js';
+import {
+  respondAccounts,
+  responseRecordPda,
+  signatureRequestPda,
+} from '../test-utils/utils.js';
+
+describe('Respond verification tests', () => {
+  const { provider, program } = testSetup();
+
+  const [programStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('program-state')],
+    program.programId
+  );
+
+  const requestIdOf = async (signArgs: SignArgs) => {
+    const { chainId } =
+      await program.account.programState.fetch(programStatePda);
+    const requestId = contracts.solana.getRequestIdRespond({
+      address: provider.wallet.publicKey.toString(),
+      payload: signArgs.payload,
+      path: signArgs.path,
+      keyVersion: signArgs.keyVersion,
+      chainId,
+      algo: signArgs.algo,
+      dest: signArgs.dest,
+      params: signArgs.params,
+    });
+    return Array.from(Buffer.from(requestId.slice(2), 'hex'));
+  };
+
+  // `sign` and `respond` go in one transaction so the fakenet signer cannot
+  // answer the request first.
+  const signAndRespond = async (
+    signArgs: SignArgs,
+    signature: Awaited<ReturnType<typeof CryptoUtils.signMessage>>
+  ) => {
+    const requestId = await requestIdOf(signArgs);
+
+    const signIx = await program.methods
+      .sign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        signatureRequest: signatureRequestPda(program.programId, requestId),
+        responseRecord: responseRecordPda(program.programId, requestId),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    return program.methods
+      .respond([requestId], [signature])
+      .accounts({
+        responder: provider.wallet.publicKey,
+      })
+      .remainingAccounts(
+        respondAccounts(
+          program.programId,
+          [requestId],
+          provider.wallet.publicKey
+        )
+      )
+      .preInstructions([signIx])
+      .rpc();
+  };
+
+  const derivedSigningKey = (signArgs: SignArgs) =>
+    CryptoUtils.deriveSigningKey(
+      signArgs.path,
+      provider.wallet.publicKey.toString(),
+      testEnvConfig.MPC_ROOT_KEY
+    );
+
+  it('Accepts a signature from the derived key', async () => {
+    const signArgs = createSignArgs('WALLET_TEST', `verify-${Date.now()}`);
+    const signature = await CryptoUtils.signMessage(
+      signArgs.payload,
+      await derivedSigningKey(signArgs)
+    );
+
+    const tx = await signAndRespond(signArgs, signature);
+    assert.ok(tx, 'Transaction should succeed');
+  });
+
+  it('Rejects a signature from a different key', async () => {
+    const signArgs = createSignArgs('WALLET_TEST', `forged-${Date.now()}`);
+    const signature = await CryptoUtils.signMessage(
+      signArgs.payload,
+      await CryptoUtils.deriveSigningKey(
+        'another-path',
+        provider.wallet.publicKey.toString(),
+        testEnvConfig.MPC_ROOT_KEY
+      )
+    );
+
+    try {
+      await signAndRespond(signArgs, signature);
+      assert.fail('Should have thrown InvalidSignature');
+    } catch (error) {
+      assert.ok(
+        error.message.includes('InvalidSignature'),
+        `Expected InvalidSignature error, got: ${error.message}`
+      );
+    }
+  });
+});
@@ -1,16 +1,25 @@
 import { assert } from 'chai';
 import * as anchor from '@coral-xyz/anchor';
 import type { Program } from '@coral-xyz/anchor';
+import { CpiEventParser, type SignatureRequestedEvent } from 'fakenet-signer';
 import type { ProxyTestCpi } from '../target/types/proxy_test_cpi';
+import { contracts } from 'signet.js';
 import { testSetup } from '../test-utils/testSetup.js';
+import { confirmTransaction } from '../test-utils/utils.js';
 import {
   createSignArgs,
   callProxySign,
+  signRequestAccounts,
   waitForSignatureResponse,
 } from '../test-utils/signingUtils.js';
 
 describe('Sign/Respond CPI tests', () => {
-  const { provider, program: signetProgram, signetSolContract } = testSetup();
+  const {
+    provider,
+    connection,
+    program: signetProgram,
+    signetSolContract,
+  } = testSetup();
 
   const proxyProgram = anchor.workspace.proxyTestCpi as Program<ProxyTestCpi>;
 
@@ -24,6 +33,7 @@ describe('Sign/Respond CPI tests', () => {
 
     const txSignature = await callProxySign(
       proxyProgram,
+      signetProgram,
       signArgs,
       provider.wallet.publicKey,
       eventAuthorityPda
@@ -39,6 +49,93 @@ describe('Sign/Respond CPI tests', () => {
     assert.ok(response.isValid, 'Signature should be valid');
   });
 
+  it('Records the top-level program in the request event', async () => {
+    const signArgs = createSignArgs('CPI_TEST', `caller-${Date.now()}`);
+
+    const txSignature = await callProxySign(
+      proxyProgram,
+      signetProgram,
+      signArgs,
+      provider.wallet.publicKey,
+      eventAuthorityPda
+    );
+    await confirmTransaction(connection, txSignature);
+
+    const events = await CpiEventParser.parseCpiEvents(
+      connection,
+      txSignature,
+      signetProgram.programId.toString(),
+      signetProgram
+    );
+    const event = events.find((e) => e.name === 'signatureRequestedEvent')
+      ?.data as unknown as SignatureRequestedEvent;
+
+    assert.ok(event, 'signatureRequestedEvent should be emitted');
+    assert.ok(event.sender.equals(provider.wallet.publicKey));
+    assert.ok(event.topLevelProgram?.equals(proxyProgram.programId));
+  });
+
+  it('Can request signatures as a program-owned PDA', async () => {
+    const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('vault')],
+      proxyProgram.programId
+    );
+    const signArgs = createSignArgs('CPI_TEST', `vault-${Date.now()}`);
+
+    const txSignature = await proxyProgram.methods
+      .callSignAsVault(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        feePayer: provider.wallet.publicKey,
+        ...(await signRequestAccounts(
+          signetProgram,
+          vaultPda,
+          signArgs
+        )),
+        eventAuthority: eventAuthorityPda,
+      })
+      .rpc();
+    await confirmTransaction(connection, txSignature);
+
+    const events = await CpiEventParser.parseCpiEvents(
+      connection,
+      txSignature,
+      signetProgram.programId.toString(),
+      signetProgram
+    );
+    const event = events.find((e) => e.name === 'signatureRequestedEvent')
+      ?.data as unknown as SignatureRequestedEvent;
+    assert.ok(event.sender.equals(vaultPda), 'The vault should be the sender');
+    assert.ok(event.feePayer?.equals(provider.wallet.publicKey));
+
+    // `respond` verifies the signature against the vault's derived key.
+    const requestId = contracts.solana.getRequestIdRespond({
+      address: vaultPda.toString(),
+      payload: signArgs.payload,
+      path: signArgs.path,
+      keyVersion: signArgs.keyVersion,
+      chainId: event.chainId,
+      algo: signArgs.algo,
+      dest: signArgs.dest,
+      params: signArgs.params,
+    });
+    const response = await signetSolContract.waitForEvent({
+      eventName: 'signatureRespondedEvent',
+      requestId,
+      signer: signetProgram.programId,
+      afterSignature: txSignature,
+      timeoutMs: 30000,
+      backfillIntervalMs: 1000,
+    });
+    assert.ok(response, 'The vault request should be answered');
+  });
+
   it('Can handle multiple concurrent CPI calls', async () => {
     const signArgs1 = createSignArgs('CONCURRENT_TEST', '1', 1);
     const signArgs2 = createSignArgs('CONCURRENT_TEST', '2', 2);
@@ -47,6 +144,7 @@ describe('Sign/Respond CPI tests', () => {
       (async () => {
         const tx1 = await callProxySign(
           proxyProgram,
+          signetProgram,
           signArgs1,
           provider.wallet.publicKey,
           eventAuthorityPda
@@ -61,6 +159,7 @@ describe('Sign/Respond CPI tests', () => {
       (async () => {
         const tx2 = await callProxySign(
           proxyProgram,
+          signetProgram,
           signArgs2,
           provider.wallet.publicKey,
           eventAuthorityPda
@@ -1,13 +1,16 @@
 import { assert } from 'chai';
+import { CpiEventParser, type SignatureRequestedEvent } from 'fakenet-signer';
 import { testSetup } from '../test-utils/testSetup.js';
+import { confirmTransaction } from '../test-utils/utils.js';
 import {
   createSignArgs,
   callDirectSign,
+  signRequestAccounts,
   waitForSignatureResponse,
 } from '../test-utils/signingUtils.js';
 
 describe('Sign/Respond wallet tests', () => {
-  const { program, signetSolContract } = testSetup();
+  const { connection, program, signetSolContract } = testSetup();
 
   it('Can request a signature', async () => {
     const signArgs = createSignArgs('WALLET_TEST', 'wallet');
@@ -22,4 +25,52 @@ describe('Sign/Respond wallet tests', () => {
 
     assert.ok(response.isValid, 'Signature should be valid');
   });
+
+  const requestedEvent = async (txSignature: string) => {
+    await confirmTransaction(connection, txSignature);
+    const events = await CpiEventParser.parseCpiEvents(
+      connection,
+      txSignature,
+      program.programId.toString(),
+      program
+    );
+    return events.find((e) => e.name === 'signatureRequestedEvent')
+      ?.data as unknown as SignatureRequestedEvent;
+  };
+
+  it('Records no top-level program for direct requests', async () => {
+    const signArgs = createSignArgs('WALLET_TEST', `caller-${Date.now()}`);
+
+    const event = await requestedEvent(await callDirectSign(program, signArgs));
+
+    assert.ok(event, 'signatureRequestedEvent should be emitted');
+    assert.isNull(event.topLevelProgram);
+  });
+
+  it('Accepts requests without the instructions sysvar', async () => {
+    const signArgs = createSignArgs('WALLET_TEST', `no-sysvar-${Date.now()}`);
+
+    const txSignature = await program.methods
+      .sign(
+        signArgs.payload,
+        signArgs.keyVersion,
+        signArgs.path,
+        signArgs.algo,
+        signArgs.dest,
+        signArgs.params
+      )
+      .accountsPartial({
+        ...(await signRequestAccounts(
+          program,
+          program.provider.publicKey!,
+          signArgs
+        )),
+        instructions: null,
+      })
+      .rpc();
+    const event = await requestedEvent(txSignature);
+
+    assert.ok(event, 'signatureRequestedEvent should be emitted');
+    assert.isNull(event.topLevelProgram);
+  });
 });